@@ -0,0 +1,126 @@
+//! Per-access cost of `MemoryMapper` as the number of attached devices grows
+//!
+//! ```text
+//! cargo bench --bench memory_mapper
+//! ```
+//!
+//! Every device is a 256-byte RAM. Sequential accesses sweep the address space
+//! in order, so nearly all of them hit the device used last; random accesses
+//! land on a different device almost every time. A linear scan over the same
+//! devices, the mapper's former lookup, is measured alongside for comparison.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use tiny_computers::core::memory::{
+    Addressing, MappedDevice, MemoryBus, MemoryDevice, MemoryError, MemoryMapper, Ram,
+};
+
+/// Bytes in every benchmarked device
+const DEVICE_SIZE: u32 = 0x100;
+/// Accesses timed per measurement
+const ACCESSES: usize = 1 << 20;
+/// Device counts to measure
+const DEVICE_COUNTS: [u32; 6] = [1, 4, 16, 64, 256, 1024];
+
+type Device = MappedDevice<u32, u8, MemoryError>;
+
+fn ram() -> Box<Ram<u32, u8>> {
+    Box::new(Ram::new(DEVICE_SIZE as usize))
+}
+
+fn mapper(count: u32) -> MemoryMapper<u32, u8, MemoryError> {
+    let mut mapper = MemoryMapper::new();
+    // Attach in reverse to show the order of attachment does not matter
+    for index in (0..count).rev() {
+        let start = index * DEVICE_SIZE;
+        mapper
+            .attach_device_with(
+                start,
+                start + DEVICE_SIZE - 1,
+                false,
+                Addressing::Relative,
+                ram(),
+            )
+            .unwrap();
+    }
+    mapper
+}
+
+fn devices(count: u32) -> Vec<Device> {
+    (0..count)
+        .map(|index| {
+            let start = index * DEVICE_SIZE;
+            MappedDevice::new(start, start + DEVICE_SIZE - 1, false, ram())
+                .with_addressing(Addressing::Relative)
+        })
+        .collect()
+}
+
+/// Reads through the devices the way the mapper used to, first match wins
+fn linear_read(devices: &[Device], address: u32) -> Result<u8, MemoryError> {
+    for device in devices {
+        if address >= device.start_addr() && address <= device.end_addr() {
+            return device.read(address);
+        }
+    }
+    Err(MemoryError::AddressOutOfBounds)
+}
+
+fn sequential(count: u32) -> Vec<u32> {
+    let span = count * DEVICE_SIZE;
+    (0..ACCESSES as u32).map(|index| index % span).collect()
+}
+
+/// xorshift32, so runs are repeatable without a dependency
+fn random(count: u32) -> Vec<u32> {
+    let span = count * DEVICE_SIZE;
+    let mut state = 0x2545_F491u32;
+    (0..ACCESSES)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state % span
+        })
+        .collect()
+}
+
+/// Returns the fastest of a few runs of `read` over `addresses`, in
+/// nanoseconds per access
+fn measure(addresses: &[u32], mut read: impl FnMut(u32) -> u8) -> f64 {
+    let mut best = Duration::MAX;
+    for _ in 0..5 {
+        let start = Instant::now();
+        let mut sum = 0u8;
+        for &address in addresses {
+            sum = sum.wrapping_add(read(black_box(address)));
+        }
+        black_box(sum);
+        best = best.min(start.elapsed());
+    }
+    best.as_nanos() as f64 / addresses.len() as f64
+}
+
+fn main() {
+    println!(
+        "{:>8}  {:>12}  {:>12}  {:>12}  {:>12}",
+        "devices", "mapper seq", "mapper rand", "linear seq", "linear rand"
+    );
+    for count in DEVICE_COUNTS {
+        let mapper = mapper(count);
+        let devices = devices(count);
+        let (sequential, random) = (sequential(count), random(count));
+
+        let read = |address| mapper.read(address).unwrap();
+        let linear = |address| linear_read(&devices, address).unwrap();
+        println!(
+            "{:>8}  {:>9.2} ns  {:>9.2} ns  {:>9.2} ns  {:>9.2} ns",
+            count,
+            measure(&sequential, read),
+            measure(&random, read),
+            measure(&sequential, linear),
+            measure(&random, linear),
+        );
+    }
+}
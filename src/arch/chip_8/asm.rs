@@ -0,0 +1,690 @@
+//! CHIP-8 assembler for Octo-compatible source
+//!
+//! Supports the statements [`mnemonic`](super::mnemonic) produces plus:
+//! `: name` labels, `:const name value`, `:include "file"`, `db`/`dw` data
+//! directives taking the rest of their line, bare numbers as bytes and bare
+//! names as subroutine calls. Comments start with `#`. Numbers may be
+//! decimal, `0x` hex or `0b` binary, and names, labels and constants alike,
+//! may be used before they are defined.
+
+use super::{Chip8Inst, PROGRAM_START};
+use crate::core::isa::InstructionCodec;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Includes nested deeper than this are assumed to be recursive
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// An assembly error at a position in the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chip8AsmError {
+    /// Source file the error is in, `None` for the top-level source
+    pub file: Option<String>,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for Chip8AsmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for Chip8AsmError {}
+
+/// Assembles `source` into a ROM image to be loaded at [`PROGRAM_START`]
+///
+/// Includes are resolved relative to the current directory; use
+/// [`Chip8Assembler`] to control that.
+pub fn assemble(source: &str) -> Result<Vec<u8>, Chip8AsmError> {
+    Chip8Assembler::new().assemble(source)
+}
+
+/// Assembler with configurable `:include` resolution
+///
+/// Included names are looked up among sources registered with
+/// [`with_source`](Self::with_source) first, then as files in the include
+/// directory.
+#[derive(Debug, Clone, Default)]
+pub struct Chip8Assembler {
+    sources: HashMap<String, String>,
+    include_dir: Option<PathBuf>,
+}
+
+impl Chip8Assembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `source` available to `:include "name"` without touching disk
+    pub fn with_source(mut self, name: impl Into<String>, source: impl Into<String>) -> Self {
+        self.sources.insert(name.into(), source.into());
+        self
+    }
+
+    /// Resolves included files relative to `dir`
+    pub fn with_include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dir = Some(dir.into());
+        self
+    }
+
+    /// Assembles `source`, resolving included files in the include directory,
+    /// or the current directory if none was set
+    pub fn assemble(&self, source: &str) -> Result<Vec<u8>, Chip8AsmError> {
+        let dir = self
+            .include_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."));
+        let tokens = self.tokenize(source, None, &dir, &mut Vec::new())?;
+        Parser::new(&tokens).assemble()
+    }
+
+    /// Assembles the file at `path`, resolving includes next to it unless an
+    /// include directory was set
+    pub fn assemble_file(&self, path: impl AsRef<Path>) -> Result<Vec<u8>, Chip8AsmError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let source = std::fs::read_to_string(path).map_err(|error| Chip8AsmError {
+            file: Some(name.clone()),
+            line: 0,
+            column: 0,
+            message: error.to_string(),
+        })?;
+        let dir = match &self.include_dir {
+            Some(dir) => dir.clone(),
+            None => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        let tokens = self.tokenize(&source, Some(name.into()), &dir, &mut Vec::new())?;
+        Parser::new(&tokens).assemble()
+    }
+
+    /// Splits `source` into tokens, splicing in the tokens of included files
+    fn tokenize(
+        &self,
+        source: &str,
+        file: Option<Rc<str>>,
+        dir: &Path,
+        includes: &mut Vec<String>,
+    ) -> Result<Vec<Token>, Chip8AsmError> {
+        let mut tokens = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let mut chars = line.char_indices().peekable();
+            while let Some(&(start, c)) = chars.peek() {
+                if c == '#' {
+                    break;
+                }
+                if c.is_whitespace() {
+                    chars.next();
+                    continue;
+                }
+
+                let quoted = c == '"';
+                let mut end = line.len();
+                chars.next();
+                while let Some(&(offset, c)) = chars.peek() {
+                    if quoted && c == '"' {
+                        chars.next();
+                        end = offset + 1;
+                        break;
+                    }
+                    if !quoted && c.is_whitespace() {
+                        end = offset;
+                        break;
+                    }
+                    chars.next();
+                }
+
+                tokens.push(Token {
+                    text: line[start..end].to_string(),
+                    file: file.clone(),
+                    line: index + 1,
+                    column: line[..start].chars().count() + 1,
+                });
+            }
+        }
+
+        let mut spliced = Vec::with_capacity(tokens.len());
+        let mut tokens = tokens.into_iter();
+        while let Some(token) = tokens.next() {
+            if token.text != ":include" {
+                spliced.push(token);
+                continue;
+            }
+
+            let Some(name) = tokens.next() else {
+                return Err(token.error("expected a file name after :include"));
+            };
+            let Some(path) = name
+                .text
+                .strip_prefix('"')
+                .and_then(|text| text.strip_suffix('"'))
+            else {
+                return Err(name.error("include file names must be quoted"));
+            };
+            if includes.iter().any(|included| included == path)
+                || includes.len() >= MAX_INCLUDE_DEPTH
+            {
+                return Err(name.error(format!("recursive include of \"{}\"", path)));
+            }
+
+            let (source, included_dir) = match self.sources.get(path) {
+                Some(source) => (source.clone(), dir.to_path_buf()),
+                None => {
+                    let file = dir.join(path);
+                    let source = std::fs::read_to_string(&file).map_err(|error| {
+                        token.error(format!("cannot include \"{}\": {}", path, error))
+                    })?;
+                    let parent = file.parent().map(Path::to_path_buf).unwrap_or_default();
+                    (source, parent)
+                }
+            };
+
+            includes.push(path.to_string());
+            spliced.extend(self.tokenize(&source, Some(path.into()), &included_dir, includes)?);
+            includes.pop();
+        }
+
+        Ok(spliced)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    file: Option<Rc<str>>,
+    line: usize,
+    column: usize,
+}
+
+impl Token {
+    fn error(&self, message: impl Into<String>) -> Chip8AsmError {
+        Chip8AsmError {
+            file: self.file.as_deref().map(str::to_string),
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+
+    fn register(&self) -> Option<u16> {
+        let digit = self.text.strip_prefix(['v', 'V'])?;
+        if digit.len() != 1 {
+            return None;
+        }
+        u16::from_str_radix(digit, 16).ok()
+    }
+
+    fn number(&self) -> Option<i64> {
+        let (negative, text) = match self.text.strip_prefix('-') {
+            Some(text) => (true, text),
+            None => (false, self.text.as_str()),
+        };
+        let value = if let Some(hex) = text.strip_prefix("0x") {
+            i64::from_str_radix(hex, 16).ok()?
+        } else if let Some(binary) = text.strip_prefix("0b") {
+            i64::from_str_radix(binary, 2).ok()?
+        } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+            text.parse().ok()?
+        } else {
+            return None;
+        };
+        Some(if negative { -value } else { value })
+    }
+
+    fn on_line_of(&self, other: &Token) -> bool {
+        self.line == other.line && self.file == other.file
+    }
+}
+
+/// Where an operand goes in the instruction word
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    /// 12-bit address in the low bits
+    Nnn,
+    /// Byte in the low bits
+    Nn,
+    /// Byte in the low bits, negated (for `vX -= NN`)
+    NegatedNn,
+    /// Nibble in the low bits
+    N,
+    /// Nibble in the X position
+    X,
+}
+
+impl Field {
+    fn place(self, value: i64) -> Option<u16> {
+        let byte = |value: i64| (-128..=255).contains(&value).then_some(value as u8 as u16);
+        match self {
+            Self::Nnn => (0..=0xFFF).contains(&value).then_some(value as u16),
+            Self::Nn => byte(value),
+            Self::NegatedNn => byte(value).map(|value| (value as u8).wrapping_neg() as u16),
+            Self::N => (0..=0xF).contains(&value).then_some(value as u16),
+            Self::X => (0..=0xF).contains(&value).then_some((value as u16) << 8),
+        }
+    }
+
+    fn range(self) -> &'static str {
+        match self {
+            Self::Nnn => "0x000..=0xfff",
+            Self::Nn | Self::NegatedNn => "-128..=255",
+            Self::N | Self::X => "0..=15",
+        }
+    }
+}
+
+/// Output of one statement; operands are token indices resolved once all
+/// labels are known
+#[derive(Debug)]
+enum Emit {
+    Word {
+        base: u16,
+        operand: Option<(usize, Field)>,
+    },
+    Long(usize),
+    Data {
+        values: Vec<usize>,
+        wide: bool,
+    },
+}
+
+impl Emit {
+    fn size(&self) -> usize {
+        match self {
+            Self::Word { .. } => 2,
+            Self::Long(_) => 4,
+            Self::Data { values, wide } => values.len() * if *wide { 2 } else { 1 },
+        }
+    }
+}
+
+/// What a name stands for
+#[derive(Debug, Clone, Copy)]
+enum Symbol {
+    /// A label at this address
+    Label(i64),
+    /// A `:const`, valued by the token at this index once all names are known
+    Const(usize),
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    address: usize,
+    symbols: HashMap<&'a str, Symbol>,
+    /// Token indices of the `:const` values, in source order
+    consts: Vec<usize>,
+    output: Vec<Emit>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            address: PROGRAM_START as usize,
+            symbols: HashMap::new(),
+            consts: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    fn assemble(mut self) -> Result<Vec<u8>, Chip8AsmError> {
+        while self.position < self.tokens.len() {
+            self.statement()?;
+        }
+        // Report undefined and circular constants even where they go unused
+        for &index in &self.consts {
+            self.value(index)?;
+        }
+
+        let mut rom = Vec::with_capacity(self.address - PROGRAM_START as usize);
+        for emit in &self.output {
+            match emit {
+                Emit::Word { base, operand } => {
+                    let mut word = *base;
+                    if let Some((index, field)) = *operand {
+                        let token = &self.tokens[index];
+                        let value = self.value(index)?;
+                        word |= field.place(value).ok_or_else(|| {
+                            token.error(format!("{} is out of range {}", value, field.range()))
+                        })?;
+                    }
+                    rom.extend(Chip8Inst::new(word).encode());
+                }
+                Emit::Long(index) => {
+                    let value = self.value(*index)?;
+                    if !(0..=0xFFFF).contains(&value) {
+                        return Err(self.tokens[*index]
+                            .error(format!("{} is out of range 0x0000..=0xffff", value)));
+                    }
+                    rom.extend(Chip8Inst::new(0xF000).encode());
+                    rom.extend((value as u16).to_be_bytes());
+                }
+                Emit::Data { values, wide } => {
+                    for &index in values {
+                        let value = self.value(index)?;
+                        if *wide {
+                            if !(-0x8000..=0xFFFF).contains(&value) {
+                                return Err(self.tokens[index]
+                                    .error(format!("{} does not fit in a word", value)));
+                            }
+                            rom.extend((value as u16).to_be_bytes());
+                        } else {
+                            if !(-0x80..=0xFF).contains(&value) {
+                                return Err(self.tokens[index]
+                                    .error(format!("{} does not fit in a byte", value)));
+                            }
+                            rom.push(value as u8);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(rom)
+    }
+
+    /// Resolves the number or name at token `index`
+    fn value(&self, index: usize) -> Result<i64, Chip8AsmError> {
+        let token = &self.tokens[index];
+        let mut current = token;
+        // A chain of constants longer than the number of names loops
+        for _ in 0..=self.symbols.len() {
+            if let Some(value) = current.number() {
+                return Ok(value);
+            }
+            match self.symbols.get(current.text.as_str()) {
+                Some(&Symbol::Label(address)) => return Ok(address),
+                Some(&Symbol::Const(value)) => current = &self.tokens[value],
+                None => return Err(current.error(format!("undefined name `{}`", current.text))),
+            }
+        }
+        Err(token.error(format!("`{}` is defined in terms of itself", token.text)))
+    }
+
+    fn next(&mut self) -> Result<usize, Chip8AsmError> {
+        if self.position >= self.tokens.len() {
+            let last = self.tokens.last().expect("statements start at a token");
+            return Err(last.error("unexpected end of input"));
+        }
+        self.position += 1;
+        Ok(self.position - 1)
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), Chip8AsmError> {
+        let index = self.next()?;
+        let token = &self.tokens[index];
+        if token.text != text {
+            return Err(token.error(format!("expected `{}`, found `{}`", text, token.text)));
+        }
+        Ok(())
+    }
+
+    fn register(&mut self) -> Result<u16, Chip8AsmError> {
+        let index = self.next()?;
+        let token = &self.tokens[index];
+        token
+            .register()
+            .ok_or_else(|| token.error(format!("expected a register, found `{}`", token.text)))
+    }
+
+    fn define(&mut self, index: usize, value: Symbol) -> Result<(), Chip8AsmError> {
+        let token = &self.tokens[index];
+        if token.number().is_some() || token.register().is_some() || token.text.starts_with('"') {
+            return Err(token.error(format!("`{}` is not a valid name", token.text)));
+        }
+        if self.symbols.insert(&token.text, value).is_some() {
+            return Err(token.error(format!("`{}` is already defined", token.text)));
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, at: usize, emit: Emit) -> Result<(), Chip8AsmError> {
+        self.address += emit.size();
+        if self.address > 0x10000 {
+            return Err(self.tokens[at].error("program does not fit in memory"));
+        }
+        self.output.push(emit);
+        Ok(())
+    }
+
+    fn word(&mut self, at: usize, base: u16) -> Result<(), Chip8AsmError> {
+        self.emit(
+            at,
+            Emit::Word {
+                base,
+                operand: None,
+            },
+        )
+    }
+
+    fn word_with(&mut self, at: usize, base: u16, field: Field) -> Result<(), Chip8AsmError> {
+        let operand = self.next()?;
+        self.emit(
+            at,
+            Emit::Word {
+                base,
+                operand: Some((operand, field)),
+            },
+        )
+    }
+
+    fn statement(&mut self) -> Result<(), Chip8AsmError> {
+        let at = self.next()?;
+        let token = &self.tokens[at];
+
+        if let Some(x) = token.register() {
+            return self.register_statement(at, x);
+        }
+
+        match token.text.as_str() {
+            ":" => {
+                let name = self.next()?;
+                self.define(name, Symbol::Label(self.address as i64))
+            }
+            ":const" => {
+                let name = self.next()?;
+                let value = self.next()?;
+                self.consts.push(value);
+                self.define(name, Symbol::Const(value))
+            }
+            ":call" => self.word_with(at, 0x2000, Field::Nnn),
+            "jump" => self.word_with(at, 0x1000, Field::Nnn),
+            "jump0" => self.word_with(at, 0xB000, Field::Nnn),
+            "clear" => self.word(at, 0x00E0),
+            "return" => self.word(at, 0x00EE),
+            "scroll-down" => self.word_with(at, 0x00C0, Field::N),
+            "scroll-up" => self.word_with(at, 0x00D0, Field::N),
+            "scroll-right" => self.word(at, 0x00FB),
+            "scroll-left" => self.word(at, 0x00FC),
+            "exit" => self.word(at, 0x00FD),
+            "lores" => self.word(at, 0x00FE),
+            "hires" => self.word(at, 0x00FF),
+            "audio" => self.word(at, 0xF002),
+            "plane" => self.word_with(at, 0xF001, Field::X),
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                self.word_with(at, 0xD000 | x << 8 | y << 4, Field::N)
+            }
+            "bcd" => {
+                let x = self.register()?;
+                self.word(at, 0xF033 | x << 8)
+            }
+            "saveflags" => {
+                let x = self.register()?;
+                self.word(at, 0xF075 | x << 8)
+            }
+            "loadflags" => {
+                let x = self.register()?;
+                self.word(at, 0xF085 | x << 8)
+            }
+            "save" | "load" => {
+                let save = token.text == "save";
+                let x = self.register()?;
+                let ranged = self
+                    .tokens
+                    .get(self.position)
+                    .is_some_and(|next| next.text == "-");
+                if ranged {
+                    self.position += 1;
+                    let y = self.register()?;
+                    let base = if save { 0x5002 } else { 0x5003 };
+                    self.word(at, base | x << 8 | y << 4)
+                } else {
+                    let base = if save { 0xF055 } else { 0xF065 };
+                    self.word(at, base | x << 8)
+                }
+            }
+            "delay" | "buzzer" | "pitch" => {
+                let base = match token.text.as_str() {
+                    "delay" => 0xF015,
+                    "buzzer" => 0xF018,
+                    _ => 0xF03A,
+                };
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.word(at, base | x << 8)
+            }
+            "i" => self.index_statement(at),
+            "if" => self.if_statement(at),
+            "db" | "dw" => {
+                let wide = token.text == "dw";
+                let mut values = Vec::new();
+                while self
+                    .tokens
+                    .get(self.position)
+                    .is_some_and(|next| next.on_line_of(token))
+                {
+                    values.push(self.position);
+                    self.position += 1;
+                }
+                self.emit(at, Emit::Data { values, wide })
+            }
+            _ if token.number().is_some() => self.emit(
+                at,
+                Emit::Data {
+                    values: vec![at],
+                    wide: false,
+                },
+            ),
+            text if text.starts_with([':', '"']) => {
+                Err(token.error(format!("unknown directive `{}`", text)))
+            }
+            // Octo calls a subroutine by naming it
+            _ => self.emit(
+                at,
+                Emit::Word {
+                    base: 0x2000,
+                    operand: Some((at, Field::Nnn)),
+                },
+            ),
+        }
+    }
+
+    fn register_statement(&mut self, at: usize, x: u16) -> Result<(), Chip8AsmError> {
+        let operator = self.next()?;
+        let operator = &self.tokens[operator];
+        let rhs = self.tokens.get(self.position);
+        let y = rhs.and_then(Token::register);
+        let x = x << 8;
+
+        let alu = |n: u16| 0x8000 | x | y.unwrap_or(0) << 4 | n;
+        match (operator.text.as_str(), y) {
+            (":=", Some(_)) => self.register_operand(at, alu(0x0)),
+            ("|=", Some(_)) => self.register_operand(at, alu(0x1)),
+            ("&=", Some(_)) => self.register_operand(at, alu(0x2)),
+            ("^=", Some(_)) => self.register_operand(at, alu(0x3)),
+            ("+=", Some(_)) => self.register_operand(at, alu(0x4)),
+            ("-=", Some(_)) => self.register_operand(at, alu(0x5)),
+            (">>=", Some(_)) => self.register_operand(at, alu(0x6)),
+            ("=-", Some(_)) => self.register_operand(at, alu(0x7)),
+            ("<<=", Some(_)) => self.register_operand(at, alu(0xE)),
+            (":=", None) => match rhs.map(|rhs| rhs.text.as_str()) {
+                Some("random") => {
+                    self.position += 1;
+                    self.word_with(at, 0xC000 | x, Field::Nn)
+                }
+                Some("delay") => self.register_operand(at, 0xF007 | x),
+                Some("key") => self.register_operand(at, 0xF00A | x),
+                _ => self.word_with(at, 0x6000 | x, Field::Nn),
+            },
+            ("+=", None) => self.word_with(at, 0x7000 | x, Field::Nn),
+            ("-=", None) => self.word_with(at, 0x7000 | x, Field::NegatedNn),
+            _ => Err(operator.error(format!(
+                "unsupported register operation `{}`",
+                operator.text
+            ))),
+        }
+    }
+
+    /// Emits `word` after consuming the single operand token it was built from
+    fn register_operand(&mut self, at: usize, word: u16) -> Result<(), Chip8AsmError> {
+        self.position += 1;
+        self.word(at, word)
+    }
+
+    fn index_statement(&mut self, at: usize) -> Result<(), Chip8AsmError> {
+        let operator = self.next()?;
+        match self.tokens[operator].text.as_str() {
+            ":=" => {
+                let rhs = self.tokens.get(self.position).map(|rhs| rhs.text.as_str());
+                match rhs {
+                    Some("hex") | Some("bighex") => {
+                        let base = if rhs == Some("hex") { 0xF029 } else { 0xF030 };
+                        self.position += 1;
+                        let x = self.register()?;
+                        self.word(at, base | x << 8)
+                    }
+                    Some("long") => {
+                        self.position += 1;
+                        let operand = self.next()?;
+                        self.emit(at, Emit::Long(operand))
+                    }
+                    _ => self.word_with(at, 0xA000, Field::Nnn),
+                }
+            }
+            "+=" => {
+                let x = self.register()?;
+                self.word(at, 0xF01E | x << 8)
+            }
+            _ => {
+                let token = &self.tokens[operator];
+                Err(token.error(format!("unsupported operation on i `{}`", token.text)))
+            }
+        }
+    }
+
+    /// Handles `if vX <condition> then`, which skips the next statement when
+    /// the condition is false
+    fn if_statement(&mut self, at: usize) -> Result<(), Chip8AsmError> {
+        let x = self.register()? << 8;
+        let condition = self.next()?;
+        let condition = &self.tokens[condition];
+        let result = match condition.text.as_str() {
+            "key" => self.word(at, 0xE0A1 | x),
+            "-key" => self.word(at, 0xE09E | x),
+            "==" | "!=" => {
+                let equal = condition.text == "==";
+                let rhs = self.tokens.get(self.position).and_then(Token::register);
+                match (rhs, equal) {
+                    (Some(y), true) => self.register_operand(at, 0x9000 | x | y << 4),
+                    (Some(y), false) => self.register_operand(at, 0x5000 | x | y << 4),
+                    (None, true) => self.word_with(at, 0x4000 | x, Field::Nn),
+                    (None, false) => self.word_with(at, 0x3000 | x, Field::Nn),
+                }
+            }
+            _ => return Err(condition.error(format!("unsupported condition `{}`", condition.text))),
+        };
+        result?;
+        self.expect("then")
+    }
+}
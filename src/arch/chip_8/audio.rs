@@ -0,0 +1,163 @@
+use super::{AUDIO_PATTERN_SIZE, DEFAULT_PITCH, TIMER_FREQUENCY};
+use std::collections::VecDeque;
+use std::io::{Result as IoResult, Write};
+
+/// Sample rate used unless configured otherwise
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+/// Frequency of the plain CHIP-8 buzzer in Hz
+pub const DEFAULT_BEEP_FREQUENCY: f64 = 440.0;
+/// Peak sample value; a quarter of full scale keeps the square wave bearable
+pub const AMPLITUDE: i16 = i16::MAX / 4;
+
+/// Number of 1-bit samples in the XO-CHIP pattern buffer
+const PATTERN_BITS: usize = AUDIO_PATTERN_SIZE * 8;
+
+/// PCM sample source for the sound timer
+///
+/// The machine renders one frame of samples per timer tick: a square-wave
+/// beep while ST is non-zero, or on XO-CHIP the pattern buffer loaded by F002
+/// played back at `4000 * 2^((pitch - 64) / 48)` bits per second. Samples are
+/// signed 16-bit mono and queue up in a ring buffer holding one second of
+/// audio; when the host falls behind the oldest samples are dropped.
+#[derive(Debug, Clone)]
+pub struct Chip8Audio {
+    sample_rate: u32,
+    beep_frequency: f64,
+    samples: VecDeque<i16>,
+    capacity: usize,
+    /// Position in the waveform: beep periods, or pattern bits on XO-CHIP
+    phase: f64,
+    /// Sample-rate units carried over so fractional frame lengths add up
+    carry: u32,
+}
+
+impl Chip8Audio {
+    pub fn new(sample_rate: u32) -> Self {
+        let capacity = sample_rate as usize;
+        Self {
+            sample_rate,
+            beep_frequency: DEFAULT_BEEP_FREQUENCY,
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            phase: 0.0,
+            carry: 0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn beep_frequency(&self) -> f64 {
+        self.beep_frequency
+    }
+
+    pub fn set_beep_frequency(&mut self, frequency: f64) {
+        self.beep_frequency = frequency;
+    }
+
+    /// Returns the number of samples waiting to be pulled
+    pub fn available(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Moves up to `out.len()` of the oldest samples into `out`
+    ///
+    /// # Returns
+    /// The number of samples written
+    pub fn pop_samples(&mut self, out: &mut [i16]) -> usize {
+        let count = out.len().min(self.samples.len());
+        for (slot, sample) in out.iter_mut().zip(self.samples.drain(..count)) {
+            *slot = sample;
+        }
+        count
+    }
+
+    /// Removes and returns every queued sample
+    pub fn take_samples(&mut self) -> Vec<i16> {
+        self.samples.drain(..).collect()
+    }
+
+    /// Discards queued samples and restarts the waveform
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.phase = 0.0;
+        self.carry = 0;
+    }
+
+    /// Renders one 60 Hz frame of samples
+    ///
+    /// `pattern` is the XO-CHIP pattern buffer and pitch register, or `None`
+    /// to use the plain beep.
+    pub fn render_frame(
+        &mut self,
+        sound_timer: u8,
+        pattern: Option<(&[u8; AUDIO_PATTERN_SIZE], u8)>,
+    ) {
+        self.carry += self.sample_rate;
+        let count = self.carry / TIMER_FREQUENCY;
+        self.carry %= TIMER_FREQUENCY;
+
+        if sound_timer == 0 {
+            self.phase = 0.0;
+            (0..count).for_each(|_| self.push(0));
+            return;
+        }
+
+        let rate = match pattern {
+            Some((_, pitch)) => pattern_rate(pitch),
+            None => self.beep_frequency,
+        };
+        let step = rate / self.sample_rate as f64;
+        for _ in 0..count {
+            let high = match pattern {
+                Some((pattern, _)) => {
+                    let bit = self.phase as usize % PATTERN_BITS;
+                    pattern[bit / 8] & (0x80 >> (bit % 8)) != 0
+                }
+                None => self.phase.fract() < 0.5,
+            };
+            self.push(if high { AMPLITUDE } else { -AMPLITUDE });
+            self.phase = (self.phase + step) % PATTERN_BITS as f64;
+        }
+    }
+
+    fn push(&mut self, sample: i16) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+}
+
+impl Default for Chip8Audio {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
+    }
+}
+
+/// Returns the XO-CHIP pattern playback rate in bits per second for `pitch`
+pub fn pattern_rate(pitch: u8) -> f64 {
+    4000.0 * 2f64.powf((pitch as f64 - DEFAULT_PITCH as f64) / 48.0)
+}
+
+/// Writes `samples` as a 16-bit mono PCM WAV file
+pub fn write_wav(writer: &mut impl Write, sample_rate: u32, samples: &[i16]) -> IoResult<()> {
+    let data_size = (samples.len() * 2) as u32;
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?; // fmt chunk size
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&1u16.to_le_bytes())?; // mono
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * 2).to_le_bytes())?; // byte rate
+    writer.write_all(&2u16.to_le_bytes())?; // block align
+    writer.write_all(&16u16.to_le_bytes())?; // bits per sample
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+    Ok(())
+}
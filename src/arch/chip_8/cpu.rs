@@ -0,0 +1,610 @@
+use super::{
+    mnemonic, Chip8Error, Chip8Inst, Chip8InstructionSet, Chip8Memory, Chip8Quirks, Chip8State,
+    InMemoryRplFlags, RplFlagStore, AUDIO_PATTERN_SIZE, BIG_FONT, BIG_FONT_GLYPH_SIZE,
+    BIG_FONT_OFFSET, FLAG_REGISTER, FONT, FONT_BASE, FONT_DATA_SIZE, FONT_GLYPH_SIZE,
+    PROGRAM_START,
+};
+use crate::core::{
+    cpu::{Cpu, CpuError, CpuState, RegisterFile},
+    debug::{Debuggable, MemoryAccess},
+    isa::{Instruction, InstructionCodec, InstructionError, InstructionSet},
+    memory::{MemoryDevice, MemoryError},
+    savestate::{SaveStateError, Snapshot, StateReader, StateWriter},
+    trace::{register_changes, TraceAccess, TraceEntry, TraceHeader, TraceHook, TraceSink},
+};
+
+/// Registers reported in traces besides PC, in this order
+const TRACE_REGISTERS: [&str; 20] = [
+    "v0", "v1", "v2", "v3", "v4", "v5", "v6", "v7", "v8", "v9", "va", "vb", "vc", "vd", "ve", "vf",
+    "i", "sp", "dt", "st",
+];
+
+#[derive(Debug)]
+pub struct Chip8Cpu {
+    state: Chip8State,
+    instruction_set: Chip8InstructionSet,
+    quirks: Chip8Quirks,
+    rpl_flags: Box<dyn RplFlagStore>,
+    font_base: u16,
+    trace: TraceHook,
+}
+
+impl Chip8Cpu {
+    /// Creates a CPU using the quirk preset matching `instruction_set`
+    pub fn new(instruction_set: Chip8InstructionSet) -> Self {
+        Self::with_quirks(instruction_set, instruction_set.into())
+    }
+
+    /// Creates a CPU with an explicit quirk configuration
+    ///
+    /// The built-in fonts are installed at [`FONT_BASE`].
+    pub fn with_quirks(instruction_set: Chip8InstructionSet, quirks: Chip8Quirks) -> Self {
+        let mut cpu = Self {
+            state: Chip8State::with_memory(Chip8Memory::with_size(instruction_set.memory_size())),
+            instruction_set,
+            quirks,
+            rpl_flags: Box::new(InMemoryRplFlags::new()),
+            font_base: FONT_BASE,
+            trace: TraceHook::new(),
+        };
+        cpu.install_font(FONT_BASE)
+            .expect("default font base lies below the program start");
+        cpu
+    }
+
+    /// Returns the address of the small font used by FX29
+    ///
+    /// The big font used by FX30 follows it at [`BIG_FONT_OFFSET`].
+    pub fn font_base(&self) -> u16 {
+        self.font_base
+    }
+
+    /// Writes the small and big fonts to memory at `base` and points
+    /// FX29/FX30 at them
+    ///
+    /// # Returns
+    /// * `Err(Chip8Error::Memory(MemoryError::InvalidAddressRange))` - If the
+    ///   fonts would reach into the program area starting at [`PROGRAM_START`]
+    pub fn install_font(&mut self, base: u16) -> Result<(), Chip8Error> {
+        if base as usize + FONT_DATA_SIZE > PROGRAM_START as usize {
+            return Err(MemoryError::InvalidAddressRange.into());
+        }
+        let memory = self.state.memory_mut();
+        memory.write_slice(base, &FONT)?;
+        memory.write_slice(base + BIG_FONT_OFFSET, &BIG_FONT)?;
+        self.font_base = base;
+        Ok(())
+    }
+
+    /// Copies `rom` into memory at [`PROGRAM_START`] and resets the CPU
+    ///
+    /// The rest of the program area is cleared so nothing of a previously
+    /// loaded ROM survives.
+    ///
+    /// # Returns
+    /// * `Err(Chip8Error::RomTooLarge { .. })` - If `rom` does not fit between
+    ///   [`PROGRAM_START`] and the end of memory
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
+        let capacity = self.state.memory().size() - PROGRAM_START as usize;
+        if rom.len() > capacity {
+            return Err(Chip8Error::RomTooLarge {
+                size: rom.len(),
+                capacity,
+            });
+        }
+        let mut image = vec![0; capacity];
+        image[..rom.len()].copy_from_slice(rom);
+        self.state.memory_mut().write_slice(PROGRAM_START, &image)?;
+        self.reset()
+    }
+
+    /// Replaces the store backing the SUPER-CHIP RPL user flags (FX75/FX85)
+    pub fn set_rpl_flag_store(&mut self, store: Box<dyn RplFlagStore>) {
+        self.rpl_flags = store;
+    }
+
+    pub fn quirks(&self) -> &Chip8Quirks {
+        &self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Chip8Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Attaches a sink receiving a [`TraceEntry`] for every instruction
+    /// executed by [`step`](Cpu::step), or detaches it with `None`
+    ///
+    /// Returns the previously attached sink. Instructions that fail are not
+    /// traced.
+    pub fn set_trace_sink(
+        &mut self,
+        sink: Option<Box<dyn TraceSink>>,
+    ) -> Option<Box<dyn TraceSink>> {
+        self.trace.set_sink(sink)
+    }
+
+    /// Reads the instruction word at PC without advancing it
+    pub fn fetch(&self) -> Result<Chip8Inst, Chip8Error> {
+        let pc = self.state.pc();
+        let bytes = self.state.memory().peek_slice(pc, 2)?;
+        Chip8Inst::decode(bytes)
+    }
+
+    /// Executes a single decoded instruction
+    ///
+    /// PC must already point past `inst`.
+    fn execute(&mut self, inst: Chip8Inst) -> Result<u8, Chip8Error> {
+        let (x, y, n, nn, nnn) = (inst.x(), inst.y(), inst.n(), inst.nn(), inst.nnn());
+        let quirks = self.quirks;
+        let extended = self.instruction_set != Chip8InstructionSet::Chip8;
+        let xo = self.instruction_set == Chip8InstructionSet::XOChip;
+        let state = &mut self.state;
+        let vx = state.v(x);
+        let vy = state.v(y);
+
+        match (inst.opcode(), x, y, n) {
+            // 00E0 - CLS
+            (0x0, 0x0, 0xE, 0x0) => state.display_mut().clear(),
+            // 00EE - RET
+            (0x0, 0x0, 0xE, 0xE) => {
+                let address = state.pop()?;
+                state.set_program_counter(address)?;
+            }
+            // 00CN - SCD nibble (SUPER-CHIP)
+            (0x0, 0x0, 0xC, _) if extended => state.display_mut().scroll_down(n as usize),
+            // 00DN - SCU nibble (XO-CHIP)
+            (0x0, 0x0, 0xD, _) if xo => state.display_mut().scroll_up(n as usize),
+            // 00FB - SCR (SUPER-CHIP)
+            (0x0, 0x0, 0xF, 0xB) if extended => state.display_mut().scroll_right(4),
+            // 00FC - SCL (SUPER-CHIP)
+            (0x0, 0x0, 0xF, 0xC) if extended => state.display_mut().scroll_left(4),
+            // 00FD - EXIT (SUPER-CHIP)
+            (0x0, 0x0, 0xF, 0xD) if extended => state.halt(),
+            // 00FE - LOW (SUPER-CHIP)
+            (0x0, 0x0, 0xF, 0xE) if extended => state.display_mut().set_hires(false),
+            // 00FF - HIGH (SUPER-CHIP)
+            (0x0, 0x0, 0xF, 0xF) if extended => state.display_mut().set_hires(true),
+            // 0NNN - SYS addr, machine code routines are ignored
+            (0x0, ..) => {}
+            // 1NNN - JP addr
+            (0x1, ..) => state.set_program_counter(nnn)?,
+            // 2NNN - CALL addr
+            (0x2, ..) => {
+                state.push(state.pc())?;
+                state.set_program_counter(nnn)?;
+            }
+            // 3XNN - SE VX, byte
+            (0x3, ..) => self.skip_if(vx == nn)?,
+            // 4XNN - SNE VX, byte
+            (0x4, ..) => self.skip_if(vx != nn)?,
+            // 5XY0 - SE VX, VY
+            (0x5, _, _, 0x0) => self.skip_if(vx == vy)?,
+            // 5XY2 - LD [I], VX-VY (XO-CHIP); I is not modified
+            (0x5, _, _, 0x2) if xo => {
+                let i = state.register_file().i();
+                for (offset, register) in Self::register_range(x, y).enumerate() {
+                    let value = state.v(register);
+                    state
+                        .memory_mut()
+                        .write(i.wrapping_add(offset as u16), value)?;
+                }
+            }
+            // 5XY3 - LD VX-VY, [I] (XO-CHIP); I is not modified
+            (0x5, _, _, 0x3) if xo => {
+                let i = state.register_file().i();
+                for (offset, register) in Self::register_range(x, y).enumerate() {
+                    let value = state.memory().read(i.wrapping_add(offset as u16))?;
+                    state.set_v(register, value);
+                }
+            }
+            // 6XNN - LD VX, byte
+            (0x6, ..) => state.set_v(x, nn),
+            // 7XNN - ADD VX, byte (VF untouched)
+            (0x7, ..) => state.set_v(x, vx.wrapping_add(nn)),
+            // 8XY0 - LD VX, VY
+            (0x8, _, _, 0x0) => state.set_v(x, vy),
+            // 8XY1/8XY2/8XY3 - OR/AND/XOR VX, VY
+            (0x8, _, _, 0x1..=0x3) => {
+                let result = match n {
+                    0x1 => vx | vy,
+                    0x2 => vx & vy,
+                    _ => vx ^ vy,
+                };
+                state.set_v(x, result);
+                if quirks.vf_reset {
+                    state.set_v(FLAG_REGISTER, 0);
+                }
+            }
+            // 8XY4 - ADD VX, VY; VF = carry
+            (0x8, _, _, 0x4) => {
+                let (result, carry) = vx.overflowing_add(vy);
+                state.set_v(x, result);
+                state.set_v(FLAG_REGISTER, carry as u8);
+            }
+            // 8XY5 - SUB VX, VY; VF = NOT borrow
+            (0x8, _, _, 0x5) => {
+                let (result, borrow) = vx.overflowing_sub(vy);
+                state.set_v(x, result);
+                state.set_v(FLAG_REGISTER, !borrow as u8);
+            }
+            // 8XY6 - SHR VX, VY; VF = shifted out bit
+            (0x8, _, _, 0x6) => {
+                let source = if quirks.shift_in_place { vx } else { vy };
+                state.set_v(x, source >> 1);
+                state.set_v(FLAG_REGISTER, source & 0x1);
+            }
+            // 8XY7 - SUBN VX, VY; VF = NOT borrow
+            (0x8, _, _, 0x7) => {
+                let (result, borrow) = vy.overflowing_sub(vx);
+                state.set_v(x, result);
+                state.set_v(FLAG_REGISTER, !borrow as u8);
+            }
+            // 8XYE - SHL VX, VY; VF = shifted out bit
+            (0x8, _, _, 0xE) => {
+                let source = if quirks.shift_in_place { vx } else { vy };
+                state.set_v(x, source << 1);
+                state.set_v(FLAG_REGISTER, source >> 7);
+            }
+            // 9XY0 - SNE VX, VY
+            (0x9, _, _, 0x0) => self.skip_if(vx != vy)?,
+            // ANNN - LD I, addr
+            (0xA, ..) => state.register_file_mut().set_i(nnn),
+            // BNNN - JP V0, addr (or BXNN - JP VX, addr)
+            (0xB, ..) => {
+                let offset = if quirks.jump_uses_vx { vx } else { state.v(0) };
+                state.set_program_counter(nnn.wrapping_add(offset as u16))?;
+            }
+            // CXNN - RND VX, byte
+            (0xC, ..) => {
+                let random = state.random_byte();
+                state.set_v(x, random & nn);
+            }
+            // DXY0 - DRW VX, VY, 0; 16x16 sprite (SUPER-CHIP)
+            (0xD, _, _, 0x0) if extended => {
+                let i = state.register_file().i();
+                let length = 32 * state.display().plane_count();
+                let sprite = state.memory().read_slice(i, length)?.to_vec();
+                let collision = state.display_mut().draw_large_sprite(
+                    vx as usize,
+                    vy as usize,
+                    &sprite,
+                    !quirks.clip_sprites,
+                );
+                state.set_v(FLAG_REGISTER, collision as u8);
+            }
+            // DXYN - DRW VX, VY, nibble
+            (0xD, ..) => {
+                let i = state.register_file().i();
+                let length = n as usize * state.display().plane_count();
+                let sprite = state.memory().read_slice(i, length)?.to_vec();
+                let collision = state.display_mut().draw_sprite(
+                    vx as usize,
+                    vy as usize,
+                    &sprite,
+                    !quirks.clip_sprites,
+                );
+                state.set_v(FLAG_REGISTER, collision as u8);
+            }
+            // EX9E - SKP VX
+            (0xE, _, 0x9, 0xE) => self.skip_if(self.state.keypad().is_pressed(vx))?,
+            // EXA1 - SKNP VX
+            (0xE, _, 0xA, 0x1) => self.skip_if(!self.state.keypad().is_pressed(vx))?,
+            // F000 NNNN - LD I, long addr (XO-CHIP)
+            (0xF, 0x0, 0x0, 0x0) if xo => {
+                let operand = state.pc().wrapping_sub(2);
+                let address = state.memory().peek_slice(operand, 2)?;
+                let address = u16::from_be_bytes([address[0], address[1]]);
+                state.register_file_mut().set_i(address);
+            }
+            // FN01 - PLANE n (XO-CHIP)
+            (0xF, _, 0x0, 0x1) if xo => state.display_mut().select_planes(x),
+            // F002 - AUDIO (XO-CHIP)
+            (0xF, 0x0, 0x0, 0x2) if xo => {
+                let i = state.register_file().i();
+                let mut pattern = [0; AUDIO_PATTERN_SIZE];
+                pattern.copy_from_slice(state.memory().read_slice(i, AUDIO_PATTERN_SIZE)?);
+                state.set_audio_pattern(pattern);
+            }
+            (0xF, ..) => match nn {
+                // FX07 - LD VX, DT
+                0x07 => state.set_v(x, state.register_file().delay_timer()),
+                // FX0A - LD VX, K; blocks by re-executing until a key is
+                // pressed and released
+                0x0A => match state.keypad_mut().poll_wait() {
+                    Some(key) => state.set_v(x, key),
+                    None => state.set_program_counter(state.pc().wrapping_sub(2))?,
+                },
+                // FX15 - LD DT, VX
+                0x15 => state.register_file_mut().set_delay_timer(vx),
+                // FX18 - LD ST, VX
+                0x18 => state.register_file_mut().set_sound_timer(vx),
+                // FX1E - ADD I, VX
+                0x1E => {
+                    let i = state.register_file().i();
+                    state.register_file_mut().set_i(i.wrapping_add(vx as u16));
+                }
+                // FX29 - LD F, VX
+                0x29 => state
+                    .register_file_mut()
+                    .set_i(self.font_base + (vx & 0xF) as u16 * FONT_GLYPH_SIZE),
+                // FX30 - LD HF, VX (SUPER-CHIP)
+                0x30 if extended => state.register_file_mut().set_i(
+                    self.font_base + BIG_FONT_OFFSET + (vx & 0xF) as u16 * BIG_FONT_GLYPH_SIZE,
+                ),
+                // FX3A - PITCH VX (XO-CHIP)
+                0x3A if xo => state.set_pitch(vx),
+                // FX33 - LD B, VX
+                0x33 => {
+                    let i = state.register_file().i();
+                    let memory = state.memory_mut();
+                    memory.write(i, vx / 100)?;
+                    memory.write(i.wrapping_add(1), vx / 10 % 10)?;
+                    memory.write(i.wrapping_add(2), vx % 10)?;
+                }
+                // FX55 - LD [I], VX
+                0x55 => {
+                    let i = state.register_file().i();
+                    for register in 0..=x {
+                        let value = state.v(register);
+                        state
+                            .memory_mut()
+                            .write(i.wrapping_add(register as u16), value)?;
+                    }
+                    if quirks.memory_increments_i {
+                        state
+                            .register_file_mut()
+                            .set_i(i.wrapping_add(x as u16 + 1));
+                    }
+                }
+                // FX65 - LD VX, [I]
+                0x65 => {
+                    let i = state.register_file().i();
+                    for register in 0..=x {
+                        let value = state.memory().read(i.wrapping_add(register as u16))?;
+                        state.set_v(register, value);
+                    }
+                    if quirks.memory_increments_i {
+                        state
+                            .register_file_mut()
+                            .set_i(i.wrapping_add(x as u16 + 1));
+                    }
+                }
+                // FX75 - LD R, VX (SUPER-CHIP)
+                0x75 if extended => {
+                    let registers = &state.register_file().registers()[..=x as usize];
+                    self.rpl_flags.save(registers);
+                }
+                // FX85 - LD VX, R (SUPER-CHIP)
+                0x85 if extended => {
+                    let registers = &mut state.register_file_mut().registers_mut()[..=x as usize];
+                    self.rpl_flags.load(registers);
+                }
+                _ => return Err(InstructionError::InvalidOpcode.into()),
+            },
+            _ => return Err(InstructionError::InvalidOpcode.into()),
+        }
+
+        Ok(inst.cycles())
+    }
+
+    /// Skips the next instruction when `condition` holds
+    ///
+    /// On XO-CHIP the skipped instruction may be the 4-byte F000 NNNN.
+    fn skip_if(&mut self, condition: bool) -> Result<(), Chip8Error> {
+        if condition {
+            let pc = self.state.pc();
+            let size = match self.instruction_set {
+                Chip8InstructionSet::XOChip => self.fetch()?.size(),
+                _ => 2,
+            };
+            self.state
+                .set_program_counter(pc.wrapping_add(size as u16))?;
+        }
+        Ok(())
+    }
+
+    /// Registers VX through VY, counting down when X > Y
+    fn register_range(x: u8, y: u8) -> Box<dyn Iterator<Item = u8>> {
+        if x <= y {
+            Box::new(x..=y)
+        } else {
+            Box::new((y..=x).rev())
+        }
+    }
+}
+
+impl Default for Chip8Cpu {
+    fn default() -> Self {
+        Self::new(Chip8InstructionSet::default())
+    }
+}
+
+impl Cpu for Chip8Cpu {
+    type Error = Chip8Error;
+    type ISA = Chip8InstructionSet;
+    type State = Chip8State;
+
+    fn instruction_set(&self) -> &Self::ISA {
+        &self.instruction_set
+    }
+
+    fn state(&self) -> &Self::State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut Self::State {
+        &mut self.state
+    }
+
+    fn reset(&mut self) -> Result<(), Self::Error> {
+        self.state.reset();
+        Ok(())
+    }
+
+    /// Fetches, decodes and executes the instruction at PC
+    ///
+    /// # Returns
+    /// * `Ok(cycles)` - The number of cycles the instruction took
+    /// * `Err(Chip8Error::Instruction(InstructionError::InvalidOpcode))` - If
+    ///   the dialect does not define the instruction. PC is left pointing at it.
+    /// * `Err(error)` - If memory access fails or the call stack
+    ///   over/underflows. PC is left pointing past the faulting instruction.
+    /// * `Err(Chip8Error::Cpu(CpuError::Halted))` - Once 00FD has been executed
+    fn step(&mut self) -> Result<u8, Self::Error> {
+        if self.trace.is_active() {
+            self.traced_step()
+        } else {
+            self.execute_step()
+        }
+    }
+}
+
+impl Chip8Cpu {
+    /// Executes one instruction without tracing it
+    fn execute_step(&mut self) -> Result<u8, Chip8Error> {
+        if self.state.is_halted() {
+            return Err(CpuError::Halted.into());
+        }
+        let inst = self.fetch()?;
+        if !inst.is_valid(self.instruction_set) {
+            return Err(InstructionError::InvalidOpcode.into());
+        }
+        let pc = self.state.pc();
+        self.state
+            .set_program_counter(pc.wrapping_add(inst.size_in(self.instruction_set) as u16))?;
+
+        let cycles = self.execute(inst)?;
+        self.state.add_cycles(cycles);
+        Ok(cycles)
+    }
+
+    /// Executes one instruction and reports it to the trace sink
+    ///
+    /// Memory accesses are recorded for the trace without disturbing a
+    /// recording the debugger may already have running.
+    fn traced_step(&mut self) -> Result<u8, Chip8Error> {
+        let before = self.trace_registers();
+        let pc = self.state.pc();
+        let cycle = self.state.cycles();
+        let (bytes, disassembly) = self.trace_instruction();
+
+        let memory = self.state.memory_mut();
+        let recording = memory.is_recording();
+        if !recording {
+            memory.set_recording(true);
+        }
+        let mark = memory.recorded_len();
+        let result = self.execute_step();
+        let memory = self.state.memory_mut();
+        let accesses = memory.recorded_since(mark);
+        if !recording {
+            memory.set_recording(false);
+        }
+        let cycles = result?;
+
+        let memory = self.state.memory();
+        let entry = TraceEntry {
+            cycle,
+            pc: pc as u64,
+            bytes,
+            disassembly,
+            external: Vec::new(),
+            changes: register_changes(&before, &self.trace_registers()),
+            accesses: accesses
+                .into_iter()
+                .map(|access| TraceAccess {
+                    address: access.address as u64,
+                    kind: access.kind,
+                    value: memory
+                        .peek_slice(access.address, 1)
+                        .map_or(0, |byte| byte[0]) as u64,
+                })
+                .collect(),
+        };
+        let architecture = self.instruction_set.name();
+        self.trace.emit(
+            || TraceHeader {
+                architecture: architecture.to_string(),
+                registers: TRACE_REGISTERS
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+                initial: before.to_vec(),
+            },
+            &before,
+            entry,
+        )?;
+        Ok(cycles)
+    }
+
+    /// Returns the values of [`TRACE_REGISTERS`]
+    fn trace_registers(&self) -> [u64; TRACE_REGISTERS.len()] {
+        let registers = self.state.register_file();
+        let mut values = [0; TRACE_REGISTERS.len()];
+        for (value, &register) in values.iter_mut().zip(registers.registers()) {
+            *value = register as u64;
+        }
+        values[16] = registers.i() as u64;
+        values[17] = registers.stack_pointer() as u64;
+        values[18] = registers.delay_timer() as u64;
+        values[19] = registers.sound_timer() as u64;
+        values
+    }
+
+    /// Returns the bytes and disassembly of the instruction at PC, including
+    /// the operand word of `i := long`
+    fn trace_instruction(&self) -> (Vec<u8>, String) {
+        let Ok(inst) = self.fetch() else {
+            return (Vec::new(), String::new());
+        };
+        let pc = self.state.pc();
+        let memory = self.state.memory();
+        let bytes = memory
+            .peek_slice(pc, inst.size_in(self.instruction_set))
+            .or_else(|_| memory.peek_slice(pc, 2))
+            .map_or(Vec::new(), <[u8]>::to_vec);
+        let long = (bytes.len() == 4).then(|| u16::from_be_bytes([bytes[2], bytes[3]]));
+        let disassembly = mnemonic(inst, long, &|address| format!("{:#05x}", address));
+        (bytes, disassembly)
+    }
+}
+
+impl Debuggable for Chip8Cpu {
+    /// The number of return addresses on the stack
+    fn call_depth(&self) -> usize {
+        self.state.register_file().stack_pointer() as usize
+    }
+
+    /// True for 2NNN
+    fn is_call(&self) -> bool {
+        self.fetch().is_ok_and(|inst| inst.opcode() == 0x2)
+    }
+
+    fn set_access_recording(&mut self, enabled: bool) {
+        self.state.memory_mut().set_recording(enabled);
+    }
+
+    fn take_accesses(&mut self) -> Vec<MemoryAccess<u16>> {
+        self.state.memory_mut().take_accesses()
+    }
+}
+
+/// Captures the machine state, quirks and font location
+///
+/// The RPL user flags live in the [`RplFlagStore`], which persists on its own,
+/// so they are not part of the snapshot.
+impl Snapshot for Chip8Cpu {
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        self.state.write_state(writer)?;
+        self.quirks.write_state(writer)?;
+        writer.write(&self.font_base);
+        Ok(())
+    }
+
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        self.state.read_state(reader)?;
+        self.quirks.read_state(reader)?;
+        self.font_base = reader.read()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+//! CHIP-8 disassembly in Octo syntax
+//!
+//! [`mnemonic`] formats a single instruction. [`Chip8Listing`] walks a whole
+//! ROM image from [`PROGRAM_START`], following jumps and calls to tell code
+//! from data, and labels the addresses it finds referenced.
+
+use super::{Chip8Inst, Chip8InstructionSet, PROGRAM_START};
+use crate::core::isa::InstructionCodec;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Data bytes shown per listing line
+const DATA_BYTES_PER_LINE: usize = 8;
+
+/// Formats `inst` as an Octo statement
+///
+/// `long` is the operand word of an XO-CHIP `F000 NNNN`; `address` renders
+/// 12-bit address operands, e.g. as a label. Words that are not instructions
+/// of any supported dialect are rendered as Octo byte literals.
+pub fn mnemonic(inst: Chip8Inst, long: Option<u16>, address: &dyn Fn(u16) -> String) -> String {
+    if !is_statement(inst) {
+        return data_literal(&inst.encode());
+    }
+    let (x, y, n, nn, nnn) = (inst.x(), inst.y(), inst.n(), inst.nn(), inst.nnn());
+
+    match (inst.opcode(), x, y, n) {
+        (0x0, 0x0, 0xE, 0x0) => "clear".to_string(),
+        (0x0, 0x0, 0xE, 0xE) => "return".to_string(),
+        (0x0, 0x0, 0xC, _) => format!("scroll-down {}", n),
+        (0x0, 0x0, 0xD, _) => format!("scroll-up {}", n),
+        (0x0, 0x0, 0xF, 0xB) => "scroll-right".to_string(),
+        (0x0, 0x0, 0xF, 0xC) => "scroll-left".to_string(),
+        (0x0, 0x0, 0xF, 0xD) => "exit".to_string(),
+        (0x0, 0x0, 0xF, 0xE) => "lores".to_string(),
+        (0x0, 0x0, 0xF, 0xF) => "hires".to_string(),
+        (0x1, ..) => format!("jump {}", address(nnn)),
+        (0x2, ..) => format!(":call {}", address(nnn)),
+        (0x3, ..) => format!("if v{:x} != {:#04x} then", x, nn),
+        (0x4, ..) => format!("if v{:x} == {:#04x} then", x, nn),
+        (0x5, _, _, 0x0) => format!("if v{:x} != v{:x} then", x, y),
+        (0x5, _, _, 0x2) => format!("save v{:x} - v{:x}", x, y),
+        (0x5, _, _, 0x3) => format!("load v{:x} - v{:x}", x, y),
+        (0x6, ..) => format!("v{:x} := {:#04x}", x, nn),
+        (0x7, ..) => format!("v{:x} += {:#04x}", x, nn),
+        (0x8, _, _, 0x0) => format!("v{:x} := v{:x}", x, y),
+        (0x8, _, _, 0x1) => format!("v{:x} |= v{:x}", x, y),
+        (0x8, _, _, 0x2) => format!("v{:x} &= v{:x}", x, y),
+        (0x8, _, _, 0x3) => format!("v{:x} ^= v{:x}", x, y),
+        (0x8, _, _, 0x4) => format!("v{:x} += v{:x}", x, y),
+        (0x8, _, _, 0x5) => format!("v{:x} -= v{:x}", x, y),
+        (0x8, _, _, 0x6) => format!("v{:x} >>= v{:x}", x, y),
+        (0x8, _, _, 0x7) => format!("v{:x} =- v{:x}", x, y),
+        (0x8, _, _, 0xE) => format!("v{:x} <<= v{:x}", x, y),
+        (0x9, _, _, 0x0) => format!("if v{:x} == v{:x} then", x, y),
+        (0xA, ..) => format!("i := {}", address(nnn)),
+        (0xB, ..) => format!("jump0 {}", address(nnn)),
+        (0xC, ..) => format!("v{:x} := random {:#04x}", x, nn),
+        (0xD, ..) => format!("sprite v{:x} v{:x} {}", x, y, n),
+        (0xE, _, 0x9, 0xE) => format!("if v{:x} -key then", x),
+        (0xE, _, 0xA, 0x1) => format!("if v{:x} key then", x),
+        (0xF, 0x0, 0x0, 0x0) => match long {
+            Some(long) => format!("i := long {:#06x}", long),
+            None => "i := long".to_string(),
+        },
+        (0xF, _, 0x0, 0x1) => format!("plane {}", x),
+        (0xF, 0x0, 0x0, 0x2) => "audio".to_string(),
+        (0xF, ..) => match nn {
+            0x07 => format!("v{:x} := delay", x),
+            0x0A => format!("v{:x} := key", x),
+            0x15 => format!("delay := v{:x}", x),
+            0x18 => format!("buzzer := v{:x}", x),
+            0x1E => format!("i += v{:x}", x),
+            0x29 => format!("i := hex v{:x}", x),
+            0x30 => format!("i := bighex v{:x}", x),
+            0x33 => format!("bcd v{:x}", x),
+            0x3A => format!("pitch := v{:x}", x),
+            0x55 => format!("save v{:x}", x),
+            0x65 => format!("load v{:x}", x),
+            0x75 => format!("saveflags v{:x}", x),
+            0x85 => format!("loadflags v{:x}", x),
+            _ => unreachable!("is_statement rejects {:04X}", inst.word()),
+        },
+        _ => unreachable!("is_statement rejects {:04X}", inst.word()),
+    }
+}
+
+/// Returns true if `inst` is an instruction in any supported dialect that
+/// Octo has a statement for
+///
+/// XO-CHIP is a superset of the other dialects. 0NNN machine code calls are
+/// valid but have no statement, and in practice are padding rather than code.
+fn is_statement(inst: Chip8Inst) -> bool {
+    let machine_call = inst.opcode() == 0x0
+        && !matches!(inst.nnn(), 0x0C0..=0x0DF | 0x0E0 | 0x0EE | 0x0FB..=0x0FF);
+    inst.is_valid(Chip8InstructionSet::XOChip) && !machine_call
+}
+
+/// Formats raw bytes as Octo byte literals
+fn data_literal(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:#04x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// How an address is referenced, which decides its label prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Reference {
+    Call,
+    Jump,
+    Data,
+}
+
+/// A single line of a [`Chip8Listing`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListingLine {
+    /// Address of the first byte on this line
+    pub address: u16,
+    /// Raw bytes covered by this line
+    pub bytes: Vec<u8>,
+    /// Label defined at `address`, if it is referenced anywhere
+    pub label: Option<String>,
+    /// Octo statement, or byte literals for data
+    pub text: String,
+    /// True if the bytes were reached as code
+    pub is_code: bool,
+}
+
+/// A disassembly of a complete ROM image
+///
+/// Code is found by recursive traversal from [`PROGRAM_START`]: jumps, calls
+/// and both outcomes of skips are followed, while returns, `exit` and
+/// computed `jump0` jumps end a path. Everything never reached is listed as
+/// data. Addresses referenced by jumps, calls and `i :=` are labelled
+/// `loc_XXX`, `sub_XXX` and `data_XXX` respectively.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chip8Listing {
+    lines: Vec<ListingLine>,
+}
+
+impl Chip8Listing {
+    /// Disassembles `rom` as loaded at [`PROGRAM_START`]
+    ///
+    /// The listing stops at the end of the 16-bit address space; bytes of a
+    /// longer image have no address and are left out.
+    pub fn new(rom: &[u8]) -> Self {
+        let rom = &rom[..rom.len().min(0x10000 - PROGRAM_START as usize)];
+        let end = PROGRAM_START as usize + rom.len();
+        let in_rom = |address: u16| (PROGRAM_START as usize..end).contains(&(address as usize));
+        let fetch = |address: u16| -> Option<Chip8Inst> {
+            let offset = address.checked_sub(PROGRAM_START)? as usize;
+            Chip8Inst::decode(rom.get(offset..offset + 2)?).ok()
+        };
+
+        let mut code = BTreeSet::new();
+        let mut references = BTreeMap::new();
+        let mut pending = vec![PROGRAM_START];
+
+        while let Some(address) = pending.pop() {
+            if !in_rom(address) || code.contains(&address) {
+                continue;
+            }
+            let Some(inst) = fetch(address) else {
+                continue;
+            };
+            if !is_statement(inst) {
+                continue;
+            }
+            code.insert(address);
+
+            let next = address.wrapping_add(inst.size() as u16);
+            let after_next = next.wrapping_add(fetch(next).map_or(2, |next| next.size()) as u16);
+            let mut reference = |target: u16, kind: Reference| {
+                let entry = references.entry(target).or_insert(kind);
+                *entry = (*entry).min(kind);
+            };
+
+            match (inst.opcode(), inst.nn()) {
+                (0x0, 0xEE) | (0x0, 0xFD) => {}
+                (0x1, _) => {
+                    reference(inst.nnn(), Reference::Jump);
+                    pending.push(inst.nnn());
+                }
+                (0x2, _) => {
+                    reference(inst.nnn(), Reference::Call);
+                    pending.extend([next, inst.nnn()]);
+                }
+                (0xA, _) => {
+                    reference(inst.nnn(), Reference::Data);
+                    pending.push(next);
+                }
+                (0xB, _) => reference(inst.nnn(), Reference::Jump),
+                (0x5, _) if inst.n() != 0x0 => pending.push(next),
+                (0x3 | 0x4 | 0x5 | 0x9 | 0xE, _) => pending.extend([next, after_next]),
+                _ => pending.push(next),
+            }
+        }
+
+        let label = |address: u16| {
+            references
+                .get(&address)
+                .filter(|_| in_rom(address))
+                .map(|kind| {
+                    let prefix = match kind {
+                        Reference::Call => "sub",
+                        Reference::Jump => "loc",
+                        Reference::Data => "data",
+                    };
+                    format!("{}_{:03x}", prefix, address)
+                })
+        };
+        let operand = |address: u16| label(address).unwrap_or_else(|| format!("{:#05x}", address));
+
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        while offset < rom.len() {
+            let address = PROGRAM_START + offset as u16;
+            if code.contains(&address) {
+                let inst = fetch(address).expect("code addresses hold whole instructions");
+                let size = inst.size().min(rom.len() - offset);
+                let long =
+                    (size == 4).then(|| u16::from_be_bytes([rom[offset + 2], rom[offset + 3]]));
+                lines.push(ListingLine {
+                    address,
+                    bytes: rom[offset..offset + size].to_vec(),
+                    label: label(address),
+                    text: mnemonic(inst, long, &operand),
+                    is_code: true,
+                });
+                offset += size;
+                continue;
+            }
+
+            // Data runs until the next code, label or line break
+            let mut length = 1;
+            while length < DATA_BYTES_PER_LINE && offset + length < rom.len() {
+                let next = address + length as u16;
+                if code.contains(&next) || label(next).is_some() {
+                    break;
+                }
+                length += 1;
+            }
+            let bytes = rom[offset..offset + length].to_vec();
+            lines.push(ListingLine {
+                address,
+                text: data_literal(&bytes),
+                bytes,
+                label: label(address),
+                is_code: false,
+            });
+            offset += length;
+        }
+
+        Self { lines }
+    }
+
+    pub fn lines(&self) -> &[ListingLine] {
+        &self.lines
+    }
+}
+
+/// Renders the listing as `address  raw  statement` columns with labels on
+/// their own line, Octo style
+impl Display for Chip8Listing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for line in &self.lines {
+            if let Some(label) = &line.label {
+                writeln!(f, ": {}", label)?;
+            }
+            let raw: String = line
+                .bytes
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            writeln!(f, "{:#06x}  {:<16}  {}", line.address, raw, line.text)?;
+        }
+        Ok(())
+    }
+}
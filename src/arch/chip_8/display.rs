@@ -0,0 +1,351 @@
+use crate::core::savestate::{SaveStateError, Snapshot, StateReader, StateWriter};
+use std::io::{Result as IoResult, Write};
+use std::slice::Chunks;
+
+/// Display width in pixels
+pub const DISPLAY_WIDTH: usize = 64;
+/// Display height in pixels
+pub const DISPLAY_HEIGHT: usize = 32;
+/// SUPER-CHIP hi-res display width in pixels
+pub const HIRES_DISPLAY_WIDTH: usize = 128;
+/// SUPER-CHIP hi-res display height in pixels
+pub const HIRES_DISPLAY_HEIGHT: usize = 64;
+
+/// Number of XO-CHIP bit-planes
+pub const PLANE_COUNT: usize = 2;
+
+/// The CHIP-8 framebuffer
+///
+/// Pixels are stored one byte each in row-major order. Each byte is a bitmask
+/// of the bit-planes lit at that position: bit 0 for plane 1 and bit 1 for
+/// plane 2, giving XO-CHIP its four colours. Plain CHIP-8 and SUPER-CHIP only
+/// ever use plane 1, so their pixels are `0` (off) or `1` (lit).
+///
+/// Sprites are XORed onto the selected planes and any lit pixel turned off by
+/// a draw is reported as a collision. Clearing and scrolling also only affect
+/// the selected planes.
+///
+/// The display starts in the 64x32 lo-res mode and can be switched to the
+/// SUPER-CHIP 128x64 hi-res mode; switching clears it.
+///
+/// The display keeps a dirty flag that is raised whenever its contents change
+/// so a host only needs to redraw when something was actually drawn.
+#[derive(Debug, Clone)]
+pub struct Chip8Display {
+    width: usize,
+    height: usize,
+    hires: bool,
+    /// Bitmask of the planes drawing operations apply to
+    planes: u8,
+    pixels: Vec<u8>,
+    dirty: bool,
+}
+
+impl Chip8Display {
+    pub fn new() -> Self {
+        Self {
+            width: DISPLAY_WIDTH,
+            height: DISPLAY_HEIGHT,
+            hires: false,
+            planes: 0b01,
+            pixels: vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            dirty: true,
+        }
+    }
+
+    /// Returns to the power-on state: lo-res, blank, drawing to plane 1
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns true in the 128x64 hi-res mode
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    /// Switches between the 64x32 lo-res and 128x64 hi-res modes
+    ///
+    /// The display is cleared and resized even if the mode does not change.
+    pub fn set_hires(&mut self, hires: bool) {
+        let (width, height) = if hires {
+            (HIRES_DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT)
+        } else {
+            (DISPLAY_WIDTH, DISPLAY_HEIGHT)
+        };
+        self.hires = hires;
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width * height];
+        self.dirty = true;
+    }
+
+    /// Returns the bitmask of planes drawing operations apply to
+    pub fn planes(&self) -> u8 {
+        self.planes
+    }
+
+    /// Selects the planes drawing operations apply to (XO-CHIP FN01)
+    pub fn select_planes(&mut self, planes: u8) {
+        self.planes = planes & ((1 << PLANE_COUNT) - 1);
+    }
+
+    /// Returns the number of selected planes
+    ///
+    /// A sprite carries one set of rows for each selected plane.
+    pub fn plane_count(&self) -> usize {
+        self.planes.count_ones() as usize
+    }
+
+    /// Returns true if the pixel at (`x`, `y`) is lit in any plane
+    ///
+    /// Coordinates outside the display read as unlit.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.pixel_planes(x, y) != 0
+    }
+
+    /// Returns the bitmask of planes lit at (`x`, `y`), i.e. its colour index
+    ///
+    /// Coordinates outside the display read as `0`.
+    pub fn pixel_planes(&self, x: usize, y: usize) -> u8 {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x]
+        } else {
+            0
+        }
+    }
+
+    /// Iterates over the display one row at a time, top to bottom
+    pub fn rows(&self) -> Chunks<'_, u8> {
+        self.pixels.chunks(self.width)
+    }
+
+    /// Iterates over every pixel as `(x, y, lit)`, row by row
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize, bool)> + '_ {
+        self.pixels
+            .iter()
+            .enumerate()
+            .map(|(index, &pixel)| (index % self.width, index / self.width, pixel != 0))
+    }
+
+    /// Renders the display as text, `#` for lit pixels and `.` for unlit ones
+    pub fn to_ascii(&self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            text.extend(row.iter().map(|&pixel| if pixel != 0 { '#' } else { '.' }));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Writes the display as a plain (P1) PBM image, lit pixels black
+    pub fn write_pbm(&self, writer: &mut impl Write) -> IoResult<()> {
+        writeln!(writer, "P1\n{} {}", self.width, self.height)?;
+        for row in self.rows() {
+            let bits: Vec<&str> = row
+                .iter()
+                .map(|&pixel| if pixel != 0 { "1" } else { "0" })
+                .collect();
+            writeln!(writer, "{}", bits.join(" "))?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if the display changed since the last [`mark_clean`](Self::mark_clean)
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Acknowledges the current frame, clearing the dirty flag
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Turns every pixel off in the selected planes
+    pub fn clear(&mut self) {
+        let keep = !self.planes;
+        self.pixels.iter_mut().for_each(|pixel| *pixel &= keep);
+        self.dirty = true;
+    }
+
+    /// XORs an 8-pixel-wide sprite onto the selected planes
+    ///
+    /// `sprite` holds one byte per row and, with several planes selected, the
+    /// rows for plane 1 followed by the rows for plane 2.
+    ///
+    /// The origin always wraps onto the display. Pixels that then fall past
+    /// the right or bottom edge are either wrapped around to the opposite edge
+    /// (`wrap == true`) or clipped.
+    ///
+    /// # Returns
+    /// * `true` - If any lit pixel was turned off
+    pub fn draw_sprite(&mut self, x: usize, y: usize, sprite: &[u8], wrap: bool) -> bool {
+        self.draw(x, y, sprite, 1, wrap)
+    }
+
+    /// XORs a 16x16 SUPER-CHIP sprite onto the selected planes
+    ///
+    /// `sprite` holds two bytes per row, left half first, and is laid out per
+    /// plane as in [`draw_sprite`](Self::draw_sprite), as are the edges.
+    pub fn draw_large_sprite(&mut self, x: usize, y: usize, sprite: &[u8], wrap: bool) -> bool {
+        self.draw(x, y, sprite, 2, wrap)
+    }
+
+    /// Scrolls the selected planes down by `lines`
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.shift(0, lines as isize);
+    }
+
+    /// Scrolls the selected planes up by `lines` (XO-CHIP)
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.shift(0, -(lines as isize));
+    }
+
+    /// Scrolls the selected planes right by `columns`
+    pub fn scroll_right(&mut self, columns: usize) {
+        self.shift(columns as isize, 0);
+    }
+
+    /// Scrolls the selected planes left by `columns`
+    pub fn scroll_left(&mut self, columns: usize) {
+        self.shift(-(columns as isize), 0);
+    }
+
+    /// Moves the selected planes by (`dx`, `dy`), blanking what scrolls in
+    fn shift(&mut self, dx: isize, dy: isize) {
+        let mask = self.planes;
+        let (width, height) = (self.width as isize, self.height as isize);
+        let source = self.pixels.clone();
+
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = (x - dx, y - dy);
+                let moved = if (0..width).contains(&sx) && (0..height).contains(&sy) {
+                    source[(sy * width + sx) as usize] & mask
+                } else {
+                    0
+                };
+                let pixel = &mut self.pixels[(y * width + x) as usize];
+                *pixel = (*pixel & !mask) | moved;
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// XORs a sprite `row_bytes` wide onto each selected plane in turn
+    fn draw(&mut self, x: usize, y: usize, sprite: &[u8], row_bytes: usize, wrap: bool) -> bool {
+        let planes: Vec<u8> = (0..PLANE_COUNT)
+            .map(|plane| 1 << plane)
+            .filter(|bit| self.planes & bit != 0)
+            .collect();
+        if planes.is_empty() {
+            self.dirty = true;
+            return false;
+        }
+
+        let plane_bytes = sprite.len() / planes.len();
+        let mut collision = false;
+        for (index, &plane) in planes.iter().enumerate() {
+            let data = &sprite[index * plane_bytes..(index + 1) * plane_bytes];
+            let rows = data.chunks_exact(row_bytes).map(|row| {
+                row.iter().enumerate().fold(0u16, |bits, (byte, &value)| {
+                    bits | (value as u16) << (8 - 8 * byte)
+                })
+            });
+            collision |= self.draw_plane(x, y, rows, row_bytes * 8, plane, wrap);
+        }
+
+        self.dirty = true;
+        collision
+    }
+
+    /// XORs `width` pixels of each row into `plane`, most significant bit leftmost
+    fn draw_plane(
+        &mut self,
+        x: usize,
+        y: usize,
+        rows: impl Iterator<Item = u16>,
+        width: usize,
+        plane: u8,
+        wrap: bool,
+    ) -> bool {
+        let origin_x = x % self.width;
+        let origin_y = y % self.height;
+        let mut collision = false;
+
+        for (row, bits) in rows.enumerate() {
+            let mut py = origin_y + row;
+            if py >= self.height {
+                if !wrap {
+                    break;
+                }
+                py %= self.height;
+            }
+            for column in 0..width {
+                if bits & (0x8000 >> column) == 0 {
+                    continue;
+                }
+                let mut px = origin_x + column;
+                if px >= self.width {
+                    if !wrap {
+                        break;
+                    }
+                    px %= self.width;
+                }
+                let pixel = &mut self.pixels[py * self.width + px];
+                collision |= *pixel & plane != 0;
+                *pixel ^= plane;
+            }
+        }
+
+        collision
+    }
+}
+
+impl Default for Chip8Display {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Snapshot for Chip8Display {
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        writer.write(&self.hires);
+        writer.write(&self.planes);
+        writer.write_raw(&self.pixels);
+        Ok(())
+    }
+
+    /// Restores the mode and contents and marks the display dirty so hosts
+    /// redraw it
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        let hires = reader.read()?;
+        let planes = reader.read::<u8>()?;
+        let (width, height) = if hires {
+            (HIRES_DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT)
+        } else {
+            (DISPLAY_WIDTH, DISPLAY_HEIGHT)
+        };
+        let pixels = reader.read_raw(width * height)?;
+        let mask = (1 << PLANE_COUNT) - 1;
+        if planes & !mask != 0 || pixels.iter().any(|&pixel| pixel & !mask != 0) {
+            return Err(SaveStateError::InvalidData(
+                "display uses planes that do not exist".into(),
+            ));
+        }
+        self.hires = hires;
+        self.width = width;
+        self.height = height;
+        self.planes = planes;
+        self.pixels = pixels.to_vec();
+        self.dirty = true;
+        Ok(())
+    }
+}
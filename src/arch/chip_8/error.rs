@@ -0,0 +1,157 @@
+use crate::{
+    arch::error::ArchError,
+    core::{
+        cpu::{CpuError, CpuStateError, RegisterError},
+        isa::InstructionError,
+        memory::MemoryError,
+        movie::MovieError,
+        savestate::SaveStateError,
+    },
+};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Chip8Error {
+    Cpu(CpuError),
+    Memory(MemoryError),
+    Instruction(InstructionError),
+    InvalidState(String),
+    UnsupportedFeature(String),
+    TimingViolation,
+    Register(RegisterError),
+    /// A keypad key outside `0x0..=0xF`
+    InvalidKey(u8),
+    /// A ROM that does not fit between the program start and the end of memory
+    RomTooLarge {
+        size: usize,
+        capacity: usize,
+    },
+    /// Reading a ROM or other host file failed
+    Io(String),
+    /// A save state could not be taken or restored
+    SaveState(SaveStateError),
+    /// An input could not be recorded
+    Movie(MovieError),
+}
+
+impl Display for Chip8Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Cpu(err) => write!(f, "CPU error: {}", err),
+            Self::Memory(err) => write!(f, "Memory error: {}", err),
+            Self::Instruction(err) => write!(f, "Instruction error: {}", err),
+            Self::InvalidState(msg) => write!(f, "Invalid state: {}", msg),
+            Self::UnsupportedFeature(feature) => write!(f, "Unsupported feature: {}", feature),
+            Self::TimingViolation => write!(f, "Timing violation"),
+            Self::Register(err) => write!(f, "Register error: {}", err),
+            Self::InvalidKey(key) => write!(f, "Invalid key: {:#04x}", key),
+            Self::RomTooLarge { size, capacity } => write!(
+                f,
+                "ROM too large: {} bytes, {} bytes available",
+                size, capacity
+            ),
+            Self::Io(msg) => write!(f, "I/O error: {}", msg),
+            Self::SaveState(err) => write!(f, "Save state error: {}", err),
+            Self::Movie(err) => write!(f, "Movie error: {}", err),
+        }
+    }
+}
+
+impl Error for Chip8Error {}
+
+impl From<ArchError> for Chip8Error {
+    fn from(err: ArchError) -> Self {
+        match err {
+            ArchError::Cpu(err) => Self::Cpu(err),
+            ArchError::Memory(err) => Self::Memory(err),
+            ArchError::Instruction(err) => Self::Instruction(err),
+            ArchError::InvalidState(msg) => Self::InvalidState(msg),
+            ArchError::UnsupportedFeature(feature) => Self::UnsupportedFeature(feature),
+            ArchError::TimingViolation => Self::TimingViolation,
+        }
+    }
+}
+
+impl From<CpuError> for Chip8Error {
+    fn from(err: CpuError) -> Self {
+        Self::Cpu(err)
+    }
+}
+
+impl From<Chip8Error> for CpuError {
+    fn from(err: Chip8Error) -> Self {
+        match err {
+            Chip8Error::Cpu(cpu_err) => cpu_err,
+            Chip8Error::Memory(_) => {
+                Self::State(CpuStateError::InvalidState("Memory error".into()))
+            }
+            Chip8Error::Instruction(_) => {
+                Self::State(CpuStateError::InvalidState("Instruction error".into()))
+            }
+            Chip8Error::InvalidState(msg) => Self::State(CpuStateError::InvalidState(msg)),
+            Chip8Error::UnsupportedFeature(msg) => Self::State(CpuStateError::InvalidState(msg)),
+            Chip8Error::TimingViolation => {
+                Self::State(CpuStateError::InvalidState("Timing violation".into()))
+            }
+            Chip8Error::Register(register_error) => {
+                Self::State(CpuStateError::Register(register_error))
+            }
+            Chip8Error::InvalidKey(key) => Self::State(CpuStateError::InvalidState(format!(
+                "Invalid key {:#04x}",
+                key
+            ))),
+            Chip8Error::RomTooLarge { size, capacity } => {
+                Self::State(CpuStateError::InvalidState(format!(
+                    "ROM too large: {} bytes, {} bytes available",
+                    size, capacity
+                )))
+            }
+            Chip8Error::Io(msg) => Self::Other(msg),
+            Chip8Error::SaveState(err) => Self::Other(err.to_string()),
+            Chip8Error::Movie(err) => Self::Other(err.to_string()),
+        }
+    }
+}
+
+impl From<InstructionError> for Chip8Error {
+    fn from(err: InstructionError) -> Self {
+        Self::Instruction(err)
+    }
+}
+
+impl From<CpuStateError> for Chip8Error {
+    fn from(err: CpuStateError) -> Self {
+        Self::Cpu(CpuError::State(err))
+    }
+}
+
+impl From<RegisterError> for Chip8Error {
+    fn from(err: RegisterError) -> Self {
+        Self::Register(err)
+    }
+}
+
+impl From<MemoryError> for Chip8Error {
+    fn from(err: MemoryError) -> Self {
+        Self::Memory(err)
+    }
+}
+
+impl From<std::io::Error> for Chip8Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err.to_string())
+    }
+}
+
+impl From<SaveStateError> for Chip8Error {
+    fn from(err: SaveStateError) -> Self {
+        Self::SaveState(err)
+    }
+}
+
+impl From<MovieError> for Chip8Error {
+    fn from(err: MovieError) -> Self {
+        Self::Movie(err)
+    }
+}
@@ -0,0 +1,71 @@
+use super::{Chip8, REGISTER_COUNT};
+use crate::arch::gdb::{GdbRegister, GdbTarget};
+use crate::arch::Architecture;
+use crate::core::cpu::{Cpu, RegisterFile};
+
+const fn register(name: &'static str, bits: usize, kind: &'static str) -> GdbRegister {
+    GdbRegister { name, bits, kind }
+}
+
+/// V0-VF, then I, PC, SP, DT and ST
+const REGISTERS: [GdbRegister; REGISTER_COUNT + 5] = [
+    register("v0", 8, "int"),
+    register("v1", 8, "int"),
+    register("v2", 8, "int"),
+    register("v3", 8, "int"),
+    register("v4", 8, "int"),
+    register("v5", 8, "int"),
+    register("v6", 8, "int"),
+    register("v7", 8, "int"),
+    register("v8", 8, "int"),
+    register("v9", 8, "int"),
+    register("va", 8, "int"),
+    register("vb", 8, "int"),
+    register("vc", 8, "int"),
+    register("vd", 8, "int"),
+    register("ve", 8, "int"),
+    register("vf", 8, "int"),
+    register("i", 16, "data_ptr"),
+    register("pc", 16, "code_ptr"),
+    register("sp", 8, "int"),
+    register("dt", 8, "int"),
+    register("st", 8, "int"),
+];
+
+impl GdbTarget for Chip8 {
+    fn gdb_registers(&self) -> &'static [GdbRegister] {
+        &REGISTERS
+    }
+
+    fn read_gdb_register(&self, index: usize) -> Option<Vec<u8>> {
+        let registers = self.state().register_file();
+        let value = match index.checked_sub(REGISTER_COUNT) {
+            None => return Some(vec![registers.registers()[index]]),
+            Some(0) => registers.i(),
+            Some(1) => registers.program_counter(),
+            Some(2) => return Some(vec![registers.stack_pointer() as u8]),
+            Some(3) => return Some(vec![registers.delay_timer()]),
+            Some(4) => return Some(vec![registers.sound_timer()]),
+            Some(_) => return None,
+        };
+        Some(value.to_le_bytes().to_vec())
+    }
+
+    fn write_gdb_register(&mut self, index: usize, value: &[u8]) -> bool {
+        let registers = self.cpu_mut().state_mut().register_file_mut();
+        match (index.checked_sub(REGISTER_COUNT), value) {
+            (None, &[byte]) => registers.registers_mut()[index] = byte,
+            (Some(0), &[low, high]) => registers.set_i(u16::from_le_bytes([low, high])),
+            (Some(1), &[low, high]) => {
+                registers.set_program_counter(u16::from_le_bytes([low, high]))
+            }
+            (Some(2), &[byte]) if (byte as usize) <= super::STACK_DEPTH => {
+                registers.set_stack_pointer(byte as u16)
+            }
+            (Some(3), &[byte]) => registers.set_delay_timer(byte),
+            (Some(4), &[byte]) => registers.set_sound_timer(byte),
+            _ => return false,
+        }
+        true
+    }
+}
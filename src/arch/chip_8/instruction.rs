@@ -0,0 +1,232 @@
+use super::{mnemonic, Chip8Error, Chip8InstructionSet};
+use crate::core::{
+    cpu::CpuState,
+    isa::{AddressingError, AddressingMode, Instruction, InstructionCodec, InstructionError},
+    memory::MemoryDevice,
+};
+
+/// Operand addressing used by CHIP-8 instructions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Chip8AddressingMode {
+    /// 8-bit immediate operand (NN)
+    Immediate(u8),
+    /// Register operand (VX)
+    Register(u8),
+    /// Memory addressed through the index register I
+    Indirect,
+    /// 12-bit absolute address (NNN)
+    Direct(u16),
+}
+
+impl AddressingMode for Chip8AddressingMode {
+    type Register = u8;
+
+    type Address = u16;
+
+    type Error = AddressingError;
+
+    fn resolve(&self, _cpu: &impl CpuState) -> Result<Self::Address, Self::Error> {
+        match self {
+            Self::Direct(address) => Ok(*address),
+            // I is not reachable through the generic `CpuState` interface, and
+            // immediates/registers do not name a memory location.
+            Self::Immediate(_) | Self::Register(_) | Self::Indirect => {
+                Err(AddressingError::InvalidAddressingMode)
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Self::Immediate(_) => 1,
+            Self::Direct(_) => 2,
+            Self::Register(_) | Self::Indirect => 0,
+        }
+    }
+
+    fn format(&self) -> String {
+        match self {
+            Self::Immediate(value) => format!("{:#04x}", value),
+            Self::Register(register) => format!("v{:x}", register),
+            Self::Indirect => "i".to_string(),
+            Self::Direct(address) => format!("{:#05x}", address),
+        }
+    }
+
+    fn is_valid_register(
+        &self,
+        register: <Chip8AddressingMode as AddressingMode>::Register,
+    ) -> bool {
+        register < 16
+    }
+
+    fn is_valid_address(&self, address: Self::Address) -> bool {
+        address <= 0x0FFF
+    }
+
+    fn bytes_needed(&self) -> usize {
+        self.size()
+    }
+}
+
+/// A single 16-bit CHIP-8 instruction word
+///
+/// Field accessors follow the usual CHIP-8 naming: for an opcode `0xABCD`,
+/// `x` is `B`, `y` is `C`, `n` is `D`, `nn` is `CD` and `nnn` is `BCD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chip8Inst(u16);
+
+impl Chip8Inst {
+    pub fn new(word: u16) -> Self {
+        Self(word)
+    }
+
+    /// Returns the raw instruction word
+    pub fn word(&self) -> u16 {
+        self.0
+    }
+
+    /// Returns the high nibble, which selects the instruction group
+    pub fn opcode(&self) -> u8 {
+        (self.0 >> 12) as u8
+    }
+
+    pub fn x(&self) -> u8 {
+        ((self.0 >> 8) & 0xF) as u8
+    }
+
+    pub fn y(&self) -> u8 {
+        ((self.0 >> 4) & 0xF) as u8
+    }
+
+    pub fn n(&self) -> u8 {
+        (self.0 & 0xF) as u8
+    }
+
+    pub fn nn(&self) -> u8 {
+        (self.0 & 0xFF) as u8
+    }
+
+    pub fn nnn(&self) -> u16 {
+        self.0 & 0x0FFF
+    }
+
+    /// Returns how the instruction's main operand is addressed
+    pub fn addressing_mode(&self) -> Chip8AddressingMode {
+        match self.opcode() {
+            0x0 | 0x1 | 0x2 | 0xA | 0xB => Chip8AddressingMode::Direct(self.nnn()),
+            0x3 | 0x4 | 0x6 | 0x7 | 0xC => Chip8AddressingMode::Immediate(self.nn()),
+            0xD => Chip8AddressingMode::Indirect,
+            0xF if matches!(self.nn(), 0x33 | 0x55 | 0x65) => Chip8AddressingMode::Indirect,
+            _ => Chip8AddressingMode::Register(self.x()),
+        }
+    }
+
+    /// Returns true if `instruction_set` defines this instruction
+    ///
+    /// 0NNN counts as valid everywhere: the CPU ignores machine code calls
+    /// rather than rejecting them.
+    pub fn is_valid(&self, instruction_set: Chip8InstructionSet) -> bool {
+        let extended = instruction_set != Chip8InstructionSet::Chip8;
+        let xo = instruction_set == Chip8InstructionSet::XOChip;
+        match (self.opcode(), self.x(), self.y(), self.n()) {
+            (0x0..=0x4 | 0x6 | 0x7 | 0xA..=0xD, ..) => true,
+            (0x5 | 0x9, _, _, 0x0) => true,
+            (0x5, _, _, 0x2 | 0x3) => xo,
+            (0x8, _, _, 0x0..=0x7 | 0xE) => true,
+            (0xE, _, 0x9, 0xE) | (0xE, _, 0xA, 0x1) => true,
+            (0xF, 0x0, 0x0, 0x0 | 0x2) | (0xF, _, 0x0, 0x1) => xo,
+            (0xF, ..) => match self.nn() {
+                0x07 | 0x0A | 0x15 | 0x18 | 0x1E | 0x29 | 0x33 | 0x55 | 0x65 => true,
+                0x30 | 0x75 | 0x85 => extended,
+                0x3A => xo,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns the size in bytes under `instruction_set`
+    ///
+    /// Only XO-CHIP reads F000 as `i := long` with a second operand word;
+    /// elsewhere F000 is a 2-byte invalid opcode.
+    pub fn size_in(&self, instruction_set: Chip8InstructionSet) -> usize {
+        match instruction_set {
+            Chip8InstructionSet::XOChip => self.size(),
+            _ => 2,
+        }
+    }
+}
+
+impl Instruction for Chip8Inst {
+    type Error = Chip8Error;
+    type Opcode = u8;
+    type Register = u8;
+    type Address = u16;
+    type Word = u8;
+
+    type AddressingMode = Chip8AddressingMode;
+
+    /// CHIP-8 instructions need I, the call stack, the display and the keypad,
+    /// none of which are reachable through the generic `CpuState` interface.
+    /// Execution goes through [`Chip8Cpu::step`](super::Chip8Cpu) instead.
+    fn execute(
+        &self,
+        _cpu: &mut impl CpuState,
+        _memory: &mut impl MemoryDevice<
+            Address = Self::Address,
+            Word = <Self as Instruction>::Word,
+            Error = <Self as Instruction>::Error,
+        >,
+    ) -> Result<u8, <Self as Instruction>::Error> {
+        Err(Chip8Error::UnsupportedFeature(
+            "CHIP-8 instructions execute through Chip8Cpu::step".into(),
+        ))
+    }
+
+    /// Every instruction counts as a single cycle
+    fn cycles(&self) -> u8 {
+        1
+    }
+
+    fn affects_flags(&self) -> bool {
+        match self.opcode() {
+            0x8 => matches!(self.n(), 0x1..=0x7 | 0xE),
+            0xD => true,
+            _ => false,
+        }
+    }
+
+    /// Octo syntax, covering every supported dialect. The operand word of
+    /// `i := long` is not part of this instruction; see [`Chip8Listing`](super::Chip8Listing).
+    fn disassemble(&self) -> String {
+        mnemonic(*self, None, &|address| format!("{:#05x}", address))
+    }
+}
+
+impl InstructionCodec for Chip8Inst {
+    type Error = Chip8Error;
+    type Word = u8;
+
+    fn encode(&self) -> Vec<u8> {
+        let bytes = self.0.to_be_bytes();
+        vec![bytes[0], bytes[1]]
+    }
+
+    fn decode(bytes: &[Self::Word]) -> Result<Self, Self::Error> {
+        if bytes.len() < 2 {
+            return Err(Chip8Error::Instruction(InstructionError::InvalidLength));
+        }
+        Ok(Self((bytes[0] as u16) << 8 | bytes[1] as u16))
+    }
+
+    /// XO-CHIP's F000 NNNN carries its address in a second word. This is
+    /// the widest reading; use [`Chip8Inst::size_in`] for a given dialect.
+    fn size(&self) -> usize {
+        if self.0 == 0xF000 {
+            4
+        } else {
+            2
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use super::{Chip8Error, Chip8Inst, MEMORY_SIZE, XO_MEMORY_SIZE};
+use crate::core::isa::{InstructionCategory, InstructionSet};
+
+/// The CHIP-8 dialects this crate knows about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Chip8InstructionSet {
+    #[default]
+    Chip8,
+    SuperChip,
+    XOChip,
+}
+
+impl Chip8InstructionSet {
+    /// Returns the size of the address space in bytes
+    pub fn memory_size(&self) -> usize {
+        match self {
+            Self::Chip8 | Self::SuperChip => MEMORY_SIZE,
+            Self::XOChip => XO_MEMORY_SIZE,
+        }
+    }
+}
+
+impl InstructionSet for Chip8InstructionSet {
+    type Error = Chip8Error;
+    type Instruction = Chip8Inst;
+    type Opcode = u8;
+    type Register = u8; // CHIP-8 uses 8-bit registers
+    type Address = u16; // CHIP-8 uses 16-bit addresses
+    type Word = u8; // CHIP-8 uses 8-bit words
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Chip8 => "CHIP-8",
+            Self::SuperChip => "SUPER-CHIP",
+            Self::XOChip => "XO-CHIP",
+        }
+    }
+
+    fn word_size(&self) -> u8 {
+        8
+    }
+
+    fn address_size(&self) -> u8 {
+        match self {
+            Self::Chip8 | Self::SuperChip => 12,
+            Self::XOChip => 16,
+        }
+    }
+
+    fn register_count(&self) -> usize {
+        16
+    }
+
+    /// Opcodes are the high nibble of the instruction word
+    fn is_valid_opcode(&self, opcode: Self::Opcode) -> bool {
+        opcode <= 0xF
+    }
+
+    fn categorize(&self, opcode: Self::Opcode) -> InstructionCategory {
+        match opcode {
+            0x0 => InstructionCategory::System,
+            0x1 | 0x2 | 0x3 | 0x4 | 0x5 | 0x9 | 0xB => InstructionCategory::Control,
+            0x6 | 0xA => InstructionCategory::DataTransfer,
+            0x7 | 0xC => InstructionCategory::Arithmetic,
+            0x8 => InstructionCategory::Logic,
+            0xD | 0xE => InstructionCategory::IO,
+            _ => InstructionCategory::Misc,
+        }
+    }
+
+    fn opcodes_in_category(&self, category: InstructionCategory) -> Vec<Self::Opcode> {
+        (0x0..=0xF)
+            .filter(|&opcode| self.categorize(opcode) == category)
+            .collect()
+    }
+}
@@ -0,0 +1,134 @@
+use super::Chip8Error;
+use crate::core::savestate::{SaveStateError, Snapshot, StateReader, StateWriter};
+
+/// Number of keys on the CHIP-8 hexadecimal keypad
+pub const KEY_COUNT: u8 = 16;
+
+/// The 16-key hexadecimal keypad
+///
+/// Keys are numbered `0x0`-`0xF` after the digit printed on them. Besides the
+/// held state used by EX9E/EXA1, the keypad tracks the FX0A wait: like the
+/// COSMAC VIP, a wait only completes once a key pressed during the wait is
+/// released again.
+#[derive(Debug, Clone, Default)]
+pub struct Chip8Keypad {
+    /// One bit per key, bit N set while key N is held
+    keys: u16,
+    /// True while an FX0A instruction is waiting for input
+    waiting: bool,
+    /// Keys pressed since the current wait started
+    wait_pressed: u16,
+    /// First key released after being pressed during the current wait
+    wait_released: Option<u8>,
+}
+
+impl Chip8Keypad {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks `key` as held
+    ///
+    /// # Returns
+    /// * `Err(Chip8Error::InvalidKey(key))` - If `key` is not in `0x0..=0xF`
+    pub fn press(&mut self, key: u8) -> Result<(), Chip8Error> {
+        let bit = Self::bit(key)?;
+        self.keys |= bit;
+        if self.waiting {
+            self.wait_pressed |= bit;
+        }
+        Ok(())
+    }
+
+    /// Marks `key` as released
+    ///
+    /// # Returns
+    /// * `Err(Chip8Error::InvalidKey(key))` - If `key` is not in `0x0..=0xF`
+    pub fn release(&mut self, key: u8) -> Result<(), Chip8Error> {
+        let bit = Self::bit(key)?;
+        self.keys &= !bit;
+        if self.waiting && self.wait_pressed & bit != 0 && self.wait_released.is_none() {
+            self.wait_released = Some(key);
+        }
+        Ok(())
+    }
+
+    /// Returns true while `key` is held
+    ///
+    /// Only the low nibble of `key` is considered, as on the original hardware.
+    pub fn is_pressed(&self, key: u8) -> bool {
+        self.keys & (1 << (key & 0xF)) != 0
+    }
+
+    /// Returns the held keys as a bitmask, bit N set while key N is held
+    pub fn keys(&self) -> u16 {
+        self.keys
+    }
+
+    /// Returns true while an FX0A instruction is blocked waiting for a key
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    /// Releases every key and abandons any pending wait
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Advances the FX0A wait
+    ///
+    /// The first call starts a wait; keys already held at that point do not
+    /// count. Later calls return the key once it has been pressed and
+    /// released, ending the wait.
+    pub(crate) fn poll_wait(&mut self) -> Option<u8> {
+        if !self.waiting {
+            self.waiting = true;
+            self.wait_pressed = 0;
+            self.wait_released = None;
+            return None;
+        }
+        let key = self.wait_released.take()?;
+        self.waiting = false;
+        self.wait_pressed = 0;
+        Some(key)
+    }
+
+    fn bit(key: u8) -> Result<u16, Chip8Error> {
+        if key >= KEY_COUNT {
+            return Err(Chip8Error::InvalidKey(key));
+        }
+        Ok(1 << key)
+    }
+}
+
+impl Snapshot for Chip8Keypad {
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        writer.write(&self.keys);
+        writer.write(&self.waiting);
+        writer.write(&self.wait_pressed);
+        writer.write(&self.wait_released.is_some());
+        writer.write(&self.wait_released.unwrap_or_default());
+        Ok(())
+    }
+
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        let keys = reader.read()?;
+        let waiting = reader.read()?;
+        let wait_pressed = reader.read()?;
+        let released = reader.read::<bool>()?;
+        let key = reader.read::<u8>()?;
+        if key >= KEY_COUNT {
+            return Err(SaveStateError::InvalidData(format!(
+                "invalid key {:#04x}",
+                key
+            )));
+        }
+        *self = Self {
+            keys,
+            waiting,
+            wait_pressed,
+            wait_released: released.then_some(key),
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use super::Chip8Error;
+use crate::core::debug::{AccessKind, MemoryAccess};
+use crate::core::memory::{MemoryDevice, MemoryError};
+use crate::core::savestate::{SaveStateError, Snapshot, StateReader, StateWriter};
+use std::cell::RefCell;
+
+/// Size of the CHIP-8 address space in bytes
+pub const MEMORY_SIZE: usize = 4096;
+/// Size of the XO-CHIP address space in bytes
+pub const XO_MEMORY_SIZE: usize = 65536;
+
+#[derive(Debug)]
+pub struct Chip8Memory {
+    memory: Vec<u8>,
+    /// Accesses made while recording for the debugger, `None` when not recording
+    accesses: RefCell<Option<Vec<MemoryAccess<u16>>>>,
+}
+
+impl Chip8Memory {
+    /// Creates the standard 4 KiB memory
+    pub fn new() -> Self {
+        Self::with_size(MEMORY_SIZE)
+    }
+
+    /// Creates a memory of `size` bytes, e.g. [`XO_MEMORY_SIZE`]
+    pub fn with_size(size: usize) -> Self {
+        Self {
+            memory: vec![0; size],
+            accesses: RefCell::new(None),
+        }
+    }
+
+    pub fn read_slice(&self, address: u16, length: usize) -> Result<&[u8], Chip8Error> {
+        let slice = self.peek_slice(address, length)?;
+        self.record(address, length, AccessKind::Read);
+        Ok(slice)
+    }
+
+    /// Reads like [`read_slice`](Self::read_slice) without recording an
+    /// access, for instruction fetches
+    pub(crate) fn peek_slice(&self, address: u16, length: usize) -> Result<&[u8], Chip8Error> {
+        if address as usize + length > self.memory.len() {
+            return Err(Chip8Error::Memory(MemoryError::AddressOutOfBounds));
+        }
+        Ok(&self.memory[address as usize..address as usize + length])
+    }
+
+    /// Starts or stops recording accesses, discarding any already recorded
+    pub fn set_recording(&mut self, enabled: bool) {
+        *self.accesses.get_mut() = enabled.then(Vec::new);
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        self.accesses.borrow().is_some()
+    }
+
+    /// Returns the accesses recorded after the first `skip`, leaving them in
+    /// place for whoever started the recording
+    pub(crate) fn recorded_since(&self, skip: usize) -> Vec<MemoryAccess<u16>> {
+        self.accesses
+            .borrow()
+            .as_ref()
+            .map(|accesses| accesses.get(skip..).unwrap_or_default().to_vec())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn recorded_len(&self) -> usize {
+        self.accesses.borrow().as_ref().map_or(0, Vec::len)
+    }
+
+    /// Removes and returns the accesses recorded so far, oldest first
+    pub fn take_accesses(&mut self) -> Vec<MemoryAccess<u16>> {
+        self.accesses
+            .get_mut()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn record(&self, address: u16, length: usize, kind: AccessKind) {
+        if let Some(accesses) = self.accesses.borrow_mut().as_mut() {
+            accesses.extend((0..length).map(|offset| MemoryAccess {
+                address: address.wrapping_add(offset as u16),
+                kind,
+            }));
+        }
+    }
+
+    /// Copies `data` into memory starting at `address`
+    ///
+    /// # Returns
+    /// * `Err(Chip8Error::Memory(MemoryError::AddressOutOfBounds))` - If the data
+    ///   does not fit between `address` and the end of memory
+    pub fn write_slice(&mut self, address: u16, data: &[u8]) -> Result<(), Chip8Error> {
+        if address as usize + data.len() > self.memory.len() {
+            return Err(Chip8Error::Memory(MemoryError::AddressOutOfBounds));
+        }
+        self.memory[address as usize..address as usize + data.len()].copy_from_slice(data);
+        self.record(address, data.len(), AccessKind::Write);
+        Ok(())
+    }
+}
+
+impl Default for Chip8Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryDevice for Chip8Memory {
+    type Error = Chip8Error;
+
+    type Address = u16;
+
+    type Word = u8;
+
+    fn read(&self, address: Self::Address) -> Result<Self::Word, Self::Error> {
+        let value = self
+            .memory
+            .get(address as usize)
+            .copied()
+            .ok_or(Chip8Error::Memory(MemoryError::AddressOutOfBounds))?;
+        self.record(address, 1, AccessKind::Read);
+        Ok(value)
+    }
+
+    fn write(&mut self, address: Self::Address, value: Self::Word) -> Result<(), Self::Error> {
+        let cell = self
+            .memory
+            .get_mut(address as usize)
+            .ok_or(Chip8Error::Memory(MemoryError::AddressOutOfBounds))?;
+        *cell = value;
+        self.record(address, 1, AccessKind::Write);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.memory.fill(0);
+    }
+
+    fn size(&self) -> usize {
+        self.memory.len()
+    }
+
+    fn snapshot(&self) -> Option<&dyn Snapshot> {
+        Some(self)
+    }
+
+    fn snapshot_mut(&mut self) -> Option<&mut dyn Snapshot> {
+        Some(self)
+    }
+}
+
+impl Snapshot for Chip8Memory {
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        writer.write_bytes(&self.memory);
+        Ok(())
+    }
+
+    /// Restores the memory contents, which must match the current size
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        let memory = reader.read_bytes()?;
+        if memory.len() != self.memory.len() {
+            return Err(SaveStateError::InvalidData(format!(
+                "{} bytes of memory, expected {}",
+                memory.len(),
+                self.memory.len()
+            )));
+        }
+        self.memory.copy_from_slice(memory);
+        Ok(())
+    }
+}
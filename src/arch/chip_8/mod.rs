@@ -1,518 +1,469 @@
-use crate::core::cpu::CpuState;
-use crate::core::cpu::CpuStateError;
-use crate::core::isa::{InstructionCodec, InstructionSet};
+mod asm;
+mod audio;
+mod cpu;
+mod disasm;
+mod display;
+mod error;
+mod font;
+mod gdb;
+mod instruction;
+mod isa;
+mod keypad;
+mod memory;
+mod quirks;
+mod registers;
+mod rpl;
+mod state;
+mod timers;
+
+pub use asm::{assemble, Chip8AsmError, Chip8Assembler};
+pub use audio::{
+    pattern_rate, write_wav, Chip8Audio, AMPLITUDE, DEFAULT_BEEP_FREQUENCY, DEFAULT_SAMPLE_RATE,
+};
+pub use cpu::Chip8Cpu;
+pub use disasm::{mnemonic, Chip8Listing, ListingLine};
+pub use display::{
+    Chip8Display, DISPLAY_HEIGHT, DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT, HIRES_DISPLAY_WIDTH,
+    PLANE_COUNT,
+};
+pub use error::Chip8Error;
+pub use font::{
+    BIG_FONT, BIG_FONT_GLYPH_SIZE, BIG_FONT_OFFSET, FONT, FONT_BASE, FONT_DATA_SIZE,
+    FONT_GLYPH_SIZE,
+};
+pub use instruction::{Chip8AddressingMode, Chip8Inst};
+pub use isa::Chip8InstructionSet;
+pub use keypad::{Chip8Keypad, KEY_COUNT};
+pub use memory::{Chip8Memory, MEMORY_SIZE, XO_MEMORY_SIZE};
+pub use quirks::Chip8Quirks;
+pub use registers::{
+    Chip8FlagsRegister, Chip8RegisterFile, FLAG_REGISTER, PROGRAM_START, REGISTER_COUNT,
+};
+pub use rpl::{InMemoryRplFlags, RplFlagStore, RPL_FLAG_COUNT};
+pub use state::{Chip8State, AUDIO_PATTERN_SIZE, DEFAULT_PITCH, STACK_DEPTH};
+pub use timers::{Chip8Timers, DEFAULT_INSTRUCTIONS_PER_FRAME, TIMER_FREQUENCY};
+
 use crate::{
-    arch::error::ArchError,
+    arch::Architecture,
     core::{
-        cpu::{Cpu, CpuError, FlagsRegister, RegisterError, RegisterFile},
-        isa::{AddressingError, AddressingMode, Instruction, InstructionError},
-        memory::{MemoryDevice, MemoryError},
+        cpu::{Cpu, CpuState},
+        isa::InstructionSet,
+        movie::{InputEvent, InputRecorder, InputTime, Movie, MoviePlayer},
+        savestate::{RewindBuffer, SaveState, SaveStateError, Snapshot, StateReader, StateWriter},
+        trace::TraceSink,
     },
 };
-use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
-
-pub enum Chip8Error {
-    Cpu(CpuError),
-    Memory(MemoryError),
-    Instruction(InstructionError),
-    InvalidState(String),
-    UnsupportedFeature(String),
-    TimingViolation,
-    Register(RegisterError),
-}
-
-impl From<ArchError> for Chip8Error {
-    fn from(err: ArchError) -> Self {
-        match err {
-            ArchError::Cpu(err) => Self::Cpu(err),
-            ArchError::Memory(err) => Self::Memory(err),
-            ArchError::Instruction(err) => Self::Instruction(err),
-            ArchError::InvalidState(msg) => Self::InvalidState(msg),
-            ArchError::UnsupportedFeature(feature) => Self::UnsupportedFeature(feature),
-            ArchError::TimingViolation => Self::TimingViolation,
-        }
-    }
-}
-
-impl From<CpuError> for Chip8Error {
-    fn from(err: CpuError) -> Self {
-        Self::Cpu(err)
-    }
-}
+use std::path::Path;
 
-impl From<Chip8Error> for CpuError {
-    fn from(err: Chip8Error) -> Self {
-        match err {
-            Chip8Error::Cpu(cpu_err) => cpu_err,
-            Chip8Error::Memory(_) => {
-                Self::State(CpuStateError::InvalidState("Memory error".into()))
-            }
-            Chip8Error::Instruction(_) => {
-                Self::State(CpuStateError::InvalidState("Instruction error".into()))
-            }
-            Chip8Error::InvalidState(msg) => Self::State(CpuStateError::InvalidState(msg)),
-            Chip8Error::UnsupportedFeature(msg) => Self::State(CpuStateError::InvalidState(msg)),
-            Chip8Error::TimingViolation => {
-                Self::State(CpuStateError::InvalidState("Timing violation".into()))
-            }
-            Chip8Error::Register(_register_error) => todo!(),
-        }
-    }
-}
-
-impl From<InstructionError> for Chip8Error {
-    fn from(err: InstructionError) -> Self {
-        Self::Instruction(err)
-    }
-}
-
-impl From<CpuStateError> for Chip8Error {
-    fn from(err: CpuStateError) -> Self {
-        Self::Cpu(CpuError::State(err))
-    }
-}
-
-impl From<RegisterError> for Chip8Error {
-    fn from(err: RegisterError) -> Self {
-        Self::Register(err)
-    }
-}
+/// Architecture name recorded in CHIP-8 save states
+const SAVE_STATE_ARCHITECTURE: &str = "CHIP-8";
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct Chip8Instruction(u16); // CHIP-8 uses 16-bit instructions
 
+/// A complete CHIP-8 machine
+///
+/// Owns the CPU together with everything it drives: memory, the framebuffer
+/// and the keypad live in the CPU's [`Chip8State`]. The machine itself paces
+/// execution into 60 Hz frames through [`Chip8Timers`], rendering a frame of
+/// [`Chip8Audio`] samples at every timer tick.
 #[derive(Debug)]
 pub struct Chip8 {
-    _cpu: Chip8Cpu,
-    _memory: Chip8Memory,
-}
-
-#[derive(Debug)]
-pub struct Chip8Cpu {
-    state: Chip8State, // Add a state field to hold the current state
-}
-
-impl Cpu for Chip8Cpu {
-    type Error = Chip8Error;
-    type ISA = Chip8InstructionSet;
-    type State = Chip8State;
-
-    fn instruction_set(&self) -> &Self::ISA {
-        todo!()
-    }
-
-    fn state(&self) -> &Self::State {
-        &self.state
-    }
-
-    fn state_mut(&mut self) -> &mut Self::State {
-        &mut self.state
-    }
-
-    fn reset(&mut self) -> Result<(), Self::Error> {
-        todo!()
-    }
-
-    fn step(&mut self) -> Result<u8, Self::Error> {
-        todo!()
-    }
-}
-
-#[derive(Debug)]
-pub struct Chip8Memory {
-    memory: [u8; 4096],
+    cpu: Chip8Cpu,
+    timers: Chip8Timers,
+    audio: Chip8Audio,
 }
 
-impl Chip8Memory {
-    pub fn new() -> Self {
-        Self { memory: [0; 4096] }
+impl Chip8 {
+    /// Creates a machine using the quirk preset matching `instruction_set`
+    pub fn new(instruction_set: Chip8InstructionSet) -> Self {
+        Self::with_quirks(instruction_set, instruction_set.into())
     }
 
-    pub fn read_slice(&self, address: u16, length: usize) -> Result<&[u8], Chip8Error> {
-        if address as usize + length > self.memory.len() {
-            return Err(Chip8Error::Memory(MemoryError::AddressOutOfBounds));
+    /// Creates a machine with an explicit quirk configuration
+    ///
+    /// The built-in fonts are installed at [`FONT_BASE`].
+    pub fn with_quirks(instruction_set: Chip8InstructionSet, quirks: Chip8Quirks) -> Self {
+        Self {
+            cpu: Chip8Cpu::with_quirks(instruction_set, quirks),
+            timers: Chip8Timers::default(),
+            audio: Chip8Audio::default(),
         }
-        Ok(&self.memory[address as usize..address as usize + length])
     }
-}
-
-impl Default for Chip8Memory {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl MemoryDevice for Chip8Memory {
-    type Error = Chip8Error;
-
-    type Address = u16;
-
-    type Word = u8;
 
-    fn read(&self, address: Self::Address) -> Result<Self::Word, Self::Error> {
-        Ok(self.memory[address as usize])
+    /// Installs the built-in fonts at `base` instead of [`FONT_BASE`]
+    pub fn install_font(&mut self, base: u16) -> Result<(), Chip8Error> {
+        self.cpu.install_font(base)
     }
 
-    fn write(&mut self, address: Self::Address, value: Self::Word) -> Result<(), Self::Error> {
-        self.memory[address as usize] = value;
+    /// Loads `rom` at [`PROGRAM_START`] and resets the machine to run it
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
+        self.cpu.load_rom(rom)?;
+        self.timers.reset();
+        self.audio.reset();
         Ok(())
     }
 
-    fn reset(&mut self) {
-        todo!()
+    /// Reads a ROM image from `path` and loads it like [`load_rom`](Self::load_rom)
+    pub fn load_rom_file(&mut self, path: impl AsRef<Path>) -> Result<(), Chip8Error> {
+        let rom = std::fs::read(path)?;
+        self.load_rom(&rom)
     }
 
-    fn size(&self) -> usize {
-        todo!()
+    pub fn quirks(&self) -> &Chip8Quirks {
+        self.cpu.quirks()
     }
-}
-
-#[derive(Debug)]
-pub enum Chip8AddressingMode {
-    Immediate,
-    Register,
-    Indirect,
-    Direct,
-}
-
-impl AddressingMode for Chip8AddressingMode {
-    type Register = u8;
-
-    type Address = u16;
 
-    type Error = AddressingError;
-
-    fn resolve(&self, _cpu: &impl CpuState) -> Result<Self::Address, Self::Error> {
-        todo!()
-    }
-
-    fn size(&self) -> usize {
-        todo!()
+    pub fn set_quirks(&mut self, quirks: Chip8Quirks) {
+        self.cpu.set_quirks(quirks);
     }
 
-    fn format(&self) -> String {
-        todo!()
+    /// Replaces the store backing the SUPER-CHIP RPL user flags (FX75/FX85)
+    pub fn set_rpl_flag_store(&mut self, store: Box<dyn RplFlagStore>) {
+        self.cpu.set_rpl_flag_store(store);
     }
 
-    fn is_valid_register(
-        &self,
-        _register: <Chip8AddressingMode as AddressingMode>::Register,
-    ) -> bool {
-        todo!()
-    }
-
-    fn is_valid_address(&self, _address: Self::Address) -> bool {
-        todo!()
-    }
-
-    fn bytes_needed(&self) -> usize {
-        todo!()
-    }
-}
-
-#[derive(Debug)]
-pub enum Chip8InstructionSet {
-    Chip8,
-    SuperChip,
-    XOChip,
-}
-
-pub struct Chip8Inst(u16);
-
-impl Instruction for Chip8Inst {
-    type Error = Chip8Error;
-    type Opcode = u8;
-    type Register = u8;
-    type Address = u16;
-    type Word = u8;
-
-    type AddressingMode = Chip8AddressingMode;
-
-    fn execute(
-        &self,
-        _cpu: &mut impl CpuState,
-        _memory: &mut impl MemoryDevice<
-            Address = Self::Address,
-            Word = <Self as Instruction>::Word,
-            Error = <Self as Instruction>::Error,
-        >,
-    ) -> Result<u8, <Self as Instruction>::Error> {
-        todo!()
-    }
-
-    fn cycles(&self) -> u8 {
-        todo!()
-    }
-
-    fn affects_flags(&self) -> bool {
-        todo!()
-    }
-
-    fn disassemble(&self) -> String {
-        todo!()
-    }
-}
-
-impl InstructionSet for Chip8InstructionSet {
-    type Error = Chip8Error;
-    type Instruction = Chip8Inst;
-    type Opcode = u8;
-    type Register = u8; // CHIP-8 uses 8-bit registers
-    type Address = u16; // CHIP-8 uses 16-bit addresses
-    type Word = u8; // CHIP-8 uses 8-bit words
-
-    fn name(&self) -> &str {
-        todo!()
-    }
-
-    fn word_size(&self) -> u8 {
-        todo!()
-    }
-
-    fn address_size(&self) -> u8 {
-        todo!()
-    }
-
-    fn register_count(&self) -> usize {
-        todo!()
+    /// Attaches a sink receiving every executed instruction, or detaches it
+    /// with `None`; see [`Chip8Cpu::set_trace_sink`]
+    pub fn set_trace_sink(
+        &mut self,
+        sink: Option<Box<dyn TraceSink>>,
+    ) -> Option<Box<dyn TraceSink>> {
+        self.cpu.set_trace_sink(sink)
+    }
+
+    /// Runs one 60 Hz frame
+    ///
+    /// Executes the remaining [`Chip8Timers::instructions_per_frame`]
+    /// instructions of the current frame and then
+    /// ticks the delay and sound timers once. With the
+    /// [`display_wait`](Chip8Quirks::display_wait) quirk the frame ends early
+    /// after the first DXYN, as the VIP waited for vertical blank to draw.
+    ///
+    /// # Returns
+    /// * `Ok(cycles)` - The number of cycles executed during the frame
+    /// * `Err(error)` - If an instruction failed; the timers are not ticked
+    pub fn run_frame(&mut self) -> Result<u32, Chip8Error> {
+        self.run_frame_with(|_| Ok(()))
+    }
+
+    /// Runs one frame like [`run_frame`](Self::run_frame), applying the
+    /// inputs of a movie as they fall due
+    pub fn replay_frame(&mut self, player: &mut MoviePlayer) -> Result<u32, Chip8Error> {
+        self.run_frame_with(|chip8| chip8.apply_due_inputs(player))
+    }
+
+    /// Runs one frame, calling `before_step` ahead of every instruction and of
+    /// the timer tick
+    fn run_frame_with(
+        &mut self,
+        mut before_step: impl FnMut(&mut Self) -> Result<(), Chip8Error>,
+    ) -> Result<u32, Chip8Error> {
+        let mut cycles = 0;
+        while !self.timers.frame_complete() {
+            before_step(self)?;
+            let draws = self.cpu.quirks().display_wait && self.cpu.fetch()?.opcode() == 0xD;
+            cycles += self.cpu.step()? as u32;
+            self.timers.count_instruction();
+            if draws {
+                break;
+            }
+        }
+        before_step(self)?;
+        self.tick_timers();
+        Ok(cycles)
+    }
+
+    /// Renders a frame of audio, then ticks the delay and sound timers once
+    ///
+    /// For hosts that step instructions themselves and keep their own 60 Hz
+    /// clock instead of using [`run_frame`](Self::run_frame).
+    pub fn tick_timers(&mut self) {
+        let state = self.cpu.state();
+        let pattern = state.audio_pattern();
+        let xo_pattern = *self.cpu.instruction_set() == Chip8InstructionSet::XOChip
+            && pattern.iter().any(|&byte| byte != 0);
+        self.audio.render_frame(
+            state.register_file().sound_timer(),
+            xo_pattern.then_some((pattern, state.pitch())),
+        );
+        self.timers.tick(self.cpu.state_mut().register_file_mut());
+    }
+
+    pub fn timers(&self) -> &Chip8Timers {
+        &self.timers
+    }
+
+    pub fn timers_mut(&mut self) -> &mut Chip8Timers {
+        &mut self.timers
+    }
+
+    /// Returns the audio source to pull rendered samples from
+    ///
+    /// XO-CHIP programs play their pattern buffer once F002 has loaded a
+    /// non-silent one; until then, and on other dialects, ST drives a beep.
+    pub fn audio(&self) -> &Chip8Audio {
+        &self.audio
+    }
+
+    pub fn audio_mut(&mut self) -> &mut Chip8Audio {
+        &mut self.audio
+    }
+
+    /// Captures the complete machine state
+    ///
+    /// The header records the instruction set, so the state can only be
+    /// restored into a machine of the same dialect.
+    pub fn save_state(&self) -> Result<SaveState, Chip8Error> {
+        Ok(SaveState::capture(
+            SAVE_STATE_ARCHITECTURE,
+            self.cpu.instruction_set().name(),
+            self,
+        )?)
+    }
+
+    /// Restores a state taken by [`save_state`](Self::save_state)
+    ///
+    /// Buffered audio samples are dropped. On error the machine is unchanged.
+    ///
+    /// # Returns
+    /// * `Err(Chip8Error::SaveState(error))` - If the state belongs to another
+    ///   machine or is malformed
+    pub fn load_state(&mut self, state: &SaveState) -> Result<(), Chip8Error> {
+        let instruction_set = *self.cpu.instruction_set();
+        state.restore(SAVE_STATE_ARCHITECTURE, instruction_set.name(), self)?;
+        Ok(())
     }
 
-    fn is_valid_opcode(&self, _opcode: Self::Opcode) -> bool {
-        todo!()
+    /// Records the machine state in `rewind` if one is due at the current
+    /// frame
+    ///
+    /// Call once after every [`run_frame`](Self::run_frame). Frames are
+    /// numbered by [`Chip8Timers::frames`].
+    ///
+    /// # Returns
+    /// * `Ok(true)` - If a state was recorded
+    pub fn record_rewind(&self, rewind: &mut RewindBuffer) -> Result<bool, Chip8Error> {
+        let frame = self.timers.frames();
+        if !rewind.is_due(frame) {
+            return Ok(false);
+        }
+        rewind.push(frame, self.save_state()?)?;
+        Ok(true)
+    }
+
+    /// Steps back one frame using the states recorded in `rewind`
+    ///
+    /// Restores the closest state at or before the previous frame and runs
+    /// forward to it. Frames replayed this way see the keypad as it was when
+    /// the state was recorded. Later states are dropped from `rewind`.
+    ///
+    /// # Returns
+    /// * `Ok(true)` - If the machine moved back a frame
+    /// * `Ok(false)` - If `rewind` holds no state that old
+    pub fn rewind_frame(&mut self, rewind: &mut RewindBuffer) -> Result<bool, Chip8Error> {
+        let Some(target) = self.timers.frames().checked_sub(1) else {
+            return Ok(false);
+        };
+        let Some((_, state)) = rewind.rewind_to(target)? else {
+            return Ok(false);
+        };
+        self.load_state(state)?;
+        while self.timers.frames() < target {
+            self.run_frame()?;
+        }
+        Ok(true)
+    }
+
+    /// Returns the current time for stamping recorded inputs
+    pub fn input_time(&self) -> InputTime {
+        InputTime::new(self.timers.frames(), self.cpu.state().cycles())
+    }
+
+    /// Applies a host input: key presses and releases go to the keypad
+    ///
+    /// # Returns
+    /// * `Err(Chip8Error::InvalidKey(key))` - If the key is not in `0x0..=0xF`
+    /// * `Err(Chip8Error::UnsupportedFeature(_))` - For port inputs, which
+    ///   CHIP-8 does not have
+    pub fn apply_input(&mut self, event: InputEvent) -> Result<(), Chip8Error> {
+        let key = |key: u16| u8::try_from(key).unwrap_or(u8::MAX);
+        match event {
+            InputEvent::Press(code) => self.press(key(code)),
+            InputEvent::Release(code) => self.release(key(code)),
+            InputEvent::Port { .. } => {
+                Err(Chip8Error::UnsupportedFeature("input ports".to_string()))
+            }
+        }
     }
 
-    fn categorize(&self, _opcode: Self::Opcode) -> crate::core::isa::InstructionCategory {
-        todo!()
+    /// Starts recording inputs from the current state
+    pub fn start_recording(&self) -> Result<InputRecorder, Chip8Error> {
+        Ok(InputRecorder::new(self.save_state()?))
     }
 
-    fn opcodes_in_category(
-        &self,
-        _category: crate::core::isa::InstructionCategory,
-    ) -> Vec<Self::Opcode> {
-        todo!()
+    /// Applies `event` like [`apply_input`](Self::apply_input) and records it
+    ///
+    /// Only inputs the machine accepted are recorded.
+    ///
+    /// # Returns
+    /// * `Err(Chip8Error::Movie(MovieError::OutOfOrder { .. }))` - If the
+    ///   machine went back in time since the last recorded input, e.g. through
+    ///   [`rewind_frame`](Self::rewind_frame) or
+    ///   [`load_state`](Self::load_state); the input is not applied. Start a
+    ///   new recording from the current state to go on.
+    pub fn record_input(
+        &mut self,
+        event: InputEvent,
+        recorder: &mut InputRecorder,
+    ) -> Result<(), Chip8Error> {
+        let time = self.input_time();
+        recorder.check_time(time)?;
+        self.apply_input(event)?;
+        recorder.record(time, event)?;
+        Ok(())
     }
-}
-
-impl InstructionCodec for Chip8Inst {
-    type Error = Chip8Error;
-    type Word = u8;
 
-    fn encode(&self) -> Vec<u8> {
-        let bytes = self.0.to_be_bytes();
-        vec![bytes[0], bytes[1]]
+    /// Ends a recording at the current state
+    pub fn finish_recording(&self, recorder: InputRecorder) -> Result<Movie, Chip8Error> {
+        Ok(recorder.finish(self.input_time(), &self.save_state()?))
+    }
+
+    /// Plays `movie` from its start state to its end
+    ///
+    /// Frames are run like [`run_frame`](Self::run_frame), so the recording
+    /// host must have paced the machine with it too. A recording ending in the
+    /// middle of a frame is finished instruction by instruction.
+    ///
+    /// # Returns
+    /// * `Ok(true)` - If the run ended in the recorded final state
+    /// * `Ok(false)` - If the run diverged from the recording
+    /// * `Err(error)` - If the start state does not fit this machine or an
+    ///   instruction failed
+    pub fn replay(&mut self, movie: &Movie) -> Result<bool, Chip8Error> {
+        self.load_state(movie.start())?;
+        let mut player = MoviePlayer::new(movie);
+        let end = movie.end();
+        while self.timers.frames() < end.frame {
+            self.replay_frame(&mut player)?;
+        }
+        while self.input_time() < end {
+            self.apply_due_inputs(&mut player)?;
+            self.cpu.step()?;
+            self.timers.count_instruction();
+        }
+        self.apply_due_inputs(&mut player)?;
+        Ok(movie.verify(&self.save_state()?))
     }
 
-    fn decode(bytes: &[Self::Word]) -> Result<Self, Self::Error> {
-        if bytes.len() < 2 {
-            return Err(Chip8Error::Instruction(InstructionError::InvalidLength));
+    fn apply_due_inputs(&mut self, player: &mut MoviePlayer) -> Result<(), Chip8Error> {
+        while let Some(event) = player.next_due(self.input_time()) {
+            self.apply_input(event)?;
         }
-        Ok(Self((bytes[0] as u16) << 8 | bytes[1] as u16))
-    }
-    fn size(&self) -> usize {
-        todo!()
+        Ok(())
     }
-}
-
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Chip8FlagsRegister(u8);
 
-impl Not for Chip8FlagsRegister {
-    type Output = Self;
-
-    fn not(self) -> Self::Output {
-        Chip8FlagsRegister(!self.0)
+    /// Returns the framebuffer for rendering
+    pub fn display(&self) -> &Chip8Display {
+        self.cpu.state().display()
     }
-}
 
-impl BitOrAssign for Chip8FlagsRegister {
-    fn bitor_assign(&mut self, other: Self) {
-        self.0 |= other.0;
+    /// Returns the framebuffer mutably, e.g. to acknowledge a frame with
+    /// [`Chip8Display::mark_clean`]
+    pub fn display_mut(&mut self) -> &mut Chip8Display {
+        self.cpu.state_mut().display_mut()
     }
-}
 
-impl BitAndAssign for Chip8FlagsRegister {
-    fn bitand_assign(&mut self, other: Self) {
-        self.0 &= other.0;
+    /// Returns the keypad state
+    pub fn keypad(&self) -> &Chip8Keypad {
+        self.cpu.state().keypad()
     }
-}
-
-impl BitAnd for Chip8FlagsRegister {
-    type Output = Self;
 
-    fn bitand(self, other: Self) -> Self::Output {
-        Chip8FlagsRegister(self.0 & other.0)
+    /// Presses `key` (`0x0`-`0xF`) on the keypad
+    pub fn press(&mut self, key: u8) -> Result<(), Chip8Error> {
+        self.cpu.state_mut().keypad_mut().press(key)
     }
-}
 
-impl BitOr for Chip8FlagsRegister {
-    type Output = Self;
-
-    fn bitor(self, other: Self) -> Self::Output {
-        Chip8FlagsRegister(self.0 | other.0)
+    /// Releases `key` (`0x0`-`0xF`) on the keypad
+    pub fn release(&mut self, key: u8) -> Result<(), Chip8Error> {
+        self.cpu.state_mut().keypad_mut().release(key)
     }
 }
 
-impl FlagsRegister for Chip8FlagsRegister {
-    fn get(&self) -> u8 {
-        self.0
-    }
-
-    fn set(&mut self, value: u8) {
-        self.0 = value;
-    }
-
-    fn update(&mut self, mask: u8, value: u8) {
-        self.0 = (self.0 & !mask) | (value & mask);
-    }
-
-    fn test(&self, mask: u8) -> bool {
-        (self.0 & mask) != 0
+impl Default for Chip8 {
+    fn default() -> Self {
+        Self::new(Chip8InstructionSet::default())
     }
 }
 
-#[derive(Debug)]
-pub struct Chip8RegisterFile {
-    _registers: [u8; 16],
-    _i: u16,
-    _pc: u16,
-    _sp: u16,
-    _dt: u8,
-    _st: u8,
-}
-
-impl RegisterFile for Chip8RegisterFile {
-    type Error = Chip8Error;
-    type Index = u8;
-    type Flags = Chip8FlagsRegister;
-    type Word = u8;
-    type Address = u16;
-
-    fn get(&self, _index: Self::Index) -> Result<Self::Word, Self::Error> {
-        todo!()
-    }
-
-    fn set(&mut self, _index: Self::Index, _value: Self::Word) -> Result<(), Self::Error> {
-        todo!()
+impl Snapshot for Chip8 {
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        self.cpu.write_state(writer)?;
+        self.timers.write_state(writer)
     }
 
-    fn registers(&self) -> &[Self::Word] {
-        todo!()
-    }
-
-    fn registers_mut(&mut self) -> &mut [Self::Word] {
-        todo!()
-    }
-
-    fn register_count(&self) -> usize {
-        todo!()
-    }
-
-    fn program_counter(&self) -> Self::Address {
-        todo!()
-    }
-
-    fn set_program_counter(&mut self, _value: Self::Address) {
-        todo!()
-    }
-
-    fn stack_pointer(&self) -> Self::Address {
-        todo!()
-    }
-
-    fn set_stack_pointer(&mut self, _value: Self::Address) {
-        todo!()
-    }
-
-    fn flags(&self) -> Self::Flags {
-        todo!()
-    }
-
-    fn update_flags(&mut self, _mask: Self::Flags, _value: Self::Flags) {
-        todo!()
-    }
-
-    fn reset(&mut self) {
-        todo!()
-    }
-
-    fn test_flags(&self, _mask: Self::Flags) -> bool {
-        (self.flags() & _mask) == _mask
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        self.cpu.read_state(reader)?;
+        self.timers.read_state(reader)?;
+        self.audio.reset();
+        Ok(())
     }
 }
 
-#[derive(Debug)]
-pub struct Chip8State {
-    register_file: Chip8RegisterFile,
-}
-
-impl CpuState for Chip8State {
-    type Address = u16; // CHIP-8 uses 16-bit addresses
-    type Word = u8; // CHIP-8 uses 8-bit words
-    type Memory = Chip8Memory;
+impl Architecture for Chip8 {
     type Error = Chip8Error;
-    type Register = u8; // CHIP-8 uses 8-bit registers
-
-    fn read_register(&self, _reg: Self::Register) -> Result<Self::Word, Self::Error> {
-        todo!()
-    }
-
-    fn write_register(
-        &mut self,
-        _reg: Self::Register,
-        _value: Self::Word,
-    ) -> Result<(), Self::Error> {
-        todo!()
-    }
-
-    fn get_program_counter(&self) -> Self::Address {
-        todo!()
-    }
-
-    fn set_program_counter(&mut self, _addr: Self::Address) -> Result<(), Self::Error> {
-        todo!()
-    }
-
-    fn get_stack_pointer(&self) -> Self::Address {
-        todo!()
-    }
-
-    fn set_stack_pointer(&mut self, _addr: Self::Address) -> Result<(), Self::Error> {
-        todo!()
-    }
+    type CPU = Chip8Cpu;
+    type ISA = Chip8InstructionSet;
+    type State = Chip8State;
+    type Memory = Chip8Memory;
 
-    fn get_flags(&self) -> u8 {
-        todo!()
+    fn name(&self) -> &str {
+        self.cpu.instruction_set().name()
     }
 
-    fn set_flags(&mut self, _flags: u8) -> Result<(), Self::Error> {
-        todo!()
+    fn cpu(&self) -> &Self::CPU {
+        &self.cpu
     }
 
-    fn test_flag(&self, _flag: u8) -> Result<bool, Self::Error> {
-        todo!()
+    fn cpu_mut(&mut self) -> &mut Self::CPU {
+        &mut self.cpu
     }
 
     fn memory(&self) -> &Self::Memory {
-        todo!()
+        self.cpu.state().memory()
     }
 
     fn memory_mut(&mut self) -> &mut Self::Memory {
-        todo!()
+        self.cpu.state_mut().memory_mut()
+    }
+
+    /// Resets the CPU, display, keypad, timers and audio, keeping the loaded ROM
+    fn reset(&mut self) -> Result<(), Self::Error> {
+        self.cpu.reset()?;
+        self.timers.reset();
+        self.audio.reset();
+        Ok(())
     }
 
-    fn cycles(&self) -> u64 {
-        todo!()
+    /// Executes one instruction
+    ///
+    /// The delay and sound timers tick automatically once a frame's worth of
+    /// instructions has run, so generic drivers keep the 60 Hz timing.
+    fn step(&mut self) -> Result<u8, Self::Error> {
+        let cycles = self.cpu.step()?;
+        self.timers.count_instruction();
+        if self.timers.frame_complete() {
+            self.tick_timers();
+        }
+        Ok(cycles)
     }
 
-    fn add_cycles(&mut self, _cycles: u8) {
-        todo!()
+    fn state(&self) -> &Self::State {
+        self.cpu.state()
     }
-}
 
-impl Chip8State {
-    pub fn pc(&self) -> u16 {
-        self.register_file._pc // Assuming `pc` is a field in `Chip8RegisterFile`
+    fn instruction_set(&self) -> &Self::ISA {
+        self.cpu.instruction_set()
     }
 }
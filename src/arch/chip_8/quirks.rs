@@ -0,0 +1,112 @@
+use super::Chip8InstructionSet;
+use crate::core::savestate::{SaveStateError, Snapshot, StateReader, StateWriter};
+
+/// Behavioural differences between CHIP-8 interpreters
+///
+/// ROMs written for one interpreter often rely on its particular behaviour,
+/// so each of these can be toggled independently. The named presets match the
+/// interpreters the [`Chip8InstructionSet`] variants are modelled on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chip8Quirks {
+    /// 8XY1/8XY2/8XY3 reset VF to 0
+    pub vf_reset: bool,
+    /// FX55/FX65 leave I pointing past the last register accessed
+    pub memory_increments_i: bool,
+    /// DXYN waits for the next frame, limiting drawing to one sprite per frame
+    pub display_wait: bool,
+    /// Sprites are clipped at the display edges instead of wrapping around
+    pub clip_sprites: bool,
+    /// 8XY6/8XYE shift VX in place instead of loading VY shifted
+    pub shift_in_place: bool,
+    /// BNNN behaves as BXNN, jumping to XNN + VX instead of NNN + V0
+    pub jump_uses_vx: bool,
+}
+
+impl Chip8Quirks {
+    /// The original COSMAC VIP interpreter
+    pub fn vip() -> Self {
+        Self {
+            vf_reset: true,
+            memory_increments_i: true,
+            display_wait: true,
+            clip_sprites: true,
+            shift_in_place: false,
+            jump_uses_vx: false,
+        }
+    }
+
+    /// SUPER-CHIP 1.1 on the HP 48
+    pub fn schip() -> Self {
+        Self {
+            vf_reset: false,
+            memory_increments_i: false,
+            display_wait: false,
+            clip_sprites: true,
+            shift_in_place: true,
+            jump_uses_vx: true,
+        }
+    }
+
+    /// XO-CHIP as implemented by Octo
+    pub fn xochip() -> Self {
+        Self {
+            vf_reset: false,
+            memory_increments_i: true,
+            display_wait: false,
+            clip_sprites: false,
+            shift_in_place: false,
+            jump_uses_vx: false,
+        }
+    }
+
+    /// The behaviour most modern interpreters settled on, inherited from
+    /// CHIP-48
+    pub fn modern() -> Self {
+        Self {
+            vf_reset: false,
+            memory_increments_i: false,
+            display_wait: false,
+            clip_sprites: true,
+            shift_in_place: true,
+            jump_uses_vx: false,
+        }
+    }
+}
+
+impl Default for Chip8Quirks {
+    fn default() -> Self {
+        Self::vip()
+    }
+}
+
+impl From<Chip8InstructionSet> for Chip8Quirks {
+    fn from(instruction_set: Chip8InstructionSet) -> Self {
+        match instruction_set {
+            Chip8InstructionSet::Chip8 => Self::vip(),
+            Chip8InstructionSet::SuperChip => Self::schip(),
+            Chip8InstructionSet::XOChip => Self::xochip(),
+        }
+    }
+}
+
+impl Snapshot for Chip8Quirks {
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        writer.write(&self.vf_reset);
+        writer.write(&self.memory_increments_i);
+        writer.write(&self.display_wait);
+        writer.write(&self.clip_sprites);
+        writer.write(&self.shift_in_place);
+        writer.write(&self.jump_uses_vx);
+        Ok(())
+    }
+
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        self.vf_reset = reader.read()?;
+        self.memory_increments_i = reader.read()?;
+        self.display_wait = reader.read()?;
+        self.clip_sprites = reader.read()?;
+        self.shift_in_place = reader.read()?;
+        self.jump_uses_vx = reader.read()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+use super::Chip8Error;
+use crate::core::{
+    cpu::{FlagsRegister, RegisterError, RegisterFile},
+    savestate::{SaveStateError, Snapshot, StateReader, StateWriter},
+};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+
+/// Number of general-purpose registers (V0-VF)
+pub const REGISTER_COUNT: usize = 16;
+/// Index of VF, which doubles as the carry/borrow/collision flag
+pub const FLAG_REGISTER: u8 = 0xF;
+/// Address the program counter starts at after a reset
+pub const PROGRAM_START: u16 = 0x200;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chip8FlagsRegister(u8);
+
+impl Chip8FlagsRegister {
+    pub fn new(value: u8) -> Self {
+        Self(value)
+    }
+}
+
+impl Not for Chip8FlagsRegister {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Chip8FlagsRegister(!self.0)
+    }
+}
+
+impl BitOrAssign for Chip8FlagsRegister {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+impl BitAndAssign for Chip8FlagsRegister {
+    fn bitand_assign(&mut self, other: Self) {
+        self.0 &= other.0;
+    }
+}
+
+impl BitAnd for Chip8FlagsRegister {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self::Output {
+        Chip8FlagsRegister(self.0 & other.0)
+    }
+}
+
+impl BitOr for Chip8FlagsRegister {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self::Output {
+        Chip8FlagsRegister(self.0 | other.0)
+    }
+}
+
+impl FlagsRegister for Chip8FlagsRegister {
+    fn get(&self) -> u8 {
+        self.0
+    }
+
+    fn set(&mut self, value: u8) {
+        self.0 = value;
+    }
+
+    fn update(&mut self, mask: u8, value: u8) {
+        self.0 = (self.0 & !mask) | (value & mask);
+    }
+
+    fn test(&self, mask: u8) -> bool {
+        (self.0 & mask) != 0
+    }
+}
+
+/// The CHIP-8 register file
+///
+/// Holds V0-VF, the 16-bit index register I, the program counter, the stack
+/// pointer and the delay/sound timers. VF is exposed as the flags register.
+#[derive(Debug, Clone)]
+pub struct Chip8RegisterFile {
+    registers: [u8; REGISTER_COUNT],
+    i: u16,
+    pc: u16,
+    sp: u16,
+    dt: u8,
+    st: u8,
+}
+
+impl Chip8RegisterFile {
+    pub fn new() -> Self {
+        Self {
+            registers: [0; REGISTER_COUNT],
+            i: 0,
+            pc: PROGRAM_START,
+            sp: 0,
+            dt: 0,
+            st: 0,
+        }
+    }
+
+    /// Returns the index register I
+    pub fn i(&self) -> u16 {
+        self.i
+    }
+
+    /// Sets the index register I
+    pub fn set_i(&mut self, value: u16) {
+        self.i = value;
+    }
+
+    /// Returns the delay timer
+    pub fn delay_timer(&self) -> u8 {
+        self.dt
+    }
+
+    /// Sets the delay timer
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.dt = value;
+    }
+
+    /// Returns the sound timer
+    pub fn sound_timer(&self) -> u8 {
+        self.st
+    }
+
+    /// Sets the sound timer
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.st = value;
+    }
+}
+
+impl Default for Chip8RegisterFile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegisterFile for Chip8RegisterFile {
+    type Error = Chip8Error;
+    type Index = u8;
+    type Flags = Chip8FlagsRegister;
+    type Word = u8;
+    type Address = u16;
+
+    fn get(&self, index: Self::Index) -> Result<Self::Word, Self::Error> {
+        self.registers
+            .get(index as usize)
+            .copied()
+            .ok_or(RegisterError::InvalidIndex(index).into())
+    }
+
+    fn set(&mut self, index: Self::Index, value: Self::Word) -> Result<(), Self::Error> {
+        let register = self
+            .registers
+            .get_mut(index as usize)
+            .ok_or(RegisterError::InvalidIndex(index))?;
+        *register = value;
+        Ok(())
+    }
+
+    fn registers(&self) -> &[Self::Word] {
+        &self.registers
+    }
+
+    fn registers_mut(&mut self) -> &mut [Self::Word] {
+        &mut self.registers
+    }
+
+    fn register_count(&self) -> usize {
+        REGISTER_COUNT
+    }
+
+    fn program_counter(&self) -> Self::Address {
+        self.pc
+    }
+
+    fn set_program_counter(&mut self, value: Self::Address) {
+        self.pc = value;
+    }
+
+    fn stack_pointer(&self) -> Self::Address {
+        self.sp
+    }
+
+    fn set_stack_pointer(&mut self, value: Self::Address) {
+        self.sp = value;
+    }
+
+    fn flags(&self) -> Self::Flags {
+        Chip8FlagsRegister(self.registers[FLAG_REGISTER as usize])
+    }
+
+    fn update_flags(&mut self, mask: Self::Flags, value: Self::Flags) {
+        let mut flags = self.flags();
+        flags.update(mask.0, value.0);
+        self.registers[FLAG_REGISTER as usize] = flags.0;
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn test_flags(&self, mask: Self::Flags) -> bool {
+        (self.flags() & mask) == mask
+    }
+}
+
+impl Snapshot for Chip8RegisterFile {
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        writer.write_raw(&self.registers);
+        writer.write(&self.i);
+        writer.write(&self.pc);
+        writer.write(&self.sp);
+        writer.write(&self.dt);
+        writer.write(&self.st);
+        Ok(())
+    }
+
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        self.registers = reader.read_array()?;
+        self.i = reader.read()?;
+        self.pc = reader.read()?;
+        self.sp = reader.read()?;
+        self.dt = reader.read()?;
+        self.st = reader.read()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt::Debug;
+
+/// Number of RPL user flags available to FX75/FX85
+pub const RPL_FLAG_COUNT: usize = 16;
+
+/// Host-provided persistence for the SUPER-CHIP RPL user flags
+///
+/// On the HP 48 these flags survived between runs, and games use them for
+/// things like high scores. Implement this to back them with a file or any
+/// other storage that outlives the machine.
+pub trait RplFlagStore: Debug {
+    /// Fills `flags` with the stored values, starting at flag 0
+    fn load(&mut self, flags: &mut [u8]);
+
+    /// Stores `flags`, starting at flag 0
+    fn save(&mut self, flags: &[u8]);
+}
+
+/// An [`RplFlagStore`] that only lives as long as the machine
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryRplFlags {
+    flags: [u8; RPL_FLAG_COUNT],
+}
+
+impl InMemoryRplFlags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns all stored flags
+    pub fn flags(&self) -> &[u8] {
+        &self.flags
+    }
+}
+
+impl RplFlagStore for InMemoryRplFlags {
+    fn load(&mut self, flags: &mut [u8]) {
+        let count = flags.len().min(RPL_FLAG_COUNT);
+        flags[..count].copy_from_slice(&self.flags[..count]);
+    }
+
+    fn save(&mut self, flags: &[u8]) {
+        let count = flags.len().min(RPL_FLAG_COUNT);
+        self.flags[..count].copy_from_slice(&flags[..count]);
+    }
+}
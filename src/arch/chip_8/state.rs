@@ -0,0 +1,273 @@
+use super::{Chip8Display, Chip8Error, Chip8Keypad, Chip8Memory, Chip8RegisterFile, FLAG_REGISTER};
+use crate::core::{
+    cpu::{CpuError, CpuState, FlagsRegister, RegisterFile},
+    savestate::{SaveStateError, Snapshot, StateReader, StateWriter},
+};
+
+/// Depth of the CHIP-8 call stack
+pub const STACK_DEPTH: usize = 16;
+/// Size of the XO-CHIP audio pattern buffer in bytes (128 1-bit samples)
+pub const AUDIO_PATTERN_SIZE: usize = 16;
+/// XO-CHIP pitch register value after reset, which plays the pattern at 4 kHz
+pub const DEFAULT_PITCH: u8 = 64;
+
+/// Complete execution state of a CHIP-8 machine
+#[derive(Debug)]
+pub struct Chip8State {
+    register_file: Chip8RegisterFile,
+    memory: Chip8Memory,
+    stack: [u16; STACK_DEPTH],
+    cycles: u64,
+    display: Chip8Display,
+    keypad: Chip8Keypad,
+    rng: u32,
+    halted: bool,
+    audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+    pitch: u8,
+}
+
+impl Chip8State {
+    /// Creates a state with the standard 4 KiB memory
+    pub fn new() -> Self {
+        Self::with_memory(Chip8Memory::new())
+    }
+
+    pub fn with_memory(memory: Chip8Memory) -> Self {
+        Self {
+            register_file: Chip8RegisterFile::new(),
+            memory,
+            stack: [0; STACK_DEPTH],
+            cycles: 0,
+            display: Chip8Display::new(),
+            keypad: Chip8Keypad::new(),
+            rng: 0x2545_F491,
+            halted: false,
+            audio_pattern: [0; AUDIO_PATTERN_SIZE],
+            pitch: DEFAULT_PITCH,
+        }
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.register_file.program_counter()
+    }
+
+    pub fn register_file(&self) -> &Chip8RegisterFile {
+        &self.register_file
+    }
+
+    pub fn register_file_mut(&mut self) -> &mut Chip8RegisterFile {
+        &mut self.register_file
+    }
+
+    pub fn display(&self) -> &Chip8Display {
+        &self.display
+    }
+
+    pub fn display_mut(&mut self) -> &mut Chip8Display {
+        &mut self.display
+    }
+
+    pub fn keypad(&self) -> &Chip8Keypad {
+        &self.keypad
+    }
+
+    pub fn keypad_mut(&mut self) -> &mut Chip8Keypad {
+        &mut self.keypad
+    }
+
+    /// Returns the return addresses currently on the call stack, oldest first
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.register_file.stack_pointer() as usize]
+    }
+
+    /// Resets registers, stack, display and input while leaving memory intact
+    pub fn reset(&mut self) {
+        self.register_file.reset();
+        self.stack = [0; STACK_DEPTH];
+        self.cycles = 0;
+        self.display.reset();
+        self.keypad.reset();
+        self.halted = false;
+        self.audio_pattern = [0; AUDIO_PATTERN_SIZE];
+        self.pitch = DEFAULT_PITCH;
+    }
+
+    /// Returns the XO-CHIP audio pattern buffer loaded by F002
+    pub fn audio_pattern(&self) -> &[u8; AUDIO_PATTERN_SIZE] {
+        &self.audio_pattern
+    }
+
+    /// Returns the XO-CHIP pitch register set by FX3A
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    /// Returns true once the program has exited through 00FD
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub(crate) fn halt(&mut self) {
+        self.halted = true;
+    }
+
+    pub(crate) fn set_audio_pattern(&mut self, pattern: [u8; AUDIO_PATTERN_SIZE]) {
+        self.audio_pattern = pattern;
+    }
+
+    pub(crate) fn set_pitch(&mut self, pitch: u8) {
+        self.pitch = pitch;
+    }
+
+    pub(crate) fn v(&self, register: u8) -> u8 {
+        self.register_file.registers()[register as usize & 0xF]
+    }
+
+    pub(crate) fn set_v(&mut self, register: u8, value: u8) {
+        self.register_file.registers_mut()[register as usize & 0xF] = value;
+    }
+
+    pub(crate) fn push(&mut self, address: u16) -> Result<(), Chip8Error> {
+        let sp = self.register_file.stack_pointer() as usize;
+        if sp >= STACK_DEPTH {
+            return Err(CpuError::StackOverflow.into());
+        }
+        self.stack[sp] = address;
+        self.register_file.set_stack_pointer(sp as u16 + 1);
+        Ok(())
+    }
+
+    pub(crate) fn pop(&mut self) -> Result<u16, Chip8Error> {
+        let sp = self.register_file.stack_pointer() as usize;
+        if sp == 0 {
+            return Err(CpuError::StackUnderflow.into());
+        }
+        self.register_file.set_stack_pointer(sp as u16 - 1);
+        Ok(self.stack[sp - 1])
+    }
+
+    /// Advances the xorshift generator backing CXNN and returns a byte
+    pub(crate) fn random_byte(&mut self) -> u8 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        (x >> 24) as u8
+    }
+}
+
+impl Default for Chip8State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CpuState for Chip8State {
+    type Address = u16; // CHIP-8 uses 16-bit addresses
+    type Word = u8; // CHIP-8 uses 8-bit words
+    type Memory = Chip8Memory;
+    type Error = Chip8Error;
+    type Register = u8; // CHIP-8 uses 8-bit registers
+
+    fn read_register(&self, reg: Self::Register) -> Result<Self::Word, Self::Error> {
+        self.register_file.get(reg)
+    }
+
+    fn write_register(
+        &mut self,
+        reg: Self::Register,
+        value: Self::Word,
+    ) -> Result<(), Self::Error> {
+        self.register_file.set(reg, value)
+    }
+
+    fn get_program_counter(&self) -> Self::Address {
+        self.register_file.program_counter()
+    }
+
+    fn set_program_counter(&mut self, addr: Self::Address) -> Result<(), Self::Error> {
+        self.register_file.set_program_counter(addr);
+        Ok(())
+    }
+
+    fn get_stack_pointer(&self) -> Self::Address {
+        self.register_file.stack_pointer()
+    }
+
+    fn set_stack_pointer(&mut self, addr: Self::Address) -> Result<(), Self::Error> {
+        if addr as usize > STACK_DEPTH {
+            return Err(CpuError::StackOverflow.into());
+        }
+        self.register_file.set_stack_pointer(addr);
+        Ok(())
+    }
+
+    fn get_flags(&self) -> u8 {
+        self.register_file.flags().get()
+    }
+
+    fn set_flags(&mut self, flags: u8) -> Result<(), Self::Error> {
+        self.register_file.set(FLAG_REGISTER, flags)
+    }
+
+    fn test_flag(&self, flag: u8) -> Result<bool, Self::Error> {
+        Ok(self.register_file.flags().test(flag))
+    }
+
+    fn memory(&self) -> &Self::Memory {
+        &self.memory
+    }
+
+    fn memory_mut(&mut self) -> &mut Self::Memory {
+        &mut self.memory
+    }
+
+    fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    fn add_cycles(&mut self, cycles: u8) {
+        self.cycles += cycles as u64;
+    }
+}
+
+impl Snapshot for Chip8State {
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        self.register_file.write_state(writer)?;
+        self.memory.write_state(writer)?;
+        for address in &self.stack {
+            writer.write(address);
+        }
+        writer.write(&self.cycles);
+        self.display.write_state(writer)?;
+        self.keypad.write_state(writer)?;
+        writer.write(&self.rng);
+        writer.write(&self.halted);
+        writer.write_raw(&self.audio_pattern);
+        writer.write(&self.pitch);
+        Ok(())
+    }
+
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        self.register_file.read_state(reader)?;
+        if self.register_file.stack_pointer() as usize > STACK_DEPTH {
+            return Err(SaveStateError::InvalidData(format!(
+                "stack pointer {} exceeds the stack depth",
+                self.register_file.stack_pointer()
+            )));
+        }
+        self.memory.read_state(reader)?;
+        for address in &mut self.stack {
+            *address = reader.read()?;
+        }
+        self.cycles = reader.read()?;
+        self.display.read_state(reader)?;
+        self.keypad.read_state(reader)?;
+        self.rng = reader.read()?;
+        self.halted = reader.read()?;
+        self.audio_pattern = reader.read_array()?;
+        self.pitch = reader.read()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use super::Chip8RegisterFile;
+use crate::core::savestate::{SaveStateError, Snapshot, StateReader, StateWriter};
+
+/// Rate at which the delay and sound timers count down
+pub const TIMER_FREQUENCY: u32 = 60;
+/// Instructions executed per 60 Hz frame unless configured otherwise (~600 Hz)
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: u32 = 10;
+
+/// Frame pacing for the 60 Hz delay and sound timers
+///
+/// CHIP-8 has no defined instruction rate, so the timers are decoupled from
+/// instruction stepping: a frame executes a configurable number of
+/// instructions and then ticks the timers once.
+#[derive(Debug, Clone)]
+pub struct Chip8Timers {
+    instructions_per_frame: u32,
+    /// Instructions executed since the last tick
+    executed: u32,
+    frames: u64,
+}
+
+impl Chip8Timers {
+    pub fn new(instructions_per_frame: u32) -> Self {
+        Self {
+            instructions_per_frame,
+            executed: 0,
+            frames: 0,
+        }
+    }
+
+    /// Returns how many instructions run between timer ticks
+    pub fn instructions_per_frame(&self) -> u32 {
+        self.instructions_per_frame
+    }
+
+    /// Sets how many instructions run between timer ticks
+    pub fn set_instructions_per_frame(&mut self, instructions_per_frame: u32) {
+        self.instructions_per_frame = instructions_per_frame;
+    }
+
+    /// Returns the number of timer ticks since the last reset
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Records that an instruction was executed in the current frame
+    pub fn count_instruction(&mut self) {
+        self.executed += 1;
+    }
+
+    /// Returns true once the current frame has run all its instructions
+    pub fn frame_complete(&self) -> bool {
+        self.executed >= self.instructions_per_frame
+    }
+
+    /// Counts DT and ST down by one, stopping at zero, and starts a new frame
+    pub fn tick(&mut self, registers: &mut Chip8RegisterFile) {
+        registers.set_delay_timer(registers.delay_timer().saturating_sub(1));
+        registers.set_sound_timer(registers.sound_timer().saturating_sub(1));
+        self.executed = 0;
+        self.frames += 1;
+    }
+
+    /// Restarts the frame count, keeping the configured instruction rate
+    pub fn reset(&mut self) {
+        self.executed = 0;
+        self.frames = 0;
+    }
+}
+
+impl Default for Chip8Timers {
+    fn default() -> Self {
+        Self::new(DEFAULT_INSTRUCTIONS_PER_FRAME)
+    }
+}
+
+impl Snapshot for Chip8Timers {
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        writer.write(&self.instructions_per_frame);
+        writer.write(&self.executed);
+        writer.write(&self.frames);
+        Ok(())
+    }
+
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        self.instructions_per_frame = reader.read()?;
+        self.executed = reader.read()?;
+        self.frames = reader.read()?;
+        Ok(())
+    }
+}
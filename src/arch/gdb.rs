@@ -0,0 +1,556 @@
+//! GDB remote serial protocol stub
+//!
+//! [`GdbStub`] serves one debugger session for any [`Architecture`] that
+//! describes its registers through [`GdbTarget`]. It supports register and
+//! memory access, software breakpoints (`Z0`/`Z1`), single-step and continue
+//! with Ctrl-C interrupts, and publishes a `target.xml` register description.
+//! Memory is accessed a byte at a time through [`MemoryDevice`], so the
+//! architecture's memory must be byte-addressed.
+//!
+//! ```no_run
+//! use tiny_computers::arch::chip_8::Chip8;
+//! use tiny_computers::arch::gdb::GdbStub;
+//! use std::net::TcpListener;
+//!
+//! let mut machine = Chip8::default();
+//! machine.load_rom_file("game.ch8").unwrap();
+//!
+//! let listener = TcpListener::bind("127.0.0.1:1234").unwrap();
+//! let (stream, _) = listener.accept().unwrap();
+//! GdbStub::new(&mut machine).serve(stream).unwrap();
+//! ```
+
+use crate::arch::Architecture;
+use crate::core::cpu::{CpuError, CpuState};
+use crate::core::memory::MemoryDevice;
+use std::collections::{BTreeSet, VecDeque};
+use std::io::{ErrorKind, Read, Result as IoResult, Write};
+use std::net::TcpStream;
+
+/// Instructions run between checks for a Ctrl-C while continuing
+const INTERRUPT_POLL_INTERVAL: u32 = 1024;
+
+/// Largest packet the stub accepts, advertised through `qSupported`
+const PACKET_SIZE: usize = 0x4000;
+
+/// One register in GDB's register numbering
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GdbRegister {
+    pub name: &'static str,
+    pub bits: usize,
+    /// GDB type name, e.g. `int`, `code_ptr` or `data_ptr`
+    pub kind: &'static str,
+}
+
+/// The architecture-specific register view a [`GdbStub`] needs
+pub trait GdbTarget: Architecture {
+    /// Returns the registers in the order `g` packets list them
+    fn gdb_registers(&self) -> &'static [GdbRegister];
+
+    /// Reads register `index` as little-endian bytes
+    fn read_gdb_register(&self, index: usize) -> Option<Vec<u8>>;
+
+    /// Writes register `index` from little-endian bytes, returning false if
+    /// the register does not exist or cannot be written
+    fn write_gdb_register(&mut self, index: usize, value: &[u8]) -> bool;
+}
+
+/// A debugger connection that can be read without blocking while the target
+/// runs
+pub trait GdbConnection: Read + Write {
+    /// Switches reads between blocking and failing with
+    /// [`ErrorKind::WouldBlock`] when nothing is waiting
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()>;
+}
+
+impl GdbConnection for TcpStream {
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl GdbConnection for std::os::unix::net::UnixStream {
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        std::os::unix::net::UnixStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+/// Buffers what the client sends while the target runs
+///
+/// Checking for a Ctrl-C during a continue reads whatever is waiting; bytes
+/// other than the interrupt stay buffered and are read as packets later.
+struct Client<C: GdbConnection> {
+    connection: C,
+    pending: VecDeque<u8>,
+    /// The client closed the connection during a poll
+    closed: bool,
+}
+
+impl<C: GdbConnection> Client<C> {
+    fn new(connection: C) -> Self {
+        Self {
+            connection,
+            pending: VecDeque::new(),
+            closed: false,
+        }
+    }
+
+    /// Returns true if the client sent an interrupt (0x03), without blocking
+    fn poll_interrupt(&mut self) -> IoResult<bool> {
+        if !self.closed {
+            self.connection.set_nonblocking(true)?;
+            let result = self.read_waiting();
+            self.connection.set_nonblocking(false)?;
+            result?;
+        }
+        let interrupt = self.pending.iter().position(|&byte| byte == 0x03);
+        Ok(interrupt
+            .and_then(|index| self.pending.remove(index))
+            .is_some())
+    }
+
+    /// Moves every byte waiting on the connection into `pending`
+    fn read_waiting(&mut self) -> IoResult<()> {
+        let mut buffer = [0; 256];
+        loop {
+            match self.connection.read(&mut buffer) {
+                Ok(0) => {
+                    self.closed = true;
+                    return Ok(());
+                }
+                Ok(count) => self.pending.extend(&buffer[..count]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+impl<C: GdbConnection> Read for Client<C> {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        if !self.pending.is_empty() {
+            return self.pending.read(buffer);
+        }
+        if self.closed {
+            return Ok(0);
+        }
+        self.connection.read(buffer)
+    }
+}
+
+impl<C: GdbConnection> Write for Client<C> {
+    fn write(&mut self, buffer: &[u8]) -> IoResult<usize> {
+        self.connection.write(buffer)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.connection.flush()
+    }
+}
+
+/// Why the target stopped, as reported to the debugger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stop {
+    /// SIGTRAP: a step finished or a breakpoint was hit
+    Trap,
+    /// SIGINT: the debugger interrupted a continue
+    Interrupt,
+    /// SIGSEGV: the target raised an error
+    Fault,
+    /// The CPU halted, which the debugger sees as the process exiting
+    Exited,
+}
+
+impl Stop {
+    fn reply(&self) -> &'static str {
+        match self {
+            Self::Trap => "S05",
+            Self::Interrupt => "S02",
+            Self::Fault => "S0b",
+            Self::Exited => "W00",
+        }
+    }
+}
+
+/// Serves the GDB remote serial protocol for a target
+pub struct GdbStub<'a, T: GdbTarget> {
+    target: &'a mut T,
+    breakpoints: BTreeSet<u64>,
+    no_ack: bool,
+    /// Why the target last stopped, reported again by `?`
+    stop: Stop,
+}
+
+impl<'a, T> GdbStub<'a, T>
+where
+    T: GdbTarget,
+    T::Error: Into<CpuError>,
+    T::Memory: MemoryDevice<Word = u8>,
+    <T::Memory as MemoryDevice>::Address: TryFrom<u64>,
+    <T::State as CpuState>::Address: Into<u64>,
+{
+    pub fn new(target: &'a mut T) -> Self {
+        Self {
+            target,
+            breakpoints: BTreeSet::new(),
+            no_ack: false,
+            stop: Stop::Trap,
+        }
+    }
+
+    /// Handles packets from `connection` until the debugger detaches, kills
+    /// the target or disconnects
+    pub fn serve(&mut self, connection: impl GdbConnection) -> IoResult<()> {
+        let mut connection = Client::new(connection);
+        let mut last_reply = String::new();
+        loop {
+            let packet = match read_packet(&mut connection)? {
+                Some(Packet::Data(packet)) => packet,
+                Some(Packet::Invalid) => {
+                    connection.write_all(b"-")?;
+                    continue;
+                }
+                Some(Packet::Retransmit) => {
+                    write_packet(&mut connection, &last_reply)?;
+                    continue;
+                }
+                Some(Packet::Interrupt) => {
+                    self.stop = Stop::Interrupt;
+                    last_reply = self.stop.reply().to_string();
+                    write_packet(&mut connection, &last_reply)?;
+                    continue;
+                }
+                None => return Ok(()),
+            };
+            if !self.no_ack {
+                connection.write_all(b"+")?;
+            }
+
+            let (reply, done) = match packet.as_str() {
+                "k" => return Ok(()),
+                "D" | "D;1" => ("OK".to_string(), true),
+                _ => (self.handle(&packet, &mut connection)?, false),
+            };
+            write_packet(&mut connection, &reply)?;
+            if done {
+                return Ok(());
+            }
+            last_reply = reply;
+        }
+    }
+
+    /// Returns the reply to `packet`; an empty reply means "unsupported"
+    fn handle<C: GdbConnection>(
+        &mut self,
+        packet: &str,
+        connection: &mut Client<C>,
+    ) -> IoResult<String> {
+        if packet.is_empty() {
+            return Ok(String::new());
+        }
+        let (command, arguments) = packet.split_at(1);
+        let reply = match command {
+            "?" => self.stop.reply().to_string(),
+            "g" => self.read_registers(),
+            "G" => reply_ok(self.write_registers(arguments)),
+            "p" => usize::from_str_radix(arguments, 16)
+                .ok()
+                .and_then(|index| self.target.read_gdb_register(index))
+                .map_or_else(|| "E01".to_string(), |bytes| hex(&bytes)),
+            "P" => reply_ok(arguments.split_once('=').is_some_and(|(index, value)| {
+                let index = usize::from_str_radix(index, 16).ok();
+                let value = unhex(value);
+                match (index, value) {
+                    (Some(index), Some(value)) => self.target.write_gdb_register(index, &value),
+                    _ => false,
+                }
+            })),
+            "m" => self.read_memory(arguments),
+            "M" => reply_ok(self.write_memory(arguments)),
+            "s" => {
+                self.stop = self.step();
+                self.stop.reply().to_string()
+            }
+            "c" => {
+                self.stop = self.resume(connection)?;
+                self.stop.reply().to_string()
+            }
+            "Z" | "z" => {
+                let mut fields = arguments.split(',');
+                let kind = fields.next();
+                let address = fields
+                    .next()
+                    .and_then(|address| u64::from_str_radix(address, 16).ok());
+                match (kind, address) {
+                    (Some("0") | Some("1"), Some(address)) => {
+                        if command == "Z" {
+                            self.breakpoints.insert(address);
+                        } else {
+                            self.breakpoints.remove(&address);
+                        }
+                        "OK".to_string()
+                    }
+                    (Some("0") | Some("1"), None) => "E01".to_string(),
+                    _ => String::new(),
+                }
+            }
+            "H" => "OK".to_string(),
+            "T" => "OK".to_string(),
+            "q" | "Q" => self.query(packet),
+            _ => String::new(),
+        };
+        Ok(reply)
+    }
+
+    fn query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return format!(
+                "PacketSize={:x};qXfer:features:read+;swbreak+;QStartNoAckMode+",
+                PACKET_SIZE
+            );
+        }
+        if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            let xml = self.target_xml();
+            let Some((offset, length)) = range.split_once(',') else {
+                return "E01".to_string();
+            };
+            let (Ok(offset), Ok(length)) = (
+                usize::from_str_radix(offset, 16),
+                usize::from_str_radix(length, 16),
+            ) else {
+                return "E01".to_string();
+            };
+            let start = offset.min(xml.len());
+            let end = start.saturating_add(length).min(xml.len());
+            let marker = if end == xml.len() { 'l' } else { 'm' };
+            return format!("{}{}", marker, &xml[start..end]);
+        }
+        match packet {
+            "QStartNoAckMode" => {
+                self.no_ack = true;
+                "OK".to_string()
+            }
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn target_xml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\"><target>",
+        );
+        xml.push_str(&format!(
+            "<feature name=\"org.tiny_computers.{}\">",
+            self.target.name().to_lowercase()
+        ));
+        for (number, register) in self.target.gdb_registers().iter().enumerate() {
+            xml.push_str(&format!(
+                "<reg name=\"{}\" bitsize=\"{}\" type=\"{}\" regnum=\"{}\"/>",
+                register.name, register.bits, register.kind, number
+            ));
+        }
+        xml.push_str("</feature></target>");
+        xml
+    }
+
+    fn read_registers(&self) -> String {
+        (0..self.target.gdb_registers().len())
+            .filter_map(|index| self.target.read_gdb_register(index))
+            .map(|bytes| hex(&bytes))
+            .collect()
+    }
+
+    fn write_registers(&mut self, data: &str) -> bool {
+        let Some(mut bytes) = unhex(data) else {
+            return false;
+        };
+        for (index, register) in self.target.gdb_registers().iter().enumerate() {
+            let size = register.bits / 8;
+            if bytes.len() < size {
+                return false;
+            }
+            let rest = bytes.split_off(size);
+            if !self.target.write_gdb_register(index, &bytes) {
+                return false;
+            }
+            bytes = rest;
+        }
+        true
+    }
+
+    /// Replies with at most half a packet of bytes, since each is sent as two
+    /// hex digits
+    fn read_memory(&self, arguments: &str) -> String {
+        let Some((address, length)) = parse_range(arguments) else {
+            return "E01".to_string();
+        };
+        if length > (PACKET_SIZE / 2) as u64 {
+            return "E01".to_string();
+        }
+        let memory = self.target.memory();
+        let mut bytes = Vec::with_capacity(length as usize);
+        for offset in 0..length {
+            let byte = address
+                .checked_add(offset)
+                .and_then(|address| address.try_into().ok())
+                .and_then(|address| memory.read(address).ok());
+            match byte {
+                Some(byte) => bytes.push(byte),
+                // GDB accepts a short read as long as it is not empty
+                None if offset > 0 => break,
+                None => return "E01".to_string(),
+            }
+        }
+        hex(&bytes)
+    }
+
+    fn write_memory(&mut self, arguments: &str) -> bool {
+        let Some((range, data)) = arguments.split_once(':') else {
+            return false;
+        };
+        let (Some((address, length)), Some(bytes)) = (parse_range(range), unhex(data)) else {
+            return false;
+        };
+        if bytes.len() as u64 != length {
+            return false;
+        }
+        let memory = self.target.memory_mut();
+        bytes.iter().zip(address..).all(|(&byte, address)| {
+            address
+                .try_into()
+                .ok()
+                .is_some_and(|address| memory.write(address, byte).is_ok())
+        })
+    }
+
+    fn pc(&self) -> u64 {
+        self.target.state().get_program_counter().into()
+    }
+
+    fn step(&mut self) -> Stop {
+        match self.target.step() {
+            Ok(_) => Stop::Trap,
+            Err(error) => match error.into() {
+                CpuError::Halted => Stop::Exited,
+                _ => Stop::Fault,
+            },
+        }
+    }
+
+    /// Runs until a breakpoint, an error or an interrupt from the debugger
+    fn resume<C: GdbConnection>(&mut self, connection: &mut Client<C>) -> IoResult<Stop> {
+        loop {
+            for _ in 0..INTERRUPT_POLL_INTERVAL {
+                let stop = self.step();
+                if stop != Stop::Trap || self.breakpoints.contains(&self.pc()) {
+                    return Ok(stop);
+                }
+            }
+            if connection.poll_interrupt()? {
+                return Ok(Stop::Interrupt);
+            }
+        }
+    }
+}
+
+enum Packet {
+    Data(String),
+    /// Checksum mismatch; the client should resend
+    Invalid,
+    /// The client asked for the last reply again
+    Retransmit,
+    /// A Ctrl-C outside a continue
+    Interrupt,
+}
+
+/// Reads the next packet, skipping acknowledgements; `None` at end of stream
+fn read_packet(connection: &mut impl Read) -> IoResult<Option<Packet>> {
+    let mut byte = [0];
+    loop {
+        if connection.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        match byte[0] {
+            b'$' => break,
+            b'-' => return Ok(Some(Packet::Retransmit)),
+            0x03 => return Ok(Some(Packet::Interrupt)),
+            _ => {}
+        }
+    }
+
+    let mut data = Vec::new();
+    loop {
+        if connection.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        if byte[0] == b'#' {
+            break;
+        }
+        data.push(byte[0]);
+        if data.len() > PACKET_SIZE {
+            return Ok(Some(Packet::Invalid));
+        }
+    }
+
+    let mut checksum = [0; 2];
+    connection.read_exact(&mut checksum)?;
+    let expected = std::str::from_utf8(&checksum)
+        .ok()
+        .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+    let actual = data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+    if expected != Some(actual) {
+        return Ok(Some(Packet::Invalid));
+    }
+
+    // `}` escapes the next byte, XOR 0x20
+    let mut unescaped = Vec::with_capacity(data.len());
+    let mut bytes = data.into_iter();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'}' => unescaped.extend(bytes.next().map(|byte| byte ^ 0x20)),
+            _ => unescaped.push(byte),
+        }
+    }
+    Ok(String::from_utf8(unescaped)
+        .ok()
+        .map(Packet::Data)
+        .or(Some(Packet::Invalid)))
+}
+
+fn write_packet(connection: &mut impl Write, data: &str) -> IoResult<()> {
+    let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+    write!(connection, "${}#{:02x}", data, checksum)?;
+    connection.flush()
+}
+
+fn reply_ok(ok: bool) -> String {
+    if ok { "OK" } else { "E01" }.to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parses `addr,length` in hex
+fn parse_range(text: &str) -> Option<(u64, u64)> {
+    let (address, length) = text.split_once(',')?;
+    Some((
+        u64::from_str_radix(address, 16).ok()?,
+        u64::from_str_radix(length, 16).ok()?,
+    ))
+}
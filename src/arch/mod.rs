@@ -1,5 +1,6 @@
 pub mod chip_8;
 pub mod error;
+pub mod gdb;
 
 use crate::core::{
     cpu::{Cpu, CpuState},
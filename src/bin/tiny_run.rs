@@ -0,0 +1,475 @@
+//! Headless runner: loads a ROM, runs it and dumps the final machine state
+//!
+//! ```text
+//! tiny-run [OPTIONS] <ROM>
+//! tiny-run [OPTIONS] --replay <MOVIE>
+//! ```
+//!
+//! Run `tiny-run --help` for the options. The exit status is 0 when the run
+//! stopped normally, 1 when the machine raised an error or a replayed movie
+//! diverged from its recording and 2 for usage errors.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::TcpListener;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::process::ExitCode;
+
+use tiny_computers::arch::chip_8::{Chip8, Chip8InstructionSet, Chip8Quirks};
+use tiny_computers::arch::gdb::GdbStub;
+use tiny_computers::arch::Architecture;
+use tiny_computers::core::cpu::{CpuState, RegisterFile};
+use tiny_computers::core::isa::InstructionSet;
+use tiny_computers::core::memory::MemoryDevice;
+use tiny_computers::core::movie::Movie;
+use tiny_computers::core::trace::{BinaryTraceSink, TextTraceSink, TraceSink};
+
+const USAGE: &str = "\
+Usage: tiny-run [OPTIONS] <ROM>
+       tiny-run [OPTIONS] --replay <MOVIE>
+
+Loads ROM, runs it headless and dumps the final registers, memory and display.
+
+Options:
+  --arch <chip8|schip|xochip>   Architecture to emulate [default: chip8]
+  --quirks <vip|schip|xochip|modern>
+                                Quirk preset [default: matches --arch]
+  --cycles <N>                  Stop after N instructions
+  --frames <N>                  Stop after N 60 Hz frames [default: 600 if no
+                                other limit is given]
+  --until-pc <ADDR>             Stop when PC reaches ADDR
+  --until-loop                  Stop at a jump to itself, the usual end-of-test idiom
+  --press <KEY>                 Hold hex KEY down for the whole run (repeatable)
+  --dump-memory <START:LEN>     Dump LEN bytes from START (repeatable)
+  --display <ascii|pbm|none>    Framebuffer dump format [default: ascii]
+  --display-out <FILE>          Write the framebuffer dump to FILE instead of stdout
+  --gdb <PORT|PATH>             Instead of running, serve one GDB remote session on
+                                127.0.0.1:PORT or the Unix socket PATH, then dump
+  --trace <FILE>                Write an execution trace to FILE
+  --trace-format <text|binary>  Trace format [default: text]
+  --replay <MOVIE>              Instead of a ROM, play a movie recorded by tiny-tui
+                                and check it ends in the recorded state
+  -h, --help                    Print this help
+
+Numbers may be decimal or 0x-prefixed hex.";
+
+/// Frame limit applied when no stop condition is given, ten seconds of emulated time
+const DEFAULT_FRAMES: u64 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DisplayFormat {
+    Ascii,
+    Pbm,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TraceFormat {
+    Text,
+    Binary,
+}
+
+#[derive(Debug)]
+struct Options {
+    rom: String,
+    instruction_set: Chip8InstructionSet,
+    quirks: Option<Chip8Quirks>,
+    cycles: Option<u64>,
+    frames: Option<u64>,
+    until_pc: Option<u16>,
+    until_loop: bool,
+    keys: Vec<u8>,
+    memory: Vec<(u16, usize)>,
+    display: DisplayFormat,
+    display_out: Option<String>,
+    gdb: Option<String>,
+    replay: Option<String>,
+    trace: Option<String>,
+    trace_format: TraceFormat,
+}
+
+fn parse_number(text: &str) -> Result<u64, String> {
+    let parsed = match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| format!("invalid number `{}`", text))
+}
+
+fn parse_address(text: &str) -> Result<u16, String> {
+    let value = parse_number(text)?;
+    u16::try_from(value).map_err(|_| format!("address `{}` out of range", text))
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut args = args.into_iter();
+    let mut rom = None;
+    let mut options = Options {
+        rom: String::new(),
+        instruction_set: Chip8InstructionSet::Chip8,
+        quirks: None,
+        cycles: None,
+        frames: None,
+        until_pc: None,
+        until_loop: false,
+        keys: Vec::new(),
+        memory: Vec::new(),
+        display: DisplayFormat::Ascii,
+        display_out: None,
+        gdb: None,
+        replay: None,
+        trace: None,
+        trace_format: TraceFormat::Text,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{}` expects a value", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--arch" => {
+                options.instruction_set = match value()?.as_str() {
+                    "chip8" => Chip8InstructionSet::Chip8,
+                    "schip" => Chip8InstructionSet::SuperChip,
+                    "xochip" => Chip8InstructionSet::XOChip,
+                    other => return Err(format!("unknown architecture `{}`", other)),
+                }
+            }
+            "--quirks" => {
+                options.quirks = Some(match value()?.as_str() {
+                    "vip" => Chip8Quirks::vip(),
+                    "schip" => Chip8Quirks::schip(),
+                    "xochip" => Chip8Quirks::xochip(),
+                    "modern" => Chip8Quirks::modern(),
+                    other => return Err(format!("unknown quirk preset `{}`", other)),
+                })
+            }
+            "--cycles" => options.cycles = Some(parse_number(&value()?)?),
+            "--frames" => options.frames = Some(parse_number(&value()?)?),
+            "--until-pc" => options.until_pc = Some(parse_address(&value()?)?),
+            "--until-loop" => options.until_loop = true,
+            "--press" => {
+                let key = value()?;
+                let key = u8::from_str_radix(key.trim_start_matches("0x"), 16)
+                    .ok()
+                    .filter(|&key| key <= 0xF)
+                    .ok_or_else(|| format!("invalid key `{}`", key))?;
+                options.keys.push(key);
+            }
+            "--dump-memory" => {
+                let range = value()?;
+                let (start, length) = range
+                    .split_once(':')
+                    .ok_or_else(|| format!("expected START:LEN, found `{}`", range))?;
+                // Dumps stop at the end of memory, so longer is as good as all
+                let length = usize::try_from(parse_number(length)?).unwrap_or(usize::MAX);
+                options.memory.push((parse_address(start)?, length));
+            }
+            "--display" => {
+                options.display = match value()?.as_str() {
+                    "ascii" => DisplayFormat::Ascii,
+                    "pbm" => DisplayFormat::Pbm,
+                    "none" => DisplayFormat::None,
+                    other => return Err(format!("unknown display format `{}`", other)),
+                }
+            }
+            "--display-out" => options.display_out = Some(value()?),
+            "--gdb" => options.gdb = Some(value()?),
+            "--replay" => options.replay = Some(value()?),
+            "--trace" => options.trace = Some(value()?),
+            "--trace-format" => {
+                options.trace_format = match value()?.as_str() {
+                    "text" => TraceFormat::Text,
+                    "binary" => TraceFormat::Binary,
+                    other => return Err(format!("unknown trace format `{}`", other)),
+                }
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+            _ if rom.is_some() => return Err(format!("unexpected argument `{}`", arg)),
+            _ => rom = Some(arg),
+        }
+    }
+
+    match (&options.replay, rom) {
+        (Some(_), Some(_)) => return Err("`--replay` does not take a ROM".to_string()),
+        (Some(_), None) => {}
+        (None, rom) => options.rom = rom.ok_or("missing ROM path")?,
+    }
+    if options.cycles.is_none() && options.frames.is_none() && options.until_pc.is_none() {
+        options.frames = Some(DEFAULT_FRAMES);
+    }
+    Ok(Some(options))
+}
+
+/// Why the run ended
+#[derive(Debug)]
+enum Stop {
+    Cycles,
+    Frames,
+    ProgramCounter(u16),
+    Loop(u16),
+    Halted,
+    Detached,
+    /// The movie ended; true if in its recorded final state
+    Replayed(bool),
+    Error(String),
+}
+
+/// Steps `machine` until one of the configured stop conditions holds
+///
+/// Stepping instruction by instruction keeps cycle limits exact; timers still
+/// tick every frame through [`Architecture::step`].
+fn run(machine: &mut Chip8, options: &Options) -> (Stop, u64) {
+    let mut executed = 0;
+    loop {
+        let pc = machine.state().pc();
+        if options.cycles.is_some_and(|cycles| executed >= cycles) {
+            return (Stop::Cycles, executed);
+        }
+        if options
+            .frames
+            .is_some_and(|frames| machine.timers().frames() >= frames)
+        {
+            return (Stop::Frames, executed);
+        }
+        if options.until_pc == Some(pc) {
+            return (Stop::ProgramCounter(pc), executed);
+        }
+        if machine.state().is_halted() {
+            return (Stop::Halted, executed);
+        }
+        if options.until_loop && machine.cpu().fetch().map(|inst| inst.word()) == Ok(0x1000 | pc) {
+            return (Stop::Loop(pc), executed);
+        }
+
+        if let Err(error) = machine.step() {
+            return (Stop::Error(error.to_string()), executed);
+        }
+        executed += 1;
+    }
+}
+
+/// Serves one GDB session on 127.0.0.1:`address` if it is a port number,
+/// otherwise on a Unix socket at `address`
+fn serve_gdb(machine: &mut Chip8, address: &str) -> io::Result<()> {
+    let mut stub = GdbStub::new(machine);
+    if let Ok(port) = address.parse::<u16>() {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        eprintln!("tiny-run: waiting for gdb on 127.0.0.1:{}", port);
+        let (stream, _) = listener.accept()?;
+        return stub.serve(stream);
+    }
+
+    #[cfg(unix)]
+    {
+        let listener = UnixListener::bind(address)?;
+        eprintln!("tiny-run: waiting for gdb on {}", address);
+        let (stream, _) = listener.accept()?;
+        let result = stub.serve(stream);
+        let _ = std::fs::remove_file(address);
+        result
+    }
+    #[cfg(not(unix))]
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("`{}` is not a port number", address),
+    ))
+}
+
+/// Reads a movie and creates a machine of the dialect it was recorded on
+fn load_movie(path: &str) -> Result<(Chip8, Movie), String> {
+    let bytes = std::fs::read(path).map_err(|error| format!("cannot read {}: {}", path, error))?;
+    let movie =
+        Movie::from_bytes(&bytes).map_err(|error| format!("cannot load {}: {}", path, error))?;
+    let variant = movie.start().variant();
+    let instruction_set = [
+        Chip8InstructionSet::Chip8,
+        Chip8InstructionSet::SuperChip,
+        Chip8InstructionSet::XOChip,
+    ]
+    .into_iter()
+    .find(|instruction_set| instruction_set.name() == variant)
+    .ok_or_else(|| format!("{} was recorded on unknown machine `{}`", path, variant))?;
+    Ok((Chip8::new(instruction_set), movie))
+}
+
+fn dump(
+    machine: &Chip8,
+    options: &Options,
+    stop: &Stop,
+    executed: u64,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let state = machine.state();
+    let registers = state.register_file();
+
+    writeln!(out, "arch: {}", machine.name())?;
+    let reason = match stop {
+        Stop::Cycles => "cycle limit".to_string(),
+        Stop::Frames => "frame limit".to_string(),
+        Stop::ProgramCounter(pc) => format!("reached pc {:#05x}", pc),
+        Stop::Loop(pc) => format!("jump to self at {:#05x}", pc),
+        Stop::Halted => "halted".to_string(),
+        Stop::Detached => "debugger detached".to_string(),
+        Stop::Replayed(true) => "end of movie, final state matches".to_string(),
+        Stop::Replayed(false) => "end of movie, final state differs".to_string(),
+        Stop::Error(error) => format!("error: {}", error),
+    };
+    writeln!(
+        out,
+        "stopped: {} after {} instructions, {} frames",
+        reason,
+        executed,
+        machine.timers().frames()
+    )?;
+
+    writeln!(out, "registers:")?;
+    for (index, values) in registers.registers().chunks(8).enumerate() {
+        let line: Vec<String> = values
+            .iter()
+            .enumerate()
+            .map(|(offset, value)| format!("v{:x}={:02x}", index * 8 + offset, value))
+            .collect();
+        writeln!(out, "  {}", line.join(" "))?;
+    }
+    writeln!(
+        out,
+        "  i={:04x} pc={:04x} sp={:x} dt={:02x} st={:02x}",
+        registers.i(),
+        registers.program_counter(),
+        registers.stack_pointer(),
+        registers.delay_timer(),
+        registers.sound_timer()
+    )?;
+    let depth = registers.stack_pointer() as usize;
+    let stack: Vec<String> = state.stack()[..depth.min(state.stack().len())]
+        .iter()
+        .map(|address| format!("{:04x}", address))
+        .collect();
+    writeln!(out, "stack: [{}]", stack.join(" "))?;
+
+    for &(start, length) in &options.memory {
+        writeln!(out, "memory {:#06x}+{:#x}:", start, length)?;
+        let end = (start as usize)
+            .saturating_add(length)
+            .min(machine.memory().size());
+        for line in (start as usize..end).step_by(16) {
+            let bytes: Vec<String> = (line..end.min(line + 16))
+                .map(|address| {
+                    machine
+                        .memory()
+                        .read(address as u16)
+                        .map_or("??".to_string(), |byte| format!("{:02x}", byte))
+                })
+                .collect();
+            writeln!(out, "  {:04x}: {}", line, bytes.join(" "))?;
+        }
+    }
+
+    let display = machine.display();
+    match (options.display, &options.display_out) {
+        (DisplayFormat::None, _) => {}
+        (format, Some(path)) => {
+            let mut file = BufWriter::new(File::create(path)?);
+            match format {
+                DisplayFormat::Ascii => file.write_all(display.to_ascii().as_bytes())?,
+                _ => display.write_pbm(&mut file)?,
+            }
+            file.flush()?;
+        }
+        (DisplayFormat::Ascii, None) => {
+            writeln!(out, "display {}x{}:", display.width(), display.height())?;
+            write!(out, "{}", display.to_ascii())?;
+        }
+        (DisplayFormat::Pbm, None) => display.write_pbm(out)?,
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("tiny-run: {}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let quirks = options
+        .quirks
+        .unwrap_or_else(|| options.instruction_set.into());
+    let (mut machine, movie) = match &options.replay {
+        Some(path) => match load_movie(path) {
+            Ok((machine, movie)) => (machine, Some(movie)),
+            Err(error) => {
+                eprintln!("tiny-run: {}", error);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => {
+            let mut machine = Chip8::with_quirks(options.instruction_set, quirks);
+            if let Err(error) = machine.load_rom_file(&options.rom) {
+                eprintln!("tiny-run: cannot load {}: {}", options.rom, error);
+                return ExitCode::FAILURE;
+            }
+            (machine, None)
+        }
+    };
+    for &key in &options.keys {
+        machine
+            .press(key)
+            .expect("keys are validated while parsing");
+    }
+    if let Some(path) = &options.trace {
+        let file = match File::create(path) {
+            Ok(file) => BufWriter::new(file),
+            Err(error) => {
+                eprintln!("tiny-run: cannot create {}: {}", path, error);
+                return ExitCode::FAILURE;
+            }
+        };
+        let sink: Box<dyn TraceSink> = match options.trace_format {
+            TraceFormat::Text => Box::new(TextTraceSink::new(file)),
+            TraceFormat::Binary => Box::new(BinaryTraceSink::new(file)),
+        };
+        machine.set_trace_sink(Some(sink));
+    }
+
+    let (stop, executed) = match (&options.gdb, &movie) {
+        (_, Some(movie)) => match machine.replay(movie) {
+            Ok(matches) => (Stop::Replayed(matches), machine.state().cycles()),
+            Err(error) => (Stop::Error(error.to_string()), machine.state().cycles()),
+        },
+        (Some(address), None) => match serve_gdb(&mut machine, address) {
+            Ok(()) => (Stop::Detached, machine.state().cycles()),
+            Err(error) => {
+                eprintln!("tiny-run: gdb: {}", error);
+                return ExitCode::FAILURE;
+            }
+        },
+        (None, None) => run(&mut machine, &options),
+    };
+    if let Some(mut sink) = machine.set_trace_sink(None) {
+        if let Err(error) = sink.flush() {
+            eprintln!("tiny-run: cannot write trace: {}", error);
+            return ExitCode::FAILURE;
+        }
+    }
+    let stdout = io::stdout();
+    if let Err(error) = dump(&machine, &options, &stop, executed, &mut stdout.lock()) {
+        eprintln!("tiny-run: {}", error);
+        return ExitCode::FAILURE;
+    }
+
+    match stop {
+        Stop::Error(_) | Stop::Replayed(false) => ExitCode::FAILURE,
+        _ => ExitCode::SUCCESS,
+    }
+}
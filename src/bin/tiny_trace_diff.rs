@@ -0,0 +1,186 @@
+//! Finds the first step at which an execution trace departs from a reference
+//!
+//! ```text
+//! tiny-trace-diff [OPTIONS] <TRACE> <REFERENCE>
+//! ```
+//!
+//! Run `tiny-trace-diff --help` for the options. Like `diff`, the exit status
+//! is 0 when the traces agree, 1 when they diverge and 2 for usage or read
+//! errors.
+
+use std::io::{self, BufReader, Write};
+use std::process::ExitCode;
+
+use tiny_computers::core::trace::{
+    first_divergence, read_reference_log, read_trace, trace_steps, Divergence, Location, TraceStep,
+};
+
+const USAGE: &str = "\
+Usage: tiny-trace-diff [OPTIONS] <TRACE> <REFERENCE>
+
+Compares TRACE, written by `tiny-run --trace`, step by step against REFERENCE
+and reports the first step where registers or memory differ, or where one of
+them ends before the other.
+
+REFERENCE is either another trace or a log with one executed instruction per
+line, giving the state before it ran as hex `name=value` or `name:value`
+tokens and the memory it touched as `[address]=value`. Registers the log does
+not name are not compared; blank lines and `#` comments are skipped.
+
+Options:
+  --context <N>     Steps to show before and after the divergence [default: 5]
+  --skip <N>        Drop the first N steps of TRACE, to line it up with a log
+                    that starts later
+  -h, --help        Print this help";
+
+/// Context shown around a divergence unless `--context` says otherwise
+const DEFAULT_CONTEXT: usize = 5;
+
+#[derive(Debug)]
+struct Options {
+    trace: String,
+    reference: String,
+    context: usize,
+    skip: usize,
+}
+
+fn parse_count(text: &str) -> Result<usize, String> {
+    text.parse()
+        .map_err(|_| format!("invalid number `{}`", text))
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut args = args.into_iter();
+    let mut paths = Vec::new();
+    let mut context = DEFAULT_CONTEXT;
+    let mut skip = 0;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{}` expects a value", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--context" => context = parse_count(&value()?)?,
+            "--skip" => skip = parse_count(&value()?)?,
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+            _ if paths.len() == 2 => return Err(format!("unexpected argument `{}`", arg)),
+            _ => paths.push(arg),
+        }
+    }
+
+    let mut paths = paths.into_iter();
+    Ok(Some(Options {
+        trace: paths.next().ok_or("missing TRACE path")?,
+        reference: paths.next().ok_or("missing REFERENCE path")?,
+        context,
+        skip,
+    }))
+}
+
+/// Reads a trace of this crate, or a reference log if `fallback` is set and
+/// the file is not a trace
+fn load(path: &str, fallback: bool) -> Result<Vec<TraceStep>, String> {
+    let data = std::fs::read(path).map_err(|error| format!("cannot read {}: {}", path, error))?;
+    let result = match read_trace(&data[..]) {
+        Ok((header, entries)) => Ok(trace_steps(&header, &entries)),
+        Err(_) if fallback => read_reference_log(BufReader::new(&data[..])),
+        Err(error) => Err(error),
+    };
+    result.map_err(|error| format!("cannot load {}: {}", path, error))
+}
+
+fn step_text(steps: &[TraceStep], index: usize) -> &str {
+    steps.get(index).map_or("", |step| step.text.as_str())
+}
+
+fn report(
+    divergence: &Divergence,
+    trace: &[TraceStep],
+    reference: &[TraceStep],
+    options: &Options,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let step = divergence.step;
+    writeln!(out, "traces diverge at step {}:", step + options.skip)?;
+    for difference in &divergence.differences {
+        let location = match &difference.location {
+            Location::Register(name) => name.clone(),
+            Location::Memory(address) => format!("[{:04x}]", address),
+            Location::End => {
+                writeln!(
+                    out,
+                    "  length: expected {} steps, found {}",
+                    difference.expected,
+                    difference.found.unwrap_or(0)
+                )?;
+                continue;
+            }
+        };
+        let found = difference
+            .found
+            .map_or("nothing".to_string(), |found| format!("{:02x}", found));
+        writeln!(
+            out,
+            "  {}: expected {:02x}, found {}",
+            location, difference.expected, found
+        )?;
+    }
+
+    writeln!(out)?;
+    let end = (step + options.context + 1).min(trace.len().max(reference.len()));
+    for index in step.saturating_sub(options.context)..end {
+        let marker = if index == step { '>' } else { ' ' };
+        let trace_line = format!(
+            "{} {:>8}  trace      {}",
+            marker,
+            index + options.skip,
+            step_text(trace, index)
+        );
+        let reference_line = format!(
+            "{} {:>8}  reference  {}",
+            marker,
+            "",
+            step_text(reference, index)
+        );
+        writeln!(out, "{}", trace_line.trim_end())?;
+        writeln!(out, "{}", reference_line.trim_end())?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("tiny-trace-diff: {}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let (trace, reference) = match (load(&options.trace, false), load(&options.reference, true)) {
+        (Ok(trace), Ok(reference)) => (trace, reference),
+        (Err(error), _) | (_, Err(error)) => {
+            eprintln!("tiny-trace-diff: {}", error);
+            return ExitCode::from(2);
+        }
+    };
+    let trace = trace.get(options.skip..).unwrap_or_default();
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let Some(divergence) = first_divergence(trace, &reference) else {
+        let _ = writeln!(out, "traces agree for {} steps", trace.len());
+        return ExitCode::SUCCESS;
+    };
+    if let Err(error) = report(&divergence, trace, &reference, &options, &mut out) {
+        eprintln!("tiny-trace-diff: {}", error);
+        return ExitCode::from(2);
+    }
+    ExitCode::FAILURE
+}
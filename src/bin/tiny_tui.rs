@@ -0,0 +1,582 @@
+//! Terminal frontend for CHIP-8
+//!
+//! ```text
+//! tiny-tui [OPTIONS] <ROM>
+//! ```
+//!
+//! Renders the framebuffer with half-block characters, two pixel rows per
+//! terminal row, next to a register panel. The keypad is mapped onto the
+//! left-hand QWERTY grid:
+//!
+//! ```text
+//! 1 2 3 C      1 2 3 4
+//! 4 5 6 D  <-  Q W E R
+//! 7 8 9 E      A S D F
+//! A 0 B F      Z X C V
+//! ```
+//!
+//! Most terminals only report key presses, so a key counts as held for a few
+//! frames after each press or auto-repeat; terminals supporting the kitty
+//! keyboard protocol report releases and are tracked exactly.
+//!
+//! Esc quits, Space pauses, Enter steps one instruction while paused, Left
+//! rewinds one frame and Backspace resets. The last ten seconds can be rewound.
+//!
+//! With `--record FILE` the keypad inputs are saved as a movie on exit, which
+//! `tiny-run --replay FILE` plays back and checks. Resetting or rewinding
+//! restarts the recording from that point.
+
+use std::io::{self, Stdout, Write};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+
+use tiny_computers::arch::chip_8::{
+    Chip8, Chip8InstructionSet, Chip8Quirks, KEY_COUNT, TIMER_FREQUENCY,
+};
+use tiny_computers::arch::Architecture;
+use tiny_computers::core::cpu::RegisterFile;
+use tiny_computers::core::isa::Instruction;
+use tiny_computers::core::movie::{InputEvent, InputRecorder};
+use tiny_computers::core::savestate::RewindBuffer;
+
+const USAGE: &str = "\
+Usage: tiny-tui [OPTIONS] <ROM>
+
+Plays ROM in the terminal.
+
+Options:
+  --arch <chip8|schip|xochip>   Architecture to emulate [default: chip8]
+  --quirks <vip|schip|xochip|modern>
+                                Quirk preset [default: matches --arch]
+  --rate <HZ>                   Instructions per second [default: 600]
+  --record <FILE>               Save the keypad inputs as a movie to FILE on exit
+  -h, --help                    Print this help
+
+Keys: 1234/QWER/ASDF/ZXCV keypad, Esc quit, Space pause,
+      Enter step while paused, Left rewind, Backspace reset";
+
+/// Instructions per second unless configured otherwise
+const DEFAULT_RATE: u32 = 600;
+/// Seconds of play kept for rewinding
+const REWIND_SECONDS: u32 = 10;
+
+/// Frames a key stays down after a press when the terminal cannot report releases
+const KEY_HOLD_FRAMES: u8 = 6;
+
+/// QWERTY key for each CHIP-8 key, laid out as the COSMAC VIP keypad
+const KEYMAP: [(char, u8); KEY_COUNT as usize] = [
+    ('1', 0x1),
+    ('2', 0x2),
+    ('3', 0x3),
+    ('4', 0xC),
+    ('q', 0x4),
+    ('w', 0x5),
+    ('e', 0x6),
+    ('r', 0xD),
+    ('a', 0x7),
+    ('s', 0x8),
+    ('d', 0x9),
+    ('f', 0xE),
+    ('z', 0xA),
+    ('x', 0x0),
+    ('c', 0xB),
+    ('v', 0xF),
+];
+
+/// Colour for each XO-CHIP plane combination; plain CHIP-8 only uses the first two
+const PALETTE: [Color; 4] = [Color::Black, Color::White, Color::Red, Color::Yellow];
+
+struct Options {
+    rom: String,
+    instruction_set: Chip8InstructionSet,
+    quirks: Option<Chip8Quirks>,
+    rate: u32,
+    record: Option<String>,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut args = args.into_iter();
+    let mut rom = None;
+    let mut options = Options {
+        rom: String::new(),
+        instruction_set: Chip8InstructionSet::Chip8,
+        quirks: None,
+        rate: DEFAULT_RATE,
+        record: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{}` expects a value", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--arch" => {
+                options.instruction_set = match value()?.as_str() {
+                    "chip8" => Chip8InstructionSet::Chip8,
+                    "schip" => Chip8InstructionSet::SuperChip,
+                    "xochip" => Chip8InstructionSet::XOChip,
+                    other => return Err(format!("unknown architecture `{}`", other)),
+                }
+            }
+            "--quirks" => {
+                options.quirks = Some(match value()?.as_str() {
+                    "vip" => Chip8Quirks::vip(),
+                    "schip" => Chip8Quirks::schip(),
+                    "xochip" => Chip8Quirks::xochip(),
+                    "modern" => Chip8Quirks::modern(),
+                    other => return Err(format!("unknown quirk preset `{}`", other)),
+                })
+            }
+            "--rate" => {
+                let rate = value()?;
+                options.rate = rate
+                    .parse()
+                    .ok()
+                    .filter(|&rate| rate > 0)
+                    .ok_or_else(|| format!("invalid rate `{}`", rate))?;
+            }
+            "--record" => options.record = Some(value()?),
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+            _ if rom.is_some() => return Err(format!("unexpected argument `{}`", arg)),
+            _ => rom = Some(arg),
+        }
+    }
+
+    options.rom = rom.ok_or("missing ROM path")?;
+    Ok(Some(options))
+}
+
+/// Puts the terminal into raw mode on the alternate screen, restoring it on drop
+struct Terminal {
+    out: Stdout,
+    reports_releases: bool,
+}
+
+impl Terminal {
+    fn enter() -> io::Result<Self> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        let reports_releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if reports_releases {
+            execute!(
+                out,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+        }
+        Ok(Self {
+            out,
+            reports_releases,
+        })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.reports_releases {
+            let _ = execute!(self.out, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(
+            self.out,
+            ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct App {
+    machine: Chip8,
+    paused: bool,
+    quit: bool,
+    /// Frames left before each key is released, for terminals without release events
+    held: [u8; KEY_COUNT as usize],
+    rewind: RewindBuffer,
+    /// Records keypad inputs when running with `--record`
+    recorder: Option<InputRecorder>,
+    error: Option<String>,
+}
+
+impl App {
+    fn handle_key(&mut self, key: KeyEvent, reports_releases: bool) {
+        if key.kind == KeyEventKind::Press {
+            match key.code {
+                KeyCode::Esc => self.quit = true,
+                KeyCode::Char(' ') => self.paused = !self.paused,
+                KeyCode::Enter if self.paused => self.step(),
+                KeyCode::Left => self.rewind_frame(),
+                KeyCode::Backspace => {
+                    self.rewind.clear();
+                    self.error = self
+                        .machine
+                        .reset()
+                        .and_then(|()| self.machine.record_rewind(&mut self.rewind))
+                        .err()
+                        .map(|error| error.to_string());
+                    self.restart_recording();
+                }
+                _ => {}
+            }
+        }
+
+        let KeyCode::Char(c) = key.code else {
+            return;
+        };
+        let Some(&(_, chip_key)) = KEYMAP
+            .iter()
+            .find(|(qwerty, _)| *qwerty == c.to_ascii_lowercase())
+        else {
+            return;
+        };
+        match key.kind {
+            KeyEventKind::Release => {
+                self.held[chip_key as usize] = 0;
+                self.input(InputEvent::Release(chip_key as u16));
+            }
+            _ => {
+                if !reports_releases {
+                    self.held[chip_key as usize] = KEY_HOLD_FRAMES;
+                }
+                self.input(InputEvent::Press(chip_key as u16));
+            }
+        }
+    }
+
+    /// Applies a keypad input, recording it with `--record`
+    fn input(&mut self, event: InputEvent) {
+        let result = match &mut self.recorder {
+            Some(recorder) => self.machine.record_input(event, recorder),
+            None => self.machine.apply_input(event),
+        };
+        result.expect("keymap only holds valid keys and recordings restart after time jumps");
+    }
+
+    /// Starts the recording over from the current state, after the machine
+    /// jumped in a way inputs can not reproduce
+    fn restart_recording(&mut self) {
+        if self.recorder.is_some() {
+            match self.machine.start_recording() {
+                Ok(recorder) => self.recorder = Some(recorder),
+                Err(error) => self.fail(error.to_string()),
+            }
+        }
+    }
+
+    /// Releases keys whose emulated hold has run out
+    fn age_keys(&mut self) {
+        for key in 0..KEY_COUNT {
+            let held = &mut self.held[key as usize];
+            if *held > 0 {
+                *held -= 1;
+                if *held == 0 {
+                    self.input(InputEvent::Release(key as u16));
+                }
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        if let Err(error) = self.machine.step() {
+            self.fail(error.to_string());
+        }
+    }
+
+    fn run_frame(&mut self) {
+        if self.machine.state().is_halted() {
+            return;
+        }
+        if let Err(error) = self
+            .machine
+            .run_frame()
+            .and_then(|_| self.machine.record_rewind(&mut self.rewind))
+        {
+            self.fail(error.to_string());
+        }
+        // No audio device here; keep the queue from holding stale samples
+        self.machine.audio_mut().take_samples();
+    }
+
+    /// Pauses and steps back one frame
+    fn rewind_frame(&mut self) {
+        self.paused = true;
+        match self.machine.rewind_frame(&mut self.rewind) {
+            Ok(rewound) => {
+                self.error = None;
+                if rewound {
+                    self.restart_recording();
+                }
+            }
+            Err(error) => self.fail(error.to_string()),
+        }
+        self.machine.audio_mut().take_samples();
+    }
+
+    fn fail(&mut self, error: String) {
+        self.error = Some(error);
+        self.paused = true;
+    }
+
+    fn render(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let display = self.machine.display();
+        let (width, height) = (display.width(), display.height());
+
+        // Each cell shows two pixel rows: the upper half block takes the top
+        // pixel's colour, its background the bottom pixel's
+        for row in 0..height / 2 {
+            queue!(out, cursor::MoveTo(0, row as u16))?;
+            for x in 0..width {
+                let top = display.pixel_planes(x, row * 2) as usize % PALETTE.len();
+                let bottom = display.pixel_planes(x, row * 2 + 1) as usize % PALETTE.len();
+                queue!(
+                    out,
+                    SetForegroundColor(PALETTE[top]),
+                    SetBackgroundColor(PALETTE[bottom]),
+                    Print('▀')
+                )?;
+            }
+        }
+        queue!(out, ResetColor)?;
+        self.machine.display_mut().mark_clean();
+
+        let state = self.machine.state();
+        let registers = state.register_file();
+        let mut panel: Vec<String> = registers
+            .registers()
+            .chunks(4)
+            .enumerate()
+            .map(|(line, values)| {
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, value)| format!("V{:X}={:02X}", line * 4 + offset, value))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        panel.push(String::new());
+        panel.push(format!(
+            "I={:04X}  PC={:04X}  SP={:X}",
+            registers.i(),
+            registers.program_counter(),
+            registers.stack_pointer()
+        ));
+        panel.push(format!(
+            "DT={:02X}    ST={:02X}{}",
+            registers.delay_timer(),
+            registers.sound_timer(),
+            if registers.sound_timer() > 0 {
+                "  ♪"
+            } else {
+                ""
+            }
+        ));
+        panel.push(String::new());
+        let next = self.machine.cpu().fetch();
+        panel.push(match next {
+            Ok(inst) => format!("{:04X}  {}", inst.word(), inst.disassemble()),
+            Err(_) => "----".to_string(),
+        });
+        panel.push(String::new());
+        panel.push(if state.is_halted() {
+            "HALTED".to_string()
+        } else if self.paused {
+            "PAUSED".to_string()
+        } else {
+            format!("frame {}", self.machine.timers().frames())
+        });
+        if let Some(error) = &self.error {
+            panel.push(format!("error: {}", error));
+        }
+
+        let column = width as u16 + 2;
+        for (line, text) in panel.iter().enumerate() {
+            queue!(
+                out,
+                cursor::MoveTo(column, line as u16),
+                terminal::Clear(terminal::ClearType::UntilNewLine),
+                Print(text)
+            )?;
+        }
+        out.flush()
+    }
+}
+
+fn run(options: Options) -> io::Result<Result<(), String>> {
+    let quirks = options
+        .quirks
+        .unwrap_or_else(|| options.instruction_set.into());
+    let mut machine = Chip8::with_quirks(options.instruction_set, quirks);
+    if let Err(error) = machine.load_rom_file(&options.rom) {
+        return Ok(Err(format!("cannot load {}: {}", options.rom, error)));
+    }
+    let per_frame = (options.rate / TIMER_FREQUENCY).max(1);
+    machine.timers_mut().set_instructions_per_frame(per_frame);
+
+    let mut app = App {
+        machine,
+        paused: false,
+        quit: false,
+        held: [0; KEY_COUNT as usize],
+        rewind: RewindBuffer::new(1, (REWIND_SECONDS * TIMER_FREQUENCY) as usize),
+        recorder: None,
+        error: None,
+    };
+
+    if let Err(error) = app.machine.record_rewind(&mut app.rewind) {
+        return Ok(Err(error.to_string()));
+    }
+    if options.record.is_some() {
+        match app.machine.start_recording() {
+            Ok(recorder) => app.recorder = Some(recorder),
+            Err(error) => return Ok(Err(error.to_string())),
+        }
+    }
+
+    let mut terminal = Terminal::enter()?;
+    execute!(terminal.out, terminal::Clear(terminal::ClearType::All))?;
+    let frame = Duration::from_secs(1) / TIMER_FREQUENCY;
+    let mut deadline = Instant::now();
+
+    while !app.quit {
+        let now = Instant::now();
+        if now < deadline {
+            if event::poll(deadline - now)? {
+                match event::read()? {
+                    Event::Key(key) => app.handle_key(key, terminal.reports_releases),
+                    Event::Resize(..) => {
+                        execute!(terminal.out, terminal::Clear(terminal::ClearType::All))?
+                    }
+                    _ => {}
+                }
+            }
+            continue;
+        }
+
+        if !app.paused {
+            app.run_frame();
+        }
+        app.age_keys();
+        app.render(&mut terminal.out)?;
+
+        // Fall behind gracefully instead of running a burst of catch-up frames
+        deadline = (deadline + frame).max(now);
+    }
+    drop(terminal);
+
+    if let (Some(path), Some(recorder)) = (&options.record, app.recorder) {
+        let movie = match app.machine.finish_recording(recorder) {
+            Ok(movie) => movie,
+            Err(error) => return Ok(Err(error.to_string())),
+        };
+        if let Err(error) = std::fs::write(path, movie.to_bytes()) {
+            return Ok(Err(format!("cannot write {}: {}", path, error)));
+        }
+    }
+    Ok(Ok(()))
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("tiny-tui: {}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(options) {
+        Ok(Ok(())) => ExitCode::SUCCESS,
+        Ok(Err(error)) => {
+            eprintln!("tiny-tui: {}", error);
+            ExitCode::FAILURE
+        }
+        Err(error) => {
+            eprintln!("tiny-tui: terminal error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    /// An app running `rom` without a terminal
+    fn app(rom: &[u8]) -> App {
+        let mut machine = Chip8::new(Chip8InstructionSet::Chip8);
+        machine.load_rom(rom).unwrap();
+        App {
+            machine,
+            paused: false,
+            quit: false,
+            held: [0; KEY_COUNT as usize],
+            rewind: RewindBuffer::new(1, TIMER_FREQUENCY as usize),
+            recorder: None,
+            error: None,
+        }
+    }
+
+    fn key(c: char, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent::new_with_kind(KeyCode::Char(c), KeyModifiers::NONE, kind)
+    }
+
+    #[test]
+    fn keymap_covers_the_keypad_once() {
+        let mut keys: Vec<u8> = KEYMAP.iter().map(|&(_, key)| key).collect();
+        keys.sort();
+        assert_eq!(keys, (0..KEY_COUNT).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn presses_are_held_for_a_few_frames() {
+        let mut app = app(&[0x12, 0x00]);
+        app.handle_key(key('Q', KeyEventKind::Press), false);
+        assert!(app.machine.keypad().is_pressed(0x4));
+        for _ in 1..KEY_HOLD_FRAMES {
+            app.age_keys();
+        }
+        assert!(app.machine.keypad().is_pressed(0x4));
+        app.age_keys();
+        assert!(!app.machine.keypad().is_pressed(0x4));
+    }
+
+    #[test]
+    fn reported_releases_release_at_once() {
+        let mut app = app(&[0x12, 0x00]);
+        app.handle_key(key('v', KeyEventKind::Press), true);
+        app.age_keys();
+        assert!(app.machine.keypad().is_pressed(0xF));
+        app.handle_key(key('v', KeyEventKind::Release), true);
+        assert!(!app.machine.keypad().is_pressed(0xF));
+    }
+
+    #[test]
+    fn render_draws_the_display_and_registers() {
+        // v3 := 0x2a, spin
+        let mut app = app(&[0x63, 0x2A, 0x12, 0x02]);
+        app.run_frame();
+        let mut out = Vec::new();
+        app.render(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.matches('▀').count(), 64 * 16);
+        assert!(text.contains("V3=2A"));
+        assert!(text.contains("PC=0202"));
+        assert!(text.contains("frame 1"));
+        assert!(!app.machine.display().is_dirty());
+    }
+}
@@ -0,0 +1,89 @@
+use super::AccessKind;
+use std::ops::RangeInclusive;
+
+/// Handle returned when adding a breakpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BreakpointId(pub(super) usize);
+
+/// Handle returned when adding a watchpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchpointId(pub(super) usize);
+
+/// How a register is compared against a [`Condition`] value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// Applies the comparison as `lhs <op> rhs`
+    pub fn compare<T: PartialOrd>(&self, lhs: &T, rhs: &T) -> bool {
+        match self {
+            Self::Equal => lhs == rhs,
+            Self::NotEqual => lhs != rhs,
+            Self::Less => lhs < rhs,
+            Self::LessOrEqual => lhs <= rhs,
+            Self::Greater => lhs > rhs,
+            Self::GreaterOrEqual => lhs >= rhs,
+        }
+    }
+}
+
+/// A register test that must hold for a breakpoint to trigger
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition<R, W> {
+    pub register: R,
+    pub comparison: Comparison,
+    pub value: W,
+}
+
+impl<R, W> Condition<R, W> {
+    pub fn new(register: R, comparison: Comparison, value: W) -> Self {
+        Self {
+            register,
+            comparison,
+            value,
+        }
+    }
+}
+
+/// Stops execution before the instruction at `address` runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint<A, R, W> {
+    pub address: A,
+    /// Only trigger when this register test holds
+    pub condition: Option<Condition<R, W>>,
+    pub enabled: bool,
+}
+
+/// Which accesses a [`Watchpoint`] triggers on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl WatchKind {
+    /// Returns true if an access of `kind` triggers this watch
+    pub fn matches(&self, kind: AccessKind) -> bool {
+        match self {
+            Self::Read => kind == AccessKind::Read,
+            Self::Write => kind == AccessKind::Write,
+            Self::ReadWrite => true,
+        }
+    }
+}
+
+/// Stops execution after an instruction accesses memory in `range`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watchpoint<A> {
+    pub range: RangeInclusive<A>,
+    pub kind: WatchKind,
+    pub enabled: bool,
+}
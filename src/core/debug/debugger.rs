@@ -0,0 +1,291 @@
+use super::{
+    Breakpoint, BreakpointId, Condition, Debuggable, MemoryAccess, WatchKind, Watchpoint,
+    WatchpointId,
+};
+use crate::core::cpu::{Cpu, CpuState};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+type Address<C> = <<C as Cpu>::State as CpuState>::Address;
+type Register<C> = <<C as Cpu>::State as CpuState>::Register;
+type Word<C> = <<C as Cpu>::State as CpuState>::Word;
+type CpuBreakpoint<C> = Breakpoint<Address<C>, Register<C>, Word<C>>;
+type WatchHit<C> = (WatchpointId, MemoryAccess<Address<C>>);
+
+/// Why a [`Debugger`] handed control back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason<A> {
+    /// The requested step, step-over, step-out or run-until finished
+    Completed,
+    /// PC reached an enabled breakpoint whose condition held
+    Breakpoint(BreakpointId),
+    /// The last instruction made an access covered by a watchpoint
+    Watchpoint(WatchpointId, MemoryAccess<A>),
+    /// The instruction budget given to [`Debugger::run_for`] ran out
+    StepLimit,
+}
+
+/// Runs a [`Cpu`] under breakpoint and watchpoint control
+///
+/// Breakpoints are checked before each instruction except the first one of a
+/// run, so resuming from a breakpoint always makes progress. Watchpoints are
+/// checked after each instruction. Errors from the CPU, such as a halt, are
+/// returned unchanged.
+pub struct Debugger<C: Debuggable> {
+    cpu: C,
+    breakpoints: BTreeMap<usize, CpuBreakpoint<C>>,
+    watchpoints: BTreeMap<usize, Watchpoint<Address<C>>>,
+    next_id: usize,
+}
+
+impl<C> Debugger<C>
+where
+    C: Debuggable,
+    Address<C>: Copy + PartialOrd,
+    Register<C>: Copy,
+    Word<C>: PartialOrd,
+{
+    pub fn new(cpu: C) -> Self {
+        Self {
+            cpu,
+            breakpoints: BTreeMap::new(),
+            watchpoints: BTreeMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn cpu(&self) -> &C {
+        &self.cpu
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut C {
+        &mut self.cpu
+    }
+
+    /// Returns the wrapped CPU, dropping all breakpoints and watchpoints
+    pub fn into_inner(self) -> C {
+        self.cpu
+    }
+
+    /// Breaks whenever PC reaches `address`
+    pub fn add_breakpoint(&mut self, address: Address<C>) -> BreakpointId {
+        self.insert_breakpoint(address, None)
+    }
+
+    /// Breaks when PC reaches `address` and `condition` holds
+    pub fn add_conditional_breakpoint(
+        &mut self,
+        address: Address<C>,
+        condition: Condition<Register<C>, Word<C>>,
+    ) -> BreakpointId {
+        self.insert_breakpoint(address, Some(condition))
+    }
+
+    fn insert_breakpoint(
+        &mut self,
+        address: Address<C>,
+        condition: Option<Condition<Register<C>, Word<C>>>,
+    ) -> BreakpointId {
+        let id = self.allocate_id();
+        self.breakpoints.insert(
+            id,
+            Breakpoint {
+                address,
+                condition,
+                enabled: true,
+            },
+        );
+        BreakpointId(id)
+    }
+
+    /// Removes a breakpoint, returning false if it did not exist
+    pub fn remove_breakpoint(&mut self, id: BreakpointId) -> bool {
+        self.breakpoints.remove(&id.0).is_some()
+    }
+
+    /// Enables or disables a breakpoint, returning false if it does not exist
+    pub fn set_breakpoint_enabled(&mut self, id: BreakpointId, enabled: bool) -> bool {
+        self.breakpoints
+            .get_mut(&id.0)
+            .map(|breakpoint| breakpoint.enabled = enabled)
+            .is_some()
+    }
+
+    pub fn breakpoint(&self, id: BreakpointId) -> Option<&CpuBreakpoint<C>> {
+        self.breakpoints.get(&id.0)
+    }
+
+    /// Iterates over all breakpoints in the order they were added
+    pub fn breakpoints(&self) -> impl Iterator<Item = (BreakpointId, &CpuBreakpoint<C>)> {
+        self.breakpoints
+            .iter()
+            .map(|(&id, breakpoint)| (BreakpointId(id), breakpoint))
+    }
+
+    /// Stops after any access of `kind` to an address in `range`
+    pub fn add_watchpoint(
+        &mut self,
+        range: RangeInclusive<Address<C>>,
+        kind: WatchKind,
+    ) -> WatchpointId {
+        let id = self.allocate_id();
+        self.watchpoints.insert(
+            id,
+            Watchpoint {
+                range,
+                kind,
+                enabled: true,
+            },
+        );
+        WatchpointId(id)
+    }
+
+    /// Removes a watchpoint, returning false if it did not exist
+    pub fn remove_watchpoint(&mut self, id: WatchpointId) -> bool {
+        self.watchpoints.remove(&id.0).is_some()
+    }
+
+    /// Enables or disables a watchpoint, returning false if it does not exist
+    pub fn set_watchpoint_enabled(&mut self, id: WatchpointId, enabled: bool) -> bool {
+        self.watchpoints
+            .get_mut(&id.0)
+            .map(|watchpoint| watchpoint.enabled = enabled)
+            .is_some()
+    }
+
+    pub fn watchpoint(&self, id: WatchpointId) -> Option<&Watchpoint<Address<C>>> {
+        self.watchpoints.get(&id.0)
+    }
+
+    /// Iterates over all watchpoints in the order they were added
+    pub fn watchpoints(&self) -> impl Iterator<Item = (WatchpointId, &Watchpoint<Address<C>>)> {
+        self.watchpoints
+            .iter()
+            .map(|(&id, watchpoint)| (WatchpointId(id), watchpoint))
+    }
+
+    fn allocate_id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Executes a single instruction
+    pub fn step(&mut self) -> Result<StopReason<Address<C>>, C::Error> {
+        self.run_until(None, |_| true)
+    }
+
+    /// Steps over a subroutine call at PC, running until it returns
+    ///
+    /// Any other instruction is single-stepped.
+    pub fn step_over(&mut self) -> Result<StopReason<Address<C>>, C::Error> {
+        if !self.cpu.is_call() {
+            return self.step();
+        }
+        let depth = self.cpu.call_depth();
+        self.run_until(None, |cpu| cpu.call_depth() <= depth)
+    }
+
+    /// Runs until the current subroutine returns to its caller
+    ///
+    /// Outside any subroutine this only stops at breakpoints and watchpoints.
+    pub fn step_out(&mut self) -> Result<StopReason<Address<C>>, C::Error> {
+        let depth = self.cpu.call_depth();
+        self.run_until(None, |cpu| cpu.call_depth() < depth)
+    }
+
+    /// Runs until PC reaches `address`
+    pub fn run_to(&mut self, address: Address<C>) -> Result<StopReason<Address<C>>, C::Error> {
+        self.run_until(None, |cpu| cpu.state().get_program_counter() == address)
+    }
+
+    /// Runs until a breakpoint or watchpoint triggers
+    pub fn run(&mut self) -> Result<StopReason<Address<C>>, C::Error> {
+        self.run_until(None, |_| false)
+    }
+
+    /// Runs like [`run`](Self::run), executing at most `steps` instructions
+    pub fn run_for(&mut self, steps: u64) -> Result<StopReason<Address<C>>, C::Error> {
+        self.run_until(Some(steps), |_| false)
+    }
+
+    /// Steps until `done` holds after an instruction, a breakpoint or
+    /// watchpoint triggers or `limit` instructions have run
+    fn run_until(
+        &mut self,
+        limit: Option<u64>,
+        mut done: impl FnMut(&C) -> bool,
+    ) -> Result<StopReason<Address<C>>, C::Error> {
+        let mut steps = 0;
+        loop {
+            if limit.is_some_and(|limit| steps >= limit) {
+                return Ok(StopReason::StepLimit);
+            }
+            if steps > 0 {
+                if let Some(id) = self.triggered_breakpoint()? {
+                    return Ok(StopReason::Breakpoint(id));
+                }
+            }
+
+            if let Some((id, access)) = self.execute()? {
+                return Ok(StopReason::Watchpoint(id, access));
+            }
+            steps += 1;
+
+            if done(&self.cpu) {
+                return Ok(StopReason::Completed);
+            }
+        }
+    }
+
+    /// Returns the first enabled breakpoint at PC whose condition holds
+    fn triggered_breakpoint(&self) -> Result<Option<BreakpointId>, C::Error> {
+        let state = self.cpu.state();
+        let pc = state.get_program_counter();
+        for (&id, breakpoint) in &self.breakpoints {
+            if !breakpoint.enabled || breakpoint.address != pc {
+                continue;
+            }
+            let holds = match &breakpoint.condition {
+                Some(condition) => {
+                    let value = state.read_register(condition.register)?;
+                    condition.comparison.compare(&value, &condition.value)
+                }
+                None => true,
+            };
+            if holds {
+                return Ok(Some(BreakpointId(id)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Executes one instruction, returning the first watched access it made
+    fn execute(&mut self) -> Result<Option<WatchHit<C>>, C::Error> {
+        let watching = self
+            .watchpoints
+            .values()
+            .any(|watchpoint| watchpoint.enabled);
+        if !watching {
+            self.cpu.step()?;
+            return Ok(None);
+        }
+
+        self.cpu.set_access_recording(true);
+        let result = self.cpu.step();
+        let accesses = self.cpu.take_accesses();
+        self.cpu.set_access_recording(false);
+        result?;
+
+        for access in accesses {
+            let watchpoint = self.watchpoints.iter().find(|(_, watchpoint)| {
+                watchpoint.enabled
+                    && watchpoint.kind.matches(access.kind)
+                    && watchpoint.range.contains(&access.address)
+            });
+            if let Some((&id, _)) = watchpoint {
+                return Ok(Some((WatchpointId(id), access)));
+            }
+        }
+        Ok(None)
+    }
+}
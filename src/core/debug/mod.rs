@@ -0,0 +1,62 @@
+//! Architecture-generic debugging
+//!
+//! [`Debugger`] wraps any [`Cpu`] implementing [`Debuggable`] and adds:
+//!
+//! - PC breakpoints, optionally conditional on a register value ([`Condition`])
+//! - Memory read/write watchpoints ([`Watchpoint`])
+//! - Single-step, step-over, step-out and run-until
+//!
+//! # Example
+//!
+//! ```rust
+//! use tiny_computers::arch::chip_8::{assemble, Chip8Cpu, Chip8InstructionSet};
+//! use tiny_computers::core::debug::{Debugger, StopReason};
+//!
+//! let mut cpu = Chip8Cpu::new(Chip8InstructionSet::Chip8);
+//! cpu.load_rom(&assemble("v0 := 1 v0 += 1 : end jump end").unwrap()).unwrap();
+//!
+//! let mut debugger = Debugger::new(cpu);
+//! let breakpoint = debugger.add_breakpoint(0x202);
+//! assert_eq!(debugger.run().unwrap(), StopReason::Breakpoint(breakpoint));
+//! ```
+
+mod breakpoint;
+mod debugger;
+
+pub use breakpoint::{
+    Breakpoint, BreakpointId, Comparison, Condition, WatchKind, Watchpoint, WatchpointId,
+};
+pub use debugger::{Debugger, StopReason};
+
+use crate::core::cpu::{Cpu, CpuState};
+
+/// Whether a memory access read or wrote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// A single data access made by an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess<A> {
+    pub address: A,
+    pub kind: AccessKind,
+}
+
+/// The architecture-specific hooks a [`Debugger`] needs beyond [`Cpu`]
+pub trait Debuggable: Cpu {
+    /// Returns the current subroutine nesting depth
+    fn call_depth(&self) -> usize;
+
+    /// Returns true if the instruction at PC calls a subroutine
+    fn is_call(&self) -> bool;
+
+    /// Starts or stops recording data accesses
+    ///
+    /// Instruction fetches are not data accesses and are never recorded.
+    fn set_access_recording(&mut self, enabled: bool);
+
+    /// Removes and returns the accesses recorded so far, oldest first
+    fn take_accesses(&mut self) -> Vec<MemoryAccess<<Self::State as CpuState>::Address>>;
+}
@@ -11,28 +11,15 @@
 //!
 //! # Example
 //!
-//! ```rust,no_run
-//! use my_emu::core::isa::{InstructionSet, InstructionCategory};
+//! ```rust
+//! use tiny_computers::arch::chip_8::Chip8InstructionSet;
+//! use tiny_computers::core::isa::{InstructionCategory, InstructionSet};
 //!
-//! struct MyISA;
+//! let isa = Chip8InstructionSet::Chip8;
 //!
-//! impl InstructionSet for MyISA {
-//!     // Implementation details...
-//! #    type Instruction = ();
-//! #    type Opcode = u8;
-//! #    type Register = u8;
-//! #    type Address = u16;
-//! #    type Word = u8;
-//! #    type Error = ();
-//!
-//! #    fn name(&self) -> &str { "MyISA" }
-//! #    fn word_size(&self) -> u8 { 8 }
-//! #    fn address_size(&self) -> u8 { 16 }
-//! #    fn register_count(&self) -> usize { 8 }
-//! #    fn is_valid_opcode(&self, _: Self::Opcode) -> bool { true }
-//! #    fn categorize(&self, _: Self::Opcode) -> InstructionCategory { InstructionCategory::Arithmetic }
-//! #    fn opcodes_in_category(&self, _: InstructionCategory) -> Vec<Self::Opcode> { vec![] }
-//! }
+//! assert_eq!(isa.name(), "CHIP-8");
+//! assert_eq!(isa.register_count(), 16);
+//! assert_eq!(isa.categorize(0x8), InstructionCategory::Logic);
 //! ```
 
 mod addressing;
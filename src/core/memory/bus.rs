@@ -1,4 +1,5 @@
-use super::{BoxedMemoryDevice, MemoryDevice};
+use super::{Addressing, BoxedMemoryDevice, MemoryDevice, MemoryError};
+use std::ops::{BitAnd, Sub};
 
 /// A trait that extends MemoryDevice to provide memory mapping capabilities.
 /// This allows for attaching and removing devices at specific address ranges,
@@ -23,6 +24,82 @@ pub trait MemoryBus: MemoryDevice {
         device: BoxedMemoryDevice<Self::Address, Self::Word, Self::Error>,
     ) -> Result<(), Self::Error>;
 
+    /// Attaches a memory device like [`attach_device`](Self::attach_device),
+    /// choosing how bus addresses are translated for it
+    ///
+    /// With [`Addressing::Relative`] the device sees `start_addr` as address
+    /// zero, the way real hardware decodes a chip select. Translating needs
+    /// address arithmetic, hence the bounds; buses that cannot translate keep
+    /// the default, which only accepts [`Addressing::Absolute`].
+    ///
+    /// # Arguments
+    /// * `start_addr` - Starting address of the range
+    /// * `end_addr` - Ending address of the range (inclusive)
+    /// * `write_only` - If true, the device is read-only
+    /// * `addressing` - How addresses are passed to the device
+    /// * `device` - The memory device to attach
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the device was successfully attached
+    /// * `Err(MemoryError::BusError)` - If the bus does not support
+    ///   `addressing`
+    /// * `Err(error)` - If the device could not be attached
+    fn attach_device_with(
+        &mut self,
+        start_addr: Self::Address,
+        end_addr: Self::Address,
+        write_only: bool,
+        addressing: Addressing<Self::Address>,
+        device: BoxedMemoryDevice<Self::Address, Self::Word, Self::Error>,
+    ) -> Result<(), Self::Error>
+    where
+        Self::Address: Sub<Output = Self::Address> + BitAnd<Output = Self::Address> + Into<u64>,
+        Self::Error: From<MemoryError>,
+    {
+        match addressing {
+            Addressing::Absolute => self.attach_device(start_addr, end_addr, write_only, device),
+            _ => Err(MemoryError::BusError.into()),
+        }
+    }
+
+    /// Attaches a memory device mirrored across the address range
+    ///
+    /// The device receives the offset from `start_addr` ANDed with `mask`,
+    /// see [`Addressing::Mirrored`]. The whole range counts as taken for
+    /// overlap checks.
+    ///
+    /// # Arguments
+    /// * `start_addr` - Starting address of the range
+    /// * `end_addr` - Ending address of the range (inclusive)
+    /// * `mask` - The address bits the device decodes: one less than a power
+    ///   of two, no larger than the device's size minus one
+    /// * `write_only` - If true, the device is read-only
+    /// * `device` - The memory device to attach
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the device was successfully attached
+    /// * `Err(error)` - If the device could not be attached
+    fn attach_mirrored(
+        &mut self,
+        start_addr: Self::Address,
+        end_addr: Self::Address,
+        mask: Self::Address,
+        write_only: bool,
+        device: BoxedMemoryDevice<Self::Address, Self::Word, Self::Error>,
+    ) -> Result<(), Self::Error>
+    where
+        Self::Address: Sub<Output = Self::Address> + BitAnd<Output = Self::Address> + Into<u64>,
+        Self::Error: From<MemoryError>,
+    {
+        self.attach_device_with(
+            start_addr,
+            end_addr,
+            write_only,
+            Addressing::Mirrored { mask },
+            device,
+        )
+    }
+
     /// Removes a device from the bus at the specified starting address
     ///
     /// # Arguments
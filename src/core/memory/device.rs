@@ -1,5 +1,7 @@
 use super::MemoryError;
+use crate::core::savestate::Snapshot;
 use std::fmt::Debug;
+use std::ops::{BitAnd, Sub};
 
 pub type BoxedMemoryDevice<A, W, E> = Box<dyn MemoryDevice<Address = A, Word = W, Error = E>>;
 
@@ -39,6 +41,40 @@ pub trait MemoryDevice: Debug {
 
     /// Returns the total size of the memory device in bytes
     fn size(&self) -> usize;
+
+    /// Returns the device's own save-state support
+    ///
+    /// A [`MemoryMapper`](super::MemoryMapper) saves its devices through
+    /// this rather than by reading and writing every address, which would
+    /// fail on ROM and trigger side effects on I/O registers. The default
+    /// `None` leaves the device out of save states.
+    fn snapshot(&self) -> Option<&dyn Snapshot> {
+        None
+    }
+
+    /// Mutable counterpart of [`snapshot`](Self::snapshot), used to restore
+    fn snapshot_mut(&mut self) -> Option<&mut dyn Snapshot> {
+        None
+    }
+}
+
+/// How bus addresses are translated before they reach a mapped device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Addressing<A> {
+    /// The device receives the bus address unchanged
+    #[default]
+    Absolute,
+    /// The device receives the offset from the start of its range, so the
+    /// same device type works at any base address
+    Relative,
+    /// The device receives the offset from the start of its range ANDed with
+    /// `mask`, so a device smaller than its range repeats across it
+    ///
+    /// This models partial address decoding: a 2 KiB RAM that only sees
+    /// address lines A0-A10 is attached with a mask of 0x7FF and appears
+    /// every 2 KiB of its range. Unlike attaching copies, every mirror shares
+    /// the one device's state.
+    Mirrored { mask: A },
 }
 
 /// Represents a mapped memory device with its address range and access properties
@@ -55,6 +91,11 @@ where
     end_addr: A,
     /// If true, the device is read-only
     write_only: bool,
+    /// How addresses are translated for the device
+    addressing: Addressing<A>,
+    /// Applies `addressing`, set together with it so that only attaching
+    /// with a translation needs address arithmetic
+    translate: fn(Addressing<A>, A, A) -> A,
     /// The actual memory device
     device: BoxedMemoryDevice<A, W, E>,
 }
@@ -75,10 +116,22 @@ where
             start_addr,
             end_addr,
             write_only,
+            addressing: Addressing::Absolute,
+            translate: |_, _, address| address,
             device,
         }
     }
 
+    /// Sets how addresses are translated for the device
+    pub fn with_addressing(mut self, addressing: Addressing<A>) -> Self
+    where
+        A: Sub<Output = A> + BitAnd<Output = A>,
+    {
+        self.addressing = addressing;
+        self.translate = translate;
+        self
+    }
+
     pub fn start_addr(&self) -> A {
         self.start_addr
     }
@@ -91,12 +144,36 @@ where
         self.write_only
     }
 
+    pub fn addressing(&self) -> Addressing<A> {
+        self.addressing
+    }
+
+    /// Translates a bus address inside the range into the device's address
+    fn device_address(&self, address: A) -> A {
+        match self.addressing {
+            Addressing::Absolute => address,
+            addressing => (self.translate)(addressing, self.start_addr, address),
+        }
+    }
+
     /// Consumes the MappedDevice and returns the inner device
     pub fn into_device(self) -> BoxedMemoryDevice<A, W, E> {
         self.device
     }
 }
 
+/// Translates `address` inside a range starting at `start` for its device
+fn translate<A>(addressing: Addressing<A>, start: A, address: A) -> A
+where
+    A: Sub<Output = A> + BitAnd<Output = A>,
+{
+    match addressing {
+        Addressing::Absolute => address,
+        Addressing::Relative => address - start,
+        Addressing::Mirrored { mask } => (address - start) & mask,
+    }
+}
+
 impl<A, W, E> MemoryDevice for MappedDevice<A, W, E>
 where
     A: Copy + Ord + Debug,
@@ -111,7 +188,7 @@ where
         if address < self.start_addr || address > self.end_addr {
             return Err(MemoryError::AddressOutOfBounds.into());
         }
-        self.device.read(address)
+        self.device.read(self.device_address(address))
     }
 
     fn write(&mut self, address: Self::Address, value: Self::Word) -> Result<(), Self::Error> {
@@ -121,7 +198,7 @@ where
         if self.write_only {
             return Err(MemoryError::ReadOnlyMemory.into());
         }
-        self.device.write(address, value)
+        self.device.write(self.device_address(address), value)
     }
 
     fn reset(&mut self) {
@@ -131,4 +208,12 @@ where
     fn size(&self) -> usize {
         self.device.size()
     }
+
+    fn snapshot(&self) -> Option<&dyn Snapshot> {
+        self.device.snapshot()
+    }
+
+    fn snapshot_mut(&mut self) -> Option<&mut dyn Snapshot> {
+        self.device.snapshot_mut()
+    }
 }
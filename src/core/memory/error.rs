@@ -1,3 +1,4 @@
+use std::error::Error;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 
 /// Represents errors that can occur during memory operations
@@ -13,6 +14,9 @@ pub enum MemoryError {
     DeviceNotFound,
     /// Attempted to attach a device to an invalid address range
     InvalidAddressRange,
+    /// Attempted to mirror a device with a mask that is not one less than a
+    /// power of two, or that reaches past the end of the device
+    InvalidMirrorMask,
     /// For more specific access control errors
     DeviceAccessViolation,
     /// For general bus-related issues
@@ -24,3 +28,5 @@ impl Display for MemoryError {
         write!(f, "Memory error: {:?}", self)
     }
 }
+
+impl Error for MemoryError {}
@@ -1,9 +1,18 @@
-use super::{MappedDevice, MemoryBus, MemoryDevice, MemoryError};
+use super::{Addressing, BoxedMemoryDevice, MappedDevice, MemoryBus, MemoryDevice, MemoryError};
+use crate::core::savestate::{SaveStateError, Snapshot, StateReader, StateWriter};
 use std::fmt::Debug;
+use std::ops::{BitAnd, Sub};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// A memory mapper that manages multiple devices in different address ranges.
 /// It implements both MemoryDevice and MemoryBus traits to provide a complete
 /// memory management system.
+///
+/// Devices are kept sorted by address, so finding the device for an access is
+/// a binary search. Accesses tend to hit the same device many times in a row,
+/// so the device found last is checked before searching. The cache is an
+/// atomic rather than a `Cell`, so lookups through `&self` do not by
+/// themselves make the mapper `!Sync`.
 #[derive(Debug)]
 pub struct MemoryMapper<A, V, E>
 where
@@ -11,8 +20,10 @@ where
     V: Debug,
     E: From<MemoryError> + Debug,
 {
-    /// Vector of mapped devices managed by this mapper
+    /// Mapped devices managed by this mapper, sorted by start address
     devices: Vec<MappedDevice<A, V, E>>,
+    /// Index of the device that served the last access
+    last_hit: AtomicUsize,
 }
 
 /// Implementation of the MemoryDevice trait for MemoryMapper.
@@ -37,12 +48,10 @@ where
     /// * `Ok(value)` - The value read from the mapped device
     /// * `Err(error)` - If no device is mapped to the address
     fn read(&self, address: Self::Address) -> Result<Self::Word, Self::Error> {
-        for device in self.devices.iter() {
-            if address >= device.start_addr() && address <= device.end_addr() {
-                return device.read(address);
-            }
+        match self.find(address) {
+            Some(index) => self.devices[index].read(address),
+            None => Err(MemoryError::AddressOutOfBounds.into()),
         }
-        Err(MemoryError::AddressOutOfBounds.into())
     }
 
     /// Writes a value to the appropriate mapped device based on the address.
@@ -55,12 +64,10 @@ where
     /// * `Ok(())` - If the write was successful
     /// * `Err(error)` - If no device is mapped to the address
     fn write(&mut self, address: Self::Address, value: Self::Word) -> Result<(), Self::Error> {
-        for device in self.devices.iter_mut() {
-            if address >= device.start_addr() && address <= device.end_addr() {
-                return device.write(address, value);
-            }
+        match self.find(address) {
+            Some(index) => self.devices[index].write(address, value),
+            None => Err(MemoryError::AddressOutOfBounds.into()),
         }
-        Err(MemoryError::AddressOutOfBounds.into())
     }
 
     /// Resets all mapped devices to their initial state
@@ -71,6 +78,8 @@ where
     }
 
     /// Returns the total size of all mapped devices in bytes
+    ///
+    /// A mirrored device counts once, however often it repeats on the bus.
     fn size(&self) -> usize {
         self.devices.iter().map(|device| device.size()).sum()
     }
@@ -100,22 +109,46 @@ where
         start_addr: Self::Address,
         end_addr: Self::Address,
         write_only: bool,
-        device: Box<
-            dyn MemoryDevice<Address = Self::Address, Word = Self::Word, Error = Self::Error>,
-        >,
+        device: BoxedMemoryDevice<Self::Address, Self::Word, Self::Error>,
     ) -> Result<(), Self::Error> {
-        if end_addr < start_addr {
-            return Err(MemoryError::InvalidAddressRange.into());
-        }
-        if self.devices.iter().any(|d| {
-            (start_addr >= d.start_addr() && start_addr <= d.end_addr())
-                || (end_addr >= d.start_addr() && end_addr <= d.end_addr())
-        }) {
-            return Err(MemoryError::DeviceAlreadyAttached.into());
+        self.insert(MappedDevice::new(start_addr, end_addr, write_only, device))
+    }
+
+    /// Attaches a memory device to the specified address range, translating
+    /// addresses for it as `addressing` says.
+    ///
+    /// # Arguments
+    /// * `start_addr` - The starting address of the range
+    /// * `end_addr` - The ending address of the range
+    /// * `write_only` - Whether the device is write-only
+    /// * `addressing` - How addresses are passed to the device
+    /// * `device` - The memory device to attach
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the device was successfully attached
+    /// * `Err(error)` - If the address range overlaps with an existing device
+    /// * `Err(MemoryError::InvalidMirrorMask)` - If a mirrored device's mask is
+    ///   not one less than a power of two or exceeds the device's size
+    fn attach_device_with(
+        &mut self,
+        start_addr: Self::Address,
+        end_addr: Self::Address,
+        write_only: bool,
+        addressing: Addressing<Self::Address>,
+        device: BoxedMemoryDevice<Self::Address, Self::Word, Self::Error>,
+    ) -> Result<(), Self::Error>
+    where
+        A: Sub<Output = A> + BitAnd<Output = A> + Into<u64>,
+    {
+        if let Addressing::Mirrored { mask } = addressing {
+            let mask: u64 = mask.into();
+            if mask & mask.wrapping_add(1) != 0 || mask >= device.size() as u64 {
+                return Err(MemoryError::InvalidMirrorMask.into());
+            }
         }
-        self.devices
-            .push(MappedDevice::new(start_addr, end_addr, write_only, device));
-        Ok(())
+        self.insert(
+            MappedDevice::new(start_addr, end_addr, write_only, device).with_addressing(addressing),
+        )
     }
 
     /// Removes and returns the device at the specified starting address.
@@ -132,15 +165,11 @@ where
     ) -> Option<
         Box<dyn MemoryDevice<Address = Self::Address, Word = Self::Word, Error = Self::Error>>,
     > {
-        if let Some(index) = self
+        let index = self
             .devices
-            .iter()
-            .position(|device| device.start_addr() == start_addr)
-        {
-            Some(self.devices.remove(index).into_device())
-        } else {
-            None
-        }
+            .binary_search_by(|device| device.start_addr().cmp(&start_addr))
+            .ok()?;
+        Some(self.devices.remove(index).into_device())
     }
 }
 
@@ -155,6 +184,7 @@ where
     fn default() -> Self {
         Self {
             devices: Vec::new(),
+            last_hit: AtomicUsize::new(0),
         }
     }
 }
@@ -201,10 +231,111 @@ where
             return Err(MemoryError::InvalidAddressRange.into());
         }
 
-        Ok(!self.devices.iter().any(|d| {
-            (start_addr >= d.start_addr() && start_addr <= d.end_addr())
-                || (end_addr >= d.start_addr() && end_addr <= d.end_addr())
-                || (start_addr <= d.start_addr() && end_addr >= d.end_addr())
-        }))
+        // Ranges never overlap, so the last device starting at or before
+        // `end_addr` is the only one that can reach into the range
+        let index = self
+            .devices
+            .partition_point(|device| device.start_addr() <= end_addr);
+        Ok(index == 0 || self.devices[index - 1].end_addr() < start_addr)
+    }
+
+    /// Inserts `device` in address order unless its range is taken
+    fn insert(&mut self, device: MappedDevice<A, V, E>) -> Result<(), E> {
+        let (start_addr, end_addr) = (device.start_addr(), device.end_addr());
+        if end_addr < start_addr {
+            return Err(MemoryError::InvalidAddressRange.into());
+        }
+        if !self.is_range_available(start_addr, end_addr)? {
+            return Err(MemoryError::DeviceAlreadyAttached.into());
+        }
+        let index = self
+            .devices
+            .partition_point(|device| device.start_addr() < start_addr);
+        self.devices.insert(index, device);
+        Ok(())
+    }
+
+    /// Returns the index of the device mapped at `address`
+    fn find(&self, address: A) -> Option<usize> {
+        let contains = |device: &MappedDevice<A, V, E>| {
+            address >= device.start_addr() && address <= device.end_addr()
+        };
+        let last_hit = self.last_hit.load(Ordering::Relaxed);
+        if self.devices.get(last_hit).is_some_and(contains) {
+            return Some(last_hit);
+        }
+        let index = self
+            .devices
+            .partition_point(|device| device.start_addr() <= address)
+            .checked_sub(1)?;
+        if !contains(&self.devices[index]) {
+            return None;
+        }
+        self.last_hit.store(index, Ordering::Relaxed);
+        Some(index)
+    }
+}
+
+/// Captures the device layout and the state of every device that has one.
+///
+/// Each device saves itself through [`MemoryDevice::snapshot`], once however
+/// often it is mirrored; devices without save-state support, such as ROMs,
+/// are recorded by address range only. Restoring requires the same devices to
+/// be attached at the same ranges; the devices themselves are not recreated.
+impl<A, W, E> Snapshot for MemoryMapper<A, W, E>
+where
+    A: Copy + Ord + Debug + Into<u64>,
+    W: Copy + Debug,
+    E: From<MemoryError> + Debug,
+{
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        writer.write(&(self.devices.len() as u64));
+        for device in &self.devices {
+            writer.write(&device.start_addr().into());
+            writer.write(&device.end_addr().into());
+            writer.write(&device.write_only());
+            let Some(snapshot) = device.snapshot() else {
+                writer.write(&false);
+                continue;
+            };
+            let mut state = StateWriter::new();
+            snapshot.write_state(&mut state)?;
+            writer.write(&true);
+            writer.write_bytes(state.as_bytes());
+        }
+        Ok(())
+    }
+
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        let count = reader.read::<u64>()?;
+        if count != self.devices.len() as u64 {
+            return Err(SaveStateError::Device(format!(
+                "{} mapped devices, expected {}",
+                count,
+                self.devices.len()
+            )));
+        }
+        for device in self.devices.iter_mut() {
+            let start = reader.read::<u64>()?;
+            let end = reader.read::<u64>()?;
+            let write_only = reader.read::<bool>()?;
+            let saved = reader.read::<bool>()?;
+            if start != device.start_addr().into()
+                || end != device.end_addr().into()
+                || write_only != device.write_only()
+                || saved != device.snapshot().is_some()
+            {
+                return Err(SaveStateError::Device(format!(
+                    "device at {:#x}..={:#x} does not match the attached devices",
+                    start, end
+                )));
+            }
+            if let Some(snapshot) = device.snapshot_mut() {
+                let mut state = StateReader::new(reader.read_bytes()?);
+                snapshot.read_state(&mut state)?;
+                state.finish()?;
+            }
+        }
+        Ok(())
     }
 }
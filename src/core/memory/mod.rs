@@ -10,21 +10,39 @@
 //! - [`MemoryBus`]: Trait for managing multiple mapped memory devices
 //! - [`MemoryMapper`]: Implementation of a memory bus that maps devices to address ranges
 //! - [`MappedDevice`]: A memory device with its address range and access properties
+//! - [`Ram`] and [`Rom`]: Ready-made read-write and read-only devices
+//!
+//! Devices receive bus addresses unchanged unless attached with
+//! [`Addressing::Relative`], in which case they see the offset from the start
+//! of their range and need not know where they are mapped. A device attached
+//! with [`Addressing::Mirrored`] additionally has that offset masked, so it
+//! repeats across a range larger than itself as with partial address decoding.
 //!
 //! # Example
 //!
 //! ```
-//! use tiny_computers::core::memory::{MemoryDevice, MemoryBus, MemoryMapper};
+//! use tiny_computers::core::memory::{
+//!     Addressing, MemoryBus, MemoryDevice, MemoryError, MemoryMapper, Ram, Rom,
+//! };
 //!
 //! // Create a new memory mapper
-//! let mut mapper = MemoryMapper::<u16, u8, Box<dyn std::error::Error>>::new();
+//! let mut mapper = MemoryMapper::<u16, u8, MemoryError>::new();
 //!
 //! // Attach devices to specific address ranges
-//! let rom = Box::new(/* some ROM device */);
-//! mapper.attach_device(0x0000, 0x1FFF, true, rom);
+//! let mut rom = Rom::with_fill(0x2000, 0xFF);
+//! rom.load(0, &[0xC3, 0x00, 0x20]).unwrap();
+//! mapper.attach_device(0x0000, 0x1FFF, true, Box::new(rom)).unwrap();
+//!
+//! // Relative addressing hands the RAM offsets from 0x2000
+//! let ram = Ram::new(0x2000);
+//! mapper
+//!     .attach_device_with(0x2000, 0x3FFF, false, Addressing::Relative, Box::new(ram))
+//!     .unwrap();
 //!
-//! let ram = Box::new(/* some RAM device */);
-//! mapper.attach_device(0x2000, 0x3FFF, false, ram);
+//! mapper.write(0x2000, 0x42).unwrap();
+//! assert_eq!(mapper.read(0x2000), Ok(0x42));
+//! assert_eq!(mapper.read(0x0000), Ok(0xC3));
+//! assert_eq!(mapper.write(0x0000, 0x00), Err(MemoryError::ReadOnlyMemory));
 //! ```
 //!
 //! # Memory Map Example
@@ -41,8 +59,12 @@ mod bus;
 mod device;
 mod error;
 mod mapper;
+mod ram;
+mod rom;
 
 pub use bus::MemoryBus;
-pub use device::{BoxedMemoryDevice, MappedDevice, MemoryDevice};
+pub use device::{Addressing, BoxedMemoryDevice, MappedDevice, MemoryDevice};
 pub use error::MemoryError;
 pub use mapper::MemoryMapper;
+pub use ram::Ram;
+pub use rom::Rom;
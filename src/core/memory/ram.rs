@@ -0,0 +1,184 @@
+use super::{MemoryDevice, MemoryError};
+use crate::core::savestate::{SaveStateError, Snapshot, StateReader, StateValue, StateWriter};
+use std::fmt::Debug;
+use std::io;
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// Read-write memory of a fixed number of words
+///
+/// Addresses are taken relative to a base address, zero unless set with
+/// [`with_base`](Self::with_base), so a RAM attached to a
+/// [`MemoryMapper`](super::MemoryMapper) at 0x2000 answers bus address 0x2000
+/// with its first word. A RAM attached with
+/// [`Addressing::Relative`](super::Addressing::Relative) keeps the base at
+/// zero instead. Resetting fills the whole RAM with its fill pattern.
+///
+/// `E` is the error type of the bus the RAM is attached to.
+#[derive(Debug)]
+pub struct Ram<A, W, E = MemoryError> {
+    data: Vec<W>,
+    fill: W,
+    base: A,
+    _error: PhantomData<fn() -> E>,
+}
+
+impl<A, W, E> Ram<A, W, E>
+where
+    A: Copy + Default + Into<u64>,
+    W: Copy + Default,
+{
+    /// Creates a RAM of `size` words, all zero
+    pub fn new(size: usize) -> Self {
+        Self::with_fill(size, W::default())
+    }
+
+    /// Creates a RAM of `size` words set to `fill`, the value it also
+    /// returns to on reset
+    pub fn with_fill(size: usize, fill: W) -> Self {
+        Self {
+            data: vec![fill; size],
+            fill,
+            base: A::default(),
+            _error: PhantomData,
+        }
+    }
+
+    /// Creates a RAM holding a copy of `data`, sized to fit it
+    pub fn from_slice(data: &[W]) -> Self {
+        let mut ram = Self::new(data.len());
+        ram.data.copy_from_slice(data);
+        ram
+    }
+}
+
+impl<A, W, E> Ram<A, W, E>
+where
+    A: Copy + Into<u64>,
+    W: Copy,
+{
+    /// Makes the first word answer at address `base`
+    pub fn with_base(mut self, base: A) -> Self {
+        self.base = base;
+        self
+    }
+
+    pub fn base(&self) -> A {
+        self.base
+    }
+
+    /// Returns the value every word is set to on reset
+    pub fn fill_value(&self) -> W {
+        self.fill
+    }
+
+    pub fn as_slice(&self) -> &[W] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [W] {
+        &mut self.data
+    }
+
+    /// Copies `data` in starting at word `offset`, ignoring the base address
+    ///
+    /// # Returns
+    /// * `Err(MemoryError::AddressOutOfBounds)` - If `data` does not fit
+    pub fn load(&mut self, offset: usize, data: &[W]) -> Result<(), MemoryError> {
+        let end = offset
+            .checked_add(data.len())
+            .filter(|&end| end <= self.data.len())
+            .ok_or(MemoryError::AddressOutOfBounds)?;
+        self.data[offset..end].copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Translates a bus address into an index into `data`
+    pub(super) fn offset(&self, address: A) -> Result<usize, MemoryError> {
+        address
+            .into()
+            .checked_sub(self.base.into())
+            .and_then(|offset| usize::try_from(offset).ok())
+            .filter(|&offset| offset < self.data.len())
+            .ok_or(MemoryError::AddressOutOfBounds)
+    }
+}
+
+impl<A, E> Ram<A, u8, E>
+where
+    A: Copy + Into<u64>,
+{
+    /// Reads the file at `path` into the RAM starting at byte `offset`, like
+    /// [`load`](Self::load)
+    pub fn load_file(&mut self, offset: usize, path: impl AsRef<Path>) -> io::Result<()> {
+        let data = std::fs::read(path)?;
+        self.load(offset, &data)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl<A, W, E> MemoryDevice for Ram<A, W, E>
+where
+    A: Copy + Into<u64> + Debug,
+    W: Copy + Debug + StateValue,
+    E: From<MemoryError> + Debug,
+{
+    type Address = A;
+    type Word = W;
+    type Error = E;
+
+    fn read(&self, address: Self::Address) -> Result<Self::Word, Self::Error> {
+        Ok(self.data[self.offset(address)?])
+    }
+
+    fn write(&mut self, address: Self::Address, value: Self::Word) -> Result<(), Self::Error> {
+        let offset = self.offset(address)?;
+        self.data[offset] = value;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.data.fill(self.fill);
+    }
+
+    /// Returns the size in words
+    fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    fn snapshot(&self) -> Option<&dyn Snapshot> {
+        Some(self)
+    }
+
+    fn snapshot_mut(&mut self) -> Option<&mut dyn Snapshot> {
+        Some(self)
+    }
+}
+
+impl<A, W, E> Snapshot for Ram<A, W, E>
+where
+    W: Copy + StateValue,
+{
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        writer.write(&(self.data.len() as u64));
+        for word in &self.data {
+            writer.write(word);
+        }
+        Ok(())
+    }
+
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        let len = reader.read::<u64>()?;
+        if len != self.data.len() as u64 {
+            return Err(SaveStateError::Device(format!(
+                "RAM of {} words, expected {}",
+                len,
+                self.data.len()
+            )));
+        }
+        for word in self.data.iter_mut() {
+            *word = reader.read()?;
+        }
+        Ok(())
+    }
+}
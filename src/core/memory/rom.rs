@@ -0,0 +1,122 @@
+use super::{MemoryDevice, MemoryError, Ram};
+use crate::core::savestate::StateValue;
+use std::fmt::Debug;
+use std::io;
+use std::path::Path;
+
+/// Read-only memory of a fixed number of words
+///
+/// Behaves like [`Ram`] except that writes through [`MemoryDevice::write`]
+/// fail with [`MemoryError::ReadOnlyMemory`] and resetting keeps the
+/// contents. The host programs it with [`load`](Self::load) or
+/// [`load_file`](Self::load_file); words not loaded hold the fill pattern,
+/// e.g. 0xFF for an erased EPROM.
+#[derive(Debug)]
+pub struct Rom<A, W, E = MemoryError> {
+    memory: Ram<A, W, E>,
+}
+
+impl<A, W, E> Rom<A, W, E>
+where
+    A: Copy + Default + Into<u64>,
+    W: Copy + Default,
+{
+    /// Creates a ROM of `size` words, all zero
+    pub fn new(size: usize) -> Self {
+        Self {
+            memory: Ram::new(size),
+        }
+    }
+
+    /// Creates a ROM of `size` words set to `fill`
+    pub fn with_fill(size: usize, fill: W) -> Self {
+        Self {
+            memory: Ram::with_fill(size, fill),
+        }
+    }
+
+    /// Creates a ROM holding a copy of `data`, sized to fit it
+    pub fn from_slice(data: &[W]) -> Self {
+        Self {
+            memory: Ram::from_slice(data),
+        }
+    }
+}
+
+impl<A, E> Rom<A, u8, E>
+where
+    A: Copy + Default + Into<u64>,
+{
+    /// Creates a ROM holding the contents of the file at `path`, sized to fit
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::from_slice(&std::fs::read(path)?))
+    }
+}
+
+impl<A, W, E> Rom<A, W, E>
+where
+    A: Copy + Into<u64>,
+    W: Copy,
+{
+    /// Makes the first word answer at address `base`
+    pub fn with_base(mut self, base: A) -> Self {
+        self.memory = self.memory.with_base(base);
+        self
+    }
+
+    pub fn base(&self) -> A {
+        self.memory.base()
+    }
+
+    pub fn as_slice(&self) -> &[W] {
+        self.memory.as_slice()
+    }
+
+    /// Programs `data` starting at word `offset`, ignoring the base address
+    ///
+    /// # Returns
+    /// * `Err(MemoryError::AddressOutOfBounds)` - If `data` does not fit
+    pub fn load(&mut self, offset: usize, data: &[W]) -> Result<(), MemoryError> {
+        self.memory.load(offset, data)
+    }
+}
+
+impl<A, E> Rom<A, u8, E>
+where
+    A: Copy + Into<u64>,
+{
+    /// Programs the contents of the file at `path` starting at byte `offset`
+    pub fn load_file(&mut self, offset: usize, path: impl AsRef<Path>) -> io::Result<()> {
+        self.memory.load_file(offset, path)
+    }
+}
+
+impl<A, W, E> MemoryDevice for Rom<A, W, E>
+where
+    A: Copy + Into<u64> + Debug,
+    W: Copy + Debug + StateValue,
+    E: From<MemoryError> + Debug,
+{
+    type Address = A;
+    type Word = W;
+    type Error = E;
+
+    fn read(&self, address: Self::Address) -> Result<Self::Word, Self::Error> {
+        self.memory.read(address)
+    }
+
+    /// Always fails; out-of-range addresses still report
+    /// [`MemoryError::AddressOutOfBounds`]
+    fn write(&mut self, address: Self::Address, _value: Self::Word) -> Result<(), Self::Error> {
+        self.memory.offset(address)?;
+        Err(MemoryError::ReadOnlyMemory.into())
+    }
+
+    /// Does nothing, ROM contents survive a reset
+    fn reset(&mut self) {}
+
+    /// Returns the size in words
+    fn size(&self) -> usize {
+        self.memory.size()
+    }
+}
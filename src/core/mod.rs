@@ -1,3 +1,7 @@
 pub mod cpu;
+pub mod debug;
 pub mod isa;
 pub mod memory;
+pub mod movie;
+pub mod savestate;
+pub mod trace;
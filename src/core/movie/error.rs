@@ -0,0 +1,25 @@
+use super::InputTime;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Represents errors that can occur while recording a movie
+#[derive(Debug, Clone, PartialEq)]
+pub enum MovieError {
+    /// An input arrived earlier than the last one recorded, as happens when
+    /// the machine is rewound or a state is loaded during a recording
+    OutOfOrder { last: InputTime, time: InputTime },
+}
+
+impl Display for MovieError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::OutOfOrder { last, time } => write!(
+                f,
+                "Input at frame {} cycle {} precedes the last recorded input at frame {} cycle {}",
+                time.frame, time.cycle, last.frame, last.cycle
+            ),
+        }
+    }
+}
+
+impl Error for MovieError {}
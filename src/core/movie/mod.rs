@@ -0,0 +1,214 @@
+//! Deterministic input recording and replay
+//!
+//! A [`Movie`] is a start [`SaveState`] plus every host input applied after
+//! it, each stamped with the [`InputTime`] it arrived at. Feeding the inputs
+//! back at the same times through a [`MoviePlayer`] reproduces the run bit for
+//! bit, which the [`final_hash`](Movie::final_hash) of the last state
+//! confirms. That makes a recorded play session a regression test.
+//!
+//! Inputs are recorded with an [`InputRecorder`] as the host applies them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use tiny_computers::arch::chip_8::{assemble, Chip8, Chip8InstructionSet};
+//! use tiny_computers::core::movie::{InputEvent, Movie};
+//!
+//! let mut chip8 = Chip8::new(Chip8InstructionSet::Chip8);
+//! chip8.load_rom(&assemble(": main v0 := key v1 += v0 jump main").unwrap()).unwrap();
+//!
+//! let mut recorder = chip8.start_recording().unwrap();
+//! for frame in 0..60 {
+//!     if frame % 10 == 0 {
+//!         chip8.record_input(InputEvent::Press(5), &mut recorder).unwrap();
+//!     } else if frame % 10 == 5 {
+//!         chip8.record_input(InputEvent::Release(5), &mut recorder).unwrap();
+//!     }
+//!     chip8.run_frame().unwrap();
+//! }
+//! let movie = Movie::from_bytes(&chip8.finish_recording(recorder).unwrap().to_bytes()).unwrap();
+//!
+//! let mut replay = Chip8::new(Chip8InstructionSet::Chip8);
+//! assert!(replay.replay(&movie).unwrap());
+//! ```
+
+mod error;
+mod player;
+mod recorder;
+
+pub use error::MovieError;
+pub use player::MoviePlayer;
+pub use recorder::InputRecorder;
+
+use crate::core::savestate::{SaveState, SaveStateError, StateReader, StateWriter};
+
+/// Bytes every movie file starts with
+pub const MAGIC: [u8; 4] = *b"TCMV";
+/// Version of the movie container layout
+pub const FORMAT_VERSION: u16 = 1;
+
+/// A host input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    /// A key or button was pressed
+    Press(u16),
+    /// A key or button was released
+    Release(u16),
+    /// An architecture-defined input port took a new value, e.g. a paddle
+    Port { port: u16, value: u32 },
+}
+
+/// When an input arrived, ordered by frame and then by cycle
+///
+/// Both counters only ever increase while a machine runs, so inputs at equal
+/// times were applied with no execution in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct InputTime {
+    pub frame: u64,
+    pub cycle: u64,
+}
+
+impl InputTime {
+    pub fn new(frame: u64, cycle: u64) -> Self {
+        Self { frame, cycle }
+    }
+}
+
+/// An input together with the time it arrived
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedInput {
+    pub time: InputTime,
+    pub event: InputEvent,
+}
+
+/// A recorded run: a start state, timed inputs and the hash of the end state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    start: SaveState,
+    inputs: Vec<TimedInput>,
+    end: InputTime,
+    final_hash: u64,
+}
+
+impl Movie {
+    /// Returns the state the run started from
+    pub fn start(&self) -> &SaveState {
+        &self.start
+    }
+
+    /// Returns the inputs in the order they were applied
+    pub fn inputs(&self) -> &[TimedInput] {
+        &self.inputs
+    }
+
+    /// Returns the time the recording stopped at
+    pub fn end(&self) -> InputTime {
+        self.end
+    }
+
+    /// Returns the [`SaveState::hash`] of the state the run ended in
+    pub fn final_hash(&self) -> u64 {
+        self.final_hash
+    }
+
+    /// Returns true if `state` is the state the recorded run ended in
+    pub fn verify(&self, state: &SaveState) -> bool {
+        state.hash() == self.final_hash
+    }
+
+    /// Encodes the movie into its binary file format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.write_raw(&MAGIC);
+        writer.write(&FORMAT_VERSION);
+        writer.write_bytes(&self.start.to_bytes());
+        writer.write(&(self.inputs.len() as u64));
+        for input in &self.inputs {
+            write_time(&mut writer, input.time);
+            match input.event {
+                InputEvent::Press(key) => {
+                    writer.write(&0u8);
+                    writer.write(&key);
+                }
+                InputEvent::Release(key) => {
+                    writer.write(&1u8);
+                    writer.write(&key);
+                }
+                InputEvent::Port { port, value } => {
+                    writer.write(&2u8);
+                    writer.write(&port);
+                    writer.write(&value);
+                }
+            }
+        }
+        write_time(&mut writer, self.end);
+        writer.write(&self.final_hash);
+        writer.into_bytes()
+    }
+
+    /// Decodes a movie produced by [`to_bytes`](Self::to_bytes)
+    ///
+    /// # Returns
+    /// * `Err(SaveStateError::InvalidMagic)` - If `bytes` is not a movie
+    /// * `Err(SaveStateError::UnsupportedVersion(version))` - If the movie was
+    ///   written by a newer format version
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveStateError> {
+        let mut reader = StateReader::new(bytes);
+        if reader.read_array::<4>().ok() != Some(MAGIC) {
+            return Err(SaveStateError::InvalidMagic);
+        }
+        let version = reader.read::<u16>()?;
+        if version != FORMAT_VERSION {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+        let start = SaveState::from_bytes(reader.read_bytes()?)?;
+
+        let count = reader.read::<u64>()?;
+        let mut inputs = Vec::new();
+        for _ in 0..count {
+            let time = read_time(&mut reader)?;
+            let event = match reader.read::<u8>()? {
+                0 => InputEvent::Press(reader.read()?),
+                1 => InputEvent::Release(reader.read()?),
+                2 => InputEvent::Port {
+                    port: reader.read()?,
+                    value: reader.read()?,
+                },
+                kind => {
+                    return Err(SaveStateError::InvalidData(format!(
+                        "unknown input kind {}",
+                        kind
+                    )))
+                }
+            };
+            if inputs
+                .last()
+                .is_some_and(|last: &TimedInput| last.time > time)
+            {
+                return Err(SaveStateError::InvalidData(
+                    "inputs are out of order".into(),
+                ));
+            }
+            inputs.push(TimedInput { time, event });
+        }
+
+        let end = read_time(&mut reader)?;
+        let final_hash = reader.read()?;
+        reader.finish()?;
+        Ok(Self {
+            start,
+            inputs,
+            end,
+            final_hash,
+        })
+    }
+}
+
+fn write_time(writer: &mut StateWriter, time: InputTime) {
+    writer.write(&time.frame);
+    writer.write(&time.cycle);
+}
+
+fn read_time(reader: &mut StateReader<'_>) -> Result<InputTime, SaveStateError> {
+    Ok(InputTime::new(reader.read()?, reader.read()?))
+}
@@ -0,0 +1,42 @@
+use super::{InputEvent, InputTime, Movie};
+
+/// Hands out the inputs of a [`Movie`] as their time comes
+///
+/// Before executing each instruction, a host drains
+/// [`next_due`](Self::next_due) for the machine's current time and applies
+/// every input it returns.
+#[derive(Debug, Clone)]
+pub struct MoviePlayer<'a> {
+    movie: &'a Movie,
+    next: usize,
+}
+
+impl<'a> MoviePlayer<'a> {
+    pub fn new(movie: &'a Movie) -> Self {
+        Self { movie, next: 0 }
+    }
+
+    pub fn movie(&self) -> &'a Movie {
+        self.movie
+    }
+
+    /// Returns the next input recorded at or before `now`, if any
+    pub fn next_due(&mut self, now: InputTime) -> Option<InputEvent> {
+        let input = self.movie.inputs.get(self.next)?;
+        if input.time > now {
+            return None;
+        }
+        self.next += 1;
+        Some(input.event)
+    }
+
+    /// Returns the number of inputs not handed out yet
+    pub fn remaining(&self) -> usize {
+        self.movie.inputs.len() - self.next
+    }
+
+    /// Returns true once the machine has reached the end of the recording
+    pub fn is_finished(&self, now: InputTime) -> bool {
+        self.remaining() == 0 && now >= self.movie.end
+    }
+}
@@ -0,0 +1,57 @@
+use super::{InputEvent, InputTime, Movie, MovieError, TimedInput};
+use crate::core::savestate::SaveState;
+
+/// Collects the inputs of a run into a [`Movie`]
+#[derive(Debug, Clone)]
+pub struct InputRecorder {
+    start: SaveState,
+    inputs: Vec<TimedInput>,
+}
+
+impl InputRecorder {
+    /// Starts a recording from `start`, the state the machine is in now
+    pub fn new(start: SaveState) -> Self {
+        Self {
+            start,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Records `event` as applied at `time`
+    ///
+    /// # Returns
+    /// * `Err(MovieError::OutOfOrder { .. })` - If `time` is earlier than the
+    ///   previously recorded input; nothing is recorded
+    pub fn record(&mut self, time: InputTime, event: InputEvent) -> Result<(), MovieError> {
+        self.check_time(time)?;
+        self.inputs.push(TimedInput { time, event });
+        Ok(())
+    }
+
+    /// Returns the error [`record`](Self::record) would give for an input at
+    /// `time`, so a host can check before applying the input
+    pub fn check_time(&self, time: InputTime) -> Result<(), MovieError> {
+        match self.inputs.last() {
+            Some(last) if last.time > time => Err(MovieError::OutOfOrder {
+                last: last.time,
+                time,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the inputs recorded so far
+    pub fn inputs(&self) -> &[TimedInput] {
+        &self.inputs
+    }
+
+    /// Ends the recording at `end` with the machine in `state`
+    pub fn finish(self, end: InputTime, state: &SaveState) -> Movie {
+        Movie {
+            start: self.start,
+            inputs: self.inputs,
+            end,
+            final_hash: state.hash(),
+        }
+    }
+}
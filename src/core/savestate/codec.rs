@@ -0,0 +1,171 @@
+use super::SaveStateError;
+
+/// A fixed-size value that can be written to and read back from a save state
+///
+/// Integers are stored little-endian regardless of the host.
+pub trait StateValue: Sized {
+    fn write_to(&self, writer: &mut StateWriter);
+    fn read_from(reader: &mut StateReader<'_>) -> Result<Self, SaveStateError>;
+}
+
+macro_rules! impl_state_value {
+    ($($ty:ty),*) => {
+        $(
+            impl StateValue for $ty {
+                fn write_to(&self, writer: &mut StateWriter) {
+                    writer.buffer.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_from(reader: &mut StateReader<'_>) -> Result<Self, SaveStateError> {
+                    Ok(<$ty>::from_le_bytes(reader.read_array()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_state_value!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl StateValue for bool {
+    fn write_to(&self, writer: &mut StateWriter) {
+        writer.write(&(*self as u8));
+    }
+
+    fn read_from(reader: &mut StateReader<'_>) -> Result<Self, SaveStateError> {
+        match reader.read::<u8>()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(SaveStateError::InvalidData(format!(
+                "{:#04x} is not a boolean",
+                value
+            ))),
+        }
+    }
+}
+
+/// Builds the binary payload of a save state
+#[derive(Debug, Clone, Default)]
+pub struct StateWriter {
+    buffer: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write<T: StateValue>(&mut self, value: &T) {
+        value.write_to(self);
+    }
+
+    /// Writes `bytes` as-is, for data whose length the reader already knows
+    pub fn write_raw(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Writes `value` as an LEB128 varint, one byte for values below 128
+    pub fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buffer.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.buffer.push(value as u8);
+    }
+
+    /// Writes `bytes` preceded by their length
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64));
+        self.write_raw(bytes);
+    }
+
+    /// Writes `text` as length-prefixed UTF-8
+    pub fn write_str(&mut self, text: &str) {
+        self.write_bytes(text.as_bytes());
+    }
+
+    /// Returns the payload written so far
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+/// Reads back a payload produced by [`StateWriter`]
+///
+/// Every read fails with [`SaveStateError::UnexpectedEof`] instead of
+/// panicking when the data runs out.
+#[derive(Debug, Clone)]
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub fn read<T: StateValue>(&mut self) -> Result<T, SaveStateError> {
+        T::read_from(self)
+    }
+
+    /// Reads exactly `len` bytes written by [`StateWriter::write_raw`]
+    pub fn read_raw(&mut self, len: usize) -> Result<&'a [u8], SaveStateError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or(SaveStateError::UnexpectedEof)?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], SaveStateError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_raw(N)?);
+        Ok(array)
+    }
+
+    /// Reads a varint written by [`StateWriter::write_varint`]
+    pub fn read_varint(&mut self) -> Result<u64, SaveStateError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read::<u8>()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SaveStateError::InvalidData("varint is too long".into()))
+    }
+
+    /// Reads bytes written by [`StateWriter::write_bytes`]
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], SaveStateError> {
+        let len = self.read::<u64>()?;
+        let len = usize::try_from(len).map_err(|_| SaveStateError::UnexpectedEof)?;
+        self.read_raw(len)
+    }
+
+    /// Reads a string written by [`StateWriter::write_str`]
+    pub fn read_str(&mut self) -> Result<&'a str, SaveStateError> {
+        std::str::from_utf8(self.read_bytes()?)
+            .map_err(|_| SaveStateError::InvalidData("string is not UTF-8".into()))
+    }
+
+    /// Returns the number of bytes not read yet
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.position
+    }
+
+    /// Fails with [`SaveStateError::TrailingData`] unless everything was read
+    pub fn finish(&self) -> Result<(), SaveStateError> {
+        match self.remaining() {
+            0 => Ok(()),
+            len => Err(SaveStateError::TrailingData(len)),
+        }
+    }
+}
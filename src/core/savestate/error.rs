@@ -0,0 +1,59 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Represents errors that can occur while saving or restoring machine state
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveStateError {
+    /// The data does not start with the save-state magic
+    InvalidMagic,
+    /// The data was written by a newer, incompatible format version
+    UnsupportedVersion(u16),
+    /// The state belongs to a different architecture
+    ArchitectureMismatch { expected: String, found: String },
+    /// The state belongs to a different variant of the architecture
+    VariantMismatch { expected: String, found: String },
+    /// The data ended before the state was complete
+    UnexpectedEof,
+    /// Bytes were left over after the state was restored
+    TrailingData(usize),
+    /// A field holds a value the target cannot take
+    InvalidData(String),
+    /// A memory device's saved contents do not fit the attached device
+    Device(String),
+    /// Restoring failed with `error` and putting the previous state back
+    /// failed with `rollback`, so the target may be partly restored
+    Rollback {
+        error: Box<SaveStateError>,
+        rollback: Box<SaveStateError>,
+    },
+}
+
+impl Display for SaveStateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::InvalidMagic => write!(f, "Not a save state"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "Unsupported save state version {}", version)
+            }
+            Self::ArchitectureMismatch { expected, found } => {
+                write!(f, "Save state is for {}, expected {}", found, expected)
+            }
+            Self::VariantMismatch { expected, found } => write!(
+                f,
+                "Save state is for the {} variant, expected {}",
+                found, expected
+            ),
+            Self::UnexpectedEof => write!(f, "Save state is truncated"),
+            Self::TrailingData(len) => write!(f, "{} unexpected bytes after save state", len),
+            Self::InvalidData(msg) => write!(f, "Invalid save state: {}", msg),
+            Self::Device(msg) => write!(f, "Memory device error: {}", msg),
+            Self::Rollback { error, rollback } => write!(
+                f,
+                "{}; restoring the previous state also failed: {}",
+                error, rollback
+            ),
+        }
+    }
+}
+
+impl Error for SaveStateError {}
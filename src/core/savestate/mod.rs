@@ -0,0 +1,200 @@
+//! Save states
+//!
+//! A [`SaveState`] is a snapshot of a whole machine that can be written to a
+//! byte buffer and later restored exactly. Anything that makes up machine
+//! state implements [`Snapshot`], writing its fields to a [`StateWriter`] and
+//! reading them back in the same order from a [`StateReader`].
+//!
+//! The binary format starts with a header made of [`MAGIC`], the
+//! [`FORMAT_VERSION`] and the names of the architecture and its variant, so a
+//! state is never restored into a machine it was not taken from. The payload
+//! that follows is owned entirely by the [`Snapshot`] implementations.
+//!
+//! A [`RewindBuffer`] keeps a bounded, delta-compressed history of states for
+//! stepping backwards.
+//!
+//! # Example
+//!
+//! ```rust
+//! use tiny_computers::arch::{chip_8::{assemble, Chip8, Chip8InstructionSet}, Architecture};
+//! use tiny_computers::core::{cpu::RegisterFile, savestate::SaveState};
+//!
+//! let mut chip8 = Chip8::new(Chip8InstructionSet::Chip8);
+//! chip8.load_rom(&assemble(": main v0 += 1 jump main").unwrap()).unwrap();
+//! chip8.run_frame().unwrap();
+//!
+//! let bytes = chip8.save_state().unwrap().to_bytes();
+//! let v0 = chip8.state().register_file().registers()[0];
+//! chip8.run_frame().unwrap();
+//!
+//! chip8.load_state(&SaveState::from_bytes(&bytes).unwrap()).unwrap();
+//! assert_eq!(chip8.state().register_file().registers()[0], v0);
+//! ```
+
+mod codec;
+mod error;
+mod rewind;
+
+pub use codec::{StateReader, StateValue, StateWriter};
+pub use error::SaveStateError;
+pub use rewind::RewindBuffer;
+
+/// Bytes every save state starts with
+pub const MAGIC: [u8; 4] = *b"TCSS";
+/// Version of the save-state header and container layout
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Machine state that can be captured in a [`SaveState`]
+///
+/// [`read_state`](Self::read_state) must consume exactly what
+/// [`write_state`](Self::write_state) produced, in the same order.
+pub trait Snapshot {
+    /// Writes the complete state to `writer`
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError>;
+
+    /// Replaces the current state with one read from `reader`
+    fn read_state(&mut self, reader: &mut StateReader<'_>) -> Result<(), SaveStateError>;
+}
+
+/// A captured machine state together with the header identifying its machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveState {
+    architecture: String,
+    variant: String,
+    payload: Vec<u8>,
+}
+
+impl SaveState {
+    /// Captures the state of `target`
+    ///
+    /// # Arguments
+    /// * `architecture` - Name of the architecture, e.g. `"CHIP-8"`
+    /// * `variant` - Name of the instruction set variant, e.g. `"XO-CHIP"`
+    /// * `target` - The machine to capture
+    pub fn capture<T: Snapshot + ?Sized>(
+        architecture: &str,
+        variant: &str,
+        target: &T,
+    ) -> Result<Self, SaveStateError> {
+        let mut writer = StateWriter::new();
+        target.write_state(&mut writer)?;
+        Ok(Self {
+            architecture: architecture.to_string(),
+            variant: variant.to_string(),
+            payload: writer.into_bytes(),
+        })
+    }
+
+    /// Restores the captured state into `target`
+    ///
+    /// On error `target` is left as it was, unless putting its previous state
+    /// back fails too.
+    ///
+    /// # Returns
+    /// * `Err(SaveStateError::ArchitectureMismatch { .. })` or
+    ///   `Err(SaveStateError::VariantMismatch { .. })` - If the state was taken
+    ///   from a different machine
+    /// * `Err(SaveStateError::Rollback { .. })` - If restoring failed and
+    ///   `target` could not be returned to its previous state either
+    /// * `Err(error)` - If the payload is malformed
+    pub fn restore<T: Snapshot + ?Sized>(
+        &self,
+        architecture: &str,
+        variant: &str,
+        target: &mut T,
+    ) -> Result<(), SaveStateError> {
+        if self.architecture != architecture {
+            return Err(SaveStateError::ArchitectureMismatch {
+                expected: architecture.to_string(),
+                found: self.architecture.clone(),
+            });
+        }
+        if self.variant != variant {
+            return Err(SaveStateError::VariantMismatch {
+                expected: variant.to_string(),
+                found: self.variant.clone(),
+            });
+        }
+
+        let mut backup = StateWriter::new();
+        target.write_state(&mut backup)?;
+
+        let mut reader = StateReader::new(&self.payload);
+        let result = target
+            .read_state(&mut reader)
+            .and_then(|()| reader.finish());
+        if let Err(error) = result {
+            let rollback = target.read_state(&mut StateReader::new(backup.as_bytes()));
+            return Err(match rollback {
+                Ok(()) => error,
+                Err(rollback) => SaveStateError::Rollback {
+                    error: Box::new(error),
+                    rollback: Box::new(rollback),
+                },
+            });
+        }
+        Ok(())
+    }
+
+    pub fn architecture(&self) -> &str {
+        &self.architecture
+    }
+
+    pub fn variant(&self) -> &str {
+        &self.variant
+    }
+
+    /// Returns the architecture-specific payload without the header
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Returns a 64-bit FNV-1a hash of the header and payload
+    ///
+    /// Equal states always hash equal, so comparing hashes is a cheap way to
+    /// check that two runs ended in the same state.
+    pub fn hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01B3;
+        self.to_bytes().iter().fold(OFFSET_BASIS, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(PRIME)
+        })
+    }
+
+    /// Encodes the header and payload into the binary save-state format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.write_raw(&MAGIC);
+        writer.write(&FORMAT_VERSION);
+        writer.write_str(&self.architecture);
+        writer.write_str(&self.variant);
+        writer.write_bytes(&self.payload);
+        writer.into_bytes()
+    }
+
+    /// Decodes a save state produced by [`to_bytes`](Self::to_bytes)
+    ///
+    /// # Returns
+    /// * `Err(SaveStateError::InvalidMagic)` - If `bytes` is not a save state
+    /// * `Err(SaveStateError::UnsupportedVersion(version))` - If the state was
+    ///   written by a newer format version
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveStateError> {
+        let mut reader = StateReader::new(bytes);
+        if reader.read_array::<4>().ok() != Some(MAGIC) {
+            return Err(SaveStateError::InvalidMagic);
+        }
+        let version = reader.read::<u16>()?;
+        if version != FORMAT_VERSION {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+        let architecture = reader.read_str()?.to_string();
+        let variant = reader.read_str()?.to_string();
+        let payload = reader.read_bytes()?.to_vec();
+        reader.finish()?;
+        Ok(Self {
+            architecture,
+            variant,
+            payload,
+        })
+    }
+}
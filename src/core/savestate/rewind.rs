@@ -0,0 +1,262 @@
+use super::{SaveState, SaveStateError, StateReader, StateWriter};
+use std::collections::VecDeque;
+
+/// A bounded history of save states for stepping backwards in time
+///
+/// A state is recorded every [`interval`](Self::interval) frames. Only the
+/// newest state is kept in full; each older one is stored as the XOR of its
+/// payload with the state recorded after it, run-length encoded. Consecutive
+/// frames differ in few bytes, so a delta is usually a small fraction of a
+/// full state. Once [`capacity`](Self::capacity) states are held, recording a
+/// new one drops the oldest.
+///
+/// Frames are numbered by the caller and must increase; recording at a frame
+/// at or before the newest state discards the newer history first, so
+/// rewinding and then playing on continues from the new timeline.
+#[derive(Debug, Clone)]
+pub struct RewindBuffer {
+    interval: u64,
+    capacity: usize,
+    /// The newest state and the frame it was recorded at
+    latest: Option<(u64, SaveState)>,
+    /// Deltas reconstructing older states, oldest first
+    deltas: VecDeque<Delta>,
+}
+
+/// Reconstructs a state from the one recorded after it
+#[derive(Debug, Clone)]
+struct Delta {
+    frame: u64,
+    /// Payload length of the reconstructed state
+    len: usize,
+    /// Run-length encoded XOR of both payloads
+    data: Vec<u8>,
+}
+
+impl RewindBuffer {
+    /// Creates a buffer recording every `interval` frames and holding at most
+    /// `capacity` states
+    ///
+    /// Both are raised to at least 1.
+    pub fn new(interval: u64, capacity: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            capacity: capacity.max(1),
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    /// Returns the number of frames between recorded states
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Returns the maximum number of states held
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns true if a state should be recorded at `frame`
+    pub fn is_due(&self, frame: u64) -> bool {
+        frame.is_multiple_of(self.interval)
+    }
+
+    /// Returns the number of states held
+    pub fn len(&self) -> usize {
+        self.deltas.len() + self.latest.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    /// Returns the approximate number of bytes used by the recorded states
+    pub fn memory_usage(&self) -> usize {
+        let latest = self
+            .latest
+            .as_ref()
+            .map_or(0, |(_, state)| state.payload().len());
+        latest
+            + self
+                .deltas
+                .iter()
+                .map(|delta| delta.data.len())
+                .sum::<usize>()
+    }
+
+    /// Returns the frame of the oldest state held
+    pub fn oldest_frame(&self) -> Option<u64> {
+        match self.deltas.front() {
+            Some(delta) => Some(delta.frame),
+            None => self.latest.as_ref().map(|(frame, _)| *frame),
+        }
+    }
+
+    /// Returns the newest state and the frame it was recorded at
+    pub fn latest(&self) -> Option<(u64, &SaveState)> {
+        self.latest.as_ref().map(|(frame, state)| (*frame, state))
+    }
+
+    /// Drops every state
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    /// Records `state` as taken at `frame`
+    ///
+    /// States recorded at or after `frame` are discarded first. A state of a
+    /// different architecture or variant than the history clears it.
+    ///
+    /// # Returns
+    /// * `Err(error)` - If a newer state could not be discarded, see
+    ///   [`pop`](Self::pop). `state` is not recorded.
+    pub fn push(&mut self, frame: u64, state: SaveState) -> Result<(), SaveStateError> {
+        while self
+            .latest
+            .as_ref()
+            .is_some_and(|(latest, _)| *latest >= frame)
+        {
+            self.pop()?;
+        }
+        if let Some((latest_frame, latest)) = self.latest.take() {
+            if latest.architecture == state.architecture && latest.variant == state.variant {
+                self.deltas.push_back(Delta {
+                    frame: latest_frame,
+                    len: latest.payload.len(),
+                    data: encode(&xor(&latest.payload, &state.payload)),
+                });
+            } else {
+                self.deltas.clear();
+            }
+        }
+        self.latest = Some((frame, state));
+        while self.len() > self.capacity {
+            self.deltas.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Removes and returns the newest state and its frame
+    ///
+    /// # Returns
+    /// * `Ok(None)` - If the buffer is empty
+    /// * `Err(SaveStateError)` - If the next older state cannot be rebuilt
+    ///   from its delta. The buffer is left unchanged.
+    pub fn pop(&mut self) -> Result<Option<(u64, SaveState)>, SaveStateError> {
+        let Some((frame, state)) = self.latest.take() else {
+            return Ok(None);
+        };
+        if let Some(delta) = self.deltas.pop_back() {
+            let mut payload = state.payload.clone();
+            payload.resize(payload.len().max(delta.len), 0);
+            if let Err(error) = decode(&delta.data, &mut payload) {
+                self.deltas.push_back(delta);
+                self.latest = Some((frame, state));
+                return Err(error);
+            }
+            payload.truncate(delta.len);
+            self.latest = Some((
+                delta.frame,
+                SaveState {
+                    architecture: state.architecture.clone(),
+                    variant: state.variant.clone(),
+                    payload,
+                },
+            ));
+        }
+        Ok(Some((frame, state)))
+    }
+
+    /// Drops every state recorded after `frame` and returns the newest one
+    /// left, which is the closest state at or before `frame`
+    ///
+    /// # Returns
+    /// * `Ok(None)` - If no state that old is held, leaving the buffer empty
+    /// * `Err(SaveStateError)` - If an older state cannot be rebuilt, see
+    ///   [`pop`](Self::pop)
+    pub fn rewind_to(&mut self, frame: u64) -> Result<Option<(u64, &SaveState)>, SaveStateError> {
+        while self
+            .latest
+            .as_ref()
+            .is_some_and(|(latest, _)| *latest > frame)
+        {
+            self.pop()?;
+        }
+        Ok(self.latest())
+    }
+}
+
+/// XORs two payloads, treating the shorter one as padded with zeros
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    (0..a.len().max(b.len()))
+        .map(|index| a.get(index).unwrap_or(&0) ^ b.get(index).unwrap_or(&0))
+        .collect()
+}
+
+/// Zero runs at least this long end a literal run
+const MIN_ZERO_RUN: usize = 4;
+
+/// Run-length encodes `data` as pairs of a zero run and a literal run
+///
+/// Each pair is the number of zero bytes to skip followed by the number of
+/// literal bytes and the bytes themselves, both counts as varints.
+fn encode(data: &[u8]) -> Vec<u8> {
+    let mut encoded = StateWriter::new();
+    let mut position = 0;
+    while position < data.len() {
+        let zeros = data[position..]
+            .iter()
+            .take_while(|&&byte| byte == 0)
+            .count();
+        position += zeros;
+        if position == data.len() {
+            break;
+        }
+
+        let start = position;
+        while position < data.len() {
+            let run = data[position..]
+                .iter()
+                .take(MIN_ZERO_RUN)
+                .take_while(|&&byte| byte == 0)
+                .count();
+            if run == MIN_ZERO_RUN || position + run == data.len() {
+                break;
+            }
+            position += run.max(1);
+        }
+
+        encoded.write_varint(zeros as u64);
+        encoded.write_varint((position - start) as u64);
+        encoded.write_raw(&data[start..position]);
+    }
+    encoded.into_bytes()
+}
+
+/// XORs data encoded by [`encode`] into `target`
+///
+/// # Returns
+/// * `Err(SaveStateError)` - If `encoded` is truncated or reaches past the
+///   end of `target`
+fn decode(encoded: &[u8], target: &mut [u8]) -> Result<(), SaveStateError> {
+    let overrun = || SaveStateError::InvalidData("rewind delta overruns its state".into());
+    let mut reader = StateReader::new(encoded);
+    let mut position = 0usize;
+    while reader.remaining() > 0 {
+        let zeros = usize::try_from(reader.read_varint()?).map_err(|_| overrun())?;
+        let len = usize::try_from(reader.read_varint()?).map_err(|_| overrun())?;
+        let literal = reader.read_raw(len)?;
+        position = position.checked_add(zeros).ok_or_else(overrun)?;
+        let end = position
+            .checked_add(len)
+            .filter(|&end| end <= target.len())
+            .ok_or_else(overrun)?;
+        for (byte, delta) in target[position..end].iter_mut().zip(literal) {
+            *byte ^= delta;
+        }
+        position = end;
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use super::{RegisterChange, TraceAccess, TraceEntry, TraceHeader, TraceSink};
+use crate::core::debug::AccessKind;
+use crate::core::savestate::{SaveStateError, StateReader, StateWriter};
+use std::io::{self, Read, Write};
+
+/// Bytes every binary trace starts with
+pub(super) const MAGIC: [u8; 4] = *b"TCTR";
+/// Version of the binary trace layout
+const FORMAT_VERSION: u16 = 2;
+
+/// Writes a compact binary trace
+///
+/// Numbers are varints, cycles are stored as the difference to the previous
+/// entry and register changes, external ones included, only as their new
+/// value, since the old one follows from the header and earlier entries.
+/// Disassembly is not stored; entries read back with [`read_binary_trace`]
+/// have it empty.
+#[derive(Debug)]
+pub struct BinaryTraceSink<W: Write> {
+    writer: W,
+    cycle: u64,
+}
+
+impl<W: Write> BinaryTraceSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, cycle: 0 }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + std::fmt::Debug> TraceSink for BinaryTraceSink<W> {
+    fn begin(&mut self, header: &TraceHeader) -> io::Result<()> {
+        let mut out = StateWriter::new();
+        out.write_raw(&MAGIC);
+        out.write(&FORMAT_VERSION);
+        out.write_str(&header.architecture);
+        out.write_varint(header.registers.len() as u64);
+        for (name, &value) in header.registers.iter().zip(&header.initial) {
+            out.write_str(name);
+            out.write_varint(value);
+        }
+        self.cycle = 0;
+        self.writer.write_all(out.as_bytes())
+    }
+
+    fn record(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        let mut out = StateWriter::new();
+        out.write_varint(entry.cycle.wrapping_sub(self.cycle));
+        self.cycle = entry.cycle;
+        out.write_varint(entry.pc);
+        out.write_varint(entry.bytes.len() as u64);
+        out.write_raw(&entry.bytes);
+        write_changes(&mut out, &entry.external);
+        write_changes(&mut out, &entry.changes);
+        out.write_varint(entry.accesses.len() as u64);
+        for access in &entry.accesses {
+            out.write(&(access.kind == AccessKind::Write));
+            out.write_varint(access.address);
+            out.write_varint(access.value);
+        }
+        self.writer.write_all(out.as_bytes())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn write_changes(out: &mut StateWriter, changes: &[RegisterChange]) {
+    out.write_varint(changes.len() as u64);
+    for change in changes {
+        out.write_varint(change.register as u64);
+        out.write_varint(change.after);
+    }
+}
+
+/// Reads a whole trace written by [`BinaryTraceSink`]
+///
+/// # Returns
+/// * `Err(error)` - With [`io::ErrorKind::InvalidData`] if the data is not a
+///   binary trace or is truncated
+pub fn read_binary_trace(mut reader: impl Read) -> io::Result<(TraceHeader, Vec<TraceEntry>)> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    parse(&data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn parse(data: &[u8]) -> Result<(TraceHeader, Vec<TraceEntry>), SaveStateError> {
+    let mut reader = StateReader::new(data);
+    if reader.read_array::<4>().ok() != Some(MAGIC) {
+        return Err(SaveStateError::InvalidMagic);
+    }
+    let version = reader.read::<u16>()?;
+    if version != FORMAT_VERSION {
+        return Err(SaveStateError::UnsupportedVersion(version));
+    }
+
+    let architecture = reader.read_str()?.to_string();
+    let count = reader.read_varint()?;
+    let mut registers = Vec::new();
+    let mut initial = Vec::new();
+    for _ in 0..count {
+        registers.push(reader.read_str()?.to_string());
+        initial.push(reader.read_varint()?);
+    }
+    let header = TraceHeader {
+        architecture,
+        registers,
+        initial,
+    };
+
+    let mut values = header.initial.clone();
+    let mut cycle = 0u64;
+    let mut entries = Vec::new();
+    while reader.remaining() > 0 {
+        cycle = cycle.wrapping_add(reader.read_varint()?);
+        let pc = reader.read_varint()?;
+        let len = reader.read_varint()? as usize;
+        let bytes = reader.read_raw(len)?.to_vec();
+        let external = read_changes(&mut reader, &mut values)?;
+        let changes = read_changes(&mut reader, &mut values)?;
+
+        let mut accesses = Vec::new();
+        for _ in 0..reader.read_varint()? {
+            let kind = match reader.read::<bool>()? {
+                true => AccessKind::Write,
+                false => AccessKind::Read,
+            };
+            accesses.push(TraceAccess {
+                kind,
+                address: reader.read_varint()?,
+                value: reader.read_varint()?,
+            });
+        }
+
+        entries.push(TraceEntry {
+            cycle,
+            pc,
+            bytes,
+            disassembly: String::new(),
+            external,
+            changes,
+            accesses,
+        });
+    }
+    Ok((header, entries))
+}
+
+/// Reads a list of changes, taking their old values from `values` and
+/// updating it
+fn read_changes(
+    reader: &mut StateReader<'_>,
+    values: &mut [u64],
+) -> Result<Vec<RegisterChange>, SaveStateError> {
+    let mut changes = Vec::new();
+    for _ in 0..reader.read_varint()? {
+        let register = reader.read_varint()? as usize;
+        let after = reader.read_varint()?;
+        let value = values
+            .get_mut(register)
+            .ok_or_else(|| SaveStateError::InvalidData(format!("unknown register {}", register)))?;
+        changes.push(RegisterChange {
+            register,
+            before: *value,
+            after,
+        });
+        *value = after;
+    }
+    Ok(changes)
+}
@@ -0,0 +1,227 @@
+use super::{sink::format_entry, RegisterChange, TraceEntry, TraceHeader};
+use std::io::{self, BufRead};
+
+/// One step of a trace in the shape the diff compares
+///
+/// A step holds the register values before its instruction ran, including
+/// `pc`, and the memory the instruction accessed with the values it left
+/// there. Register names compare case-insensitively, so `V0` in a reference
+/// log matches `v0` in a trace of this crate.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TraceStep {
+    pub registers: Vec<(String, u64)>,
+    pub memory: Vec<(u64, u64)>,
+    /// How the step is shown in a report
+    pub text: String,
+}
+
+impl TraceStep {
+    /// Returns the value of register `name`
+    pub fn register(&self, name: &str) -> Option<u64> {
+        self.registers
+            .iter()
+            .find(|(register, _)| register.eq_ignore_ascii_case(name))
+            .map(|&(_, value)| value)
+    }
+
+    /// Returns the last value the step left at `address`
+    pub fn memory(&self, address: u64) -> Option<u64> {
+        self.memory
+            .iter()
+            .rev()
+            .find(|&&(accessed, _)| accessed == address)
+            .map(|&(_, value)| value)
+    }
+
+    /// Parses one line of a reference log
+    ///
+    /// Tokens are separated by whitespace or commas. `name=value` and
+    /// `name:value` give a register, `[address]=value` a memory value, all in
+    /// hex with an optional `0x` or `$` prefix. Any other token, such as
+    /// disassembly, is ignored.
+    ///
+    /// # Returns
+    /// * `None` - For blank lines and `#` comments
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let mut step = Self {
+            text: line.to_string(),
+            ..Self::default()
+        };
+        for token in line.split(|c: char| c.is_whitespace() || c == ',') {
+            let Some((name, value)) = token.split_once(['=', ':']) else {
+                continue;
+            };
+            let Some(value) = parse_value(value) else {
+                continue;
+            };
+            let address = name
+                .trim_start_matches(['r', 'w'])
+                .strip_prefix('[')
+                .and_then(|name| name.strip_suffix(']'));
+            match address {
+                Some(address) => {
+                    if let Some(address) = parse_value(address) {
+                        step.memory.push((address, value));
+                    }
+                }
+                None if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()) => {
+                    step.registers.push((name.to_string(), value));
+                }
+                None => {}
+            }
+        }
+        Some(step)
+    }
+}
+
+fn parse_value(text: &str) -> Option<u64> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix('$'))
+        .unwrap_or(text);
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// Expands a trace of this crate into full-state steps
+///
+/// Each step takes its register values from [`register_snapshots`], so
+/// changes made between instructions, like timer ticks, show up at the step
+/// they preceded.
+pub fn trace_steps(header: &TraceHeader, entries: &[TraceEntry]) -> Vec<TraceStep> {
+    entries
+        .iter()
+        .zip(register_snapshots(header, entries))
+        .map(|(entry, values)| {
+            let mut registers = vec![("pc".to_string(), entry.pc)];
+            registers.extend(header.registers.iter().cloned().zip(values));
+            TraceStep {
+                registers,
+                memory: entry
+                    .accesses
+                    .iter()
+                    .map(|access| (access.address, access.value))
+                    .collect(),
+                text: format_entry(&header.registers, entry)
+                    .trim_start()
+                    .to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Returns the register values every entry's instruction started from, in
+/// header order
+///
+/// The values are rebuilt from the header, applying the external changes of
+/// an entry before taking its snapshot and its own changes after.
+pub fn register_snapshots(header: &TraceHeader, entries: &[TraceEntry]) -> Vec<Vec<u64>> {
+    let mut values = header.initial.clone();
+    let apply = |values: &mut Vec<u64>, changes: &[RegisterChange]| {
+        for change in changes {
+            if let Some(value) = values.get_mut(change.register) {
+                *value = change.after;
+            }
+        }
+    };
+    entries
+        .iter()
+        .map(|entry| {
+            apply(&mut values, &entry.external);
+            let snapshot = values.clone();
+            apply(&mut values, &entry.changes);
+            snapshot
+        })
+        .collect()
+}
+
+/// Reads a reference log, one step per line as described in
+/// [`TraceStep::parse`]
+pub fn read_reference_log(reader: impl BufRead) -> io::Result<Vec<TraceStep>> {
+    let mut steps = Vec::new();
+    for line in reader.lines() {
+        steps.extend(TraceStep::parse(&line?));
+    }
+    Ok(steps)
+}
+
+/// Where two traces disagree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Register(String),
+    Memory(u64),
+    /// One trace ended before the other; `expected` and `found` hold the
+    /// step counts of the reference and the trace
+    End,
+}
+
+/// A value that differs from the reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    pub location: Location,
+    pub expected: u64,
+    /// The value in the trace being checked, `None` if it has none there
+    pub found: Option<u64>,
+}
+
+/// The first step at which a trace disagrees with its reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Index of the step in both traces
+    pub step: usize,
+    pub differences: Vec<Difference>,
+}
+
+/// Compares one step against its reference
+///
+/// Only what the reference lists is checked: a register the trace does not
+/// have is skipped, since reference emulators often log extra state, while
+/// a memory value the trace did not touch is a difference.
+pub fn compare_steps(step: &TraceStep, reference: &TraceStep) -> Vec<Difference> {
+    let registers = reference.registers.iter().filter_map(|(name, expected)| {
+        let found = step.register(name)?;
+        (found != *expected).then(|| Difference {
+            location: Location::Register(name.clone()),
+            expected: *expected,
+            found: Some(found),
+        })
+    });
+    let memory = reference.memory.iter().filter_map(|&(address, expected)| {
+        let found = step.memory(address);
+        (found != Some(expected)).then_some(Difference {
+            location: Location::Memory(address),
+            expected,
+            found,
+        })
+    });
+    registers.chain(memory).collect()
+}
+
+/// Finds the first step at which `trace` disagrees with `reference`
+///
+/// Steps are paired by position and compared with [`compare_steps`]. If
+/// the shorter trace agrees throughout, the traces diverge where it ends,
+/// with a [`Location::End`] difference.
+pub fn first_divergence(trace: &[TraceStep], reference: &[TraceStep]) -> Option<Divergence> {
+    let mismatch = trace
+        .iter()
+        .zip(reference)
+        .enumerate()
+        .find_map(|(step, (ours, theirs))| {
+            let differences = compare_steps(ours, theirs);
+            (!differences.is_empty()).then_some(Divergence { step, differences })
+        });
+    mismatch.or_else(|| {
+        (trace.len() != reference.len()).then(|| Divergence {
+            step: trace.len().min(reference.len()),
+            differences: vec![Difference {
+                location: Location::End,
+                expected: reference.len() as u64,
+                found: Some(trace.len() as u64),
+            }],
+        })
+    })
+}
@@ -0,0 +1,247 @@
+//! Execution tracing
+//!
+//! A CPU with a [`TraceSink`] attached reports every instruction it executes
+//! as a [`TraceEntry`]: where it ran, its raw bytes and disassembly, the
+//! registers it changed and the memory it accessed. Before the first entry the
+//! sink receives a [`TraceHeader`] naming the registers and giving their
+//! values at that point, so the full register state at any step can be
+//! reconstructed from the changes. Registers that change between
+//! instructions, such as timers ticking or a debugger writing them, are
+//! reported with the next entry as [`TraceEntry::external`] changes, so the
+//! reconstruction does not drift from the machine.
+//!
+//! Three sinks are provided:
+//!
+//! - [`RingBufferSink`] keeps the most recent entries in memory
+//! - [`TextTraceSink`] writes one human-readable line per instruction
+//! - [`BinaryTraceSink`] writes a compact binary trace, read back with
+//!   [`read_binary_trace`]
+//!
+//! To track down where an emulation goes wrong, [`first_divergence`] compares
+//! a trace step by step against a reference, either another trace or a log
+//! from a known-good emulator read with [`read_reference_log`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//! use tiny_computers::arch::chip_8::{assemble, Chip8Cpu, Chip8InstructionSet};
+//! use tiny_computers::core::cpu::Cpu;
+//! use tiny_computers::core::trace::RingBufferSink;
+//!
+//! let mut cpu = Chip8Cpu::new(Chip8InstructionSet::Chip8);
+//! cpu.load_rom(&assemble("v0 := 7 i := 0x300 save v0").unwrap()).unwrap();
+//!
+//! let trace = Rc::new(RefCell::new(RingBufferSink::new(64)));
+//! cpu.set_trace_sink(Some(Box::new(trace.clone())));
+//! for _ in 0..3 {
+//!     cpu.step().unwrap();
+//! }
+//!
+//! let trace = trace.borrow();
+//! let save = trace.entries().back().unwrap();
+//! assert_eq!(save.disassembly, "save v0");
+//! assert_eq!(save.accesses[0].address, 0x300);
+//! assert_eq!(save.accesses[0].value, 7);
+//! ```
+
+mod binary;
+mod diff;
+mod sink;
+
+pub use binary::{read_binary_trace, BinaryTraceSink};
+pub use diff::{
+    compare_steps, first_divergence, read_reference_log, register_snapshots, trace_steps,
+    Difference, Divergence, Location, TraceStep,
+};
+pub use sink::{read_text_trace, RingBufferSink, TextTraceSink};
+
+use crate::core::debug::AccessKind;
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::io::{self, Read};
+use std::rc::Rc;
+
+/// Describes the machine a trace was taken on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceHeader {
+    /// Name of the architecture, e.g. `"CHIP-8"`
+    pub architecture: String,
+    /// Register names, indexed by [`RegisterChange::register`]
+    pub registers: Vec<String>,
+    /// Register values before the first traced instruction
+    pub initial: Vec<u64>,
+}
+
+/// A register an instruction changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterChange {
+    /// Index into [`TraceHeader::registers`]
+    pub register: usize,
+    pub before: u64,
+    pub after: u64,
+}
+
+/// A data access an instruction made
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceAccess {
+    pub address: u64,
+    pub kind: AccessKind,
+    /// The value at `address` once the instruction completed
+    pub value: u64,
+}
+
+/// One executed instruction
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TraceEntry {
+    /// Cycle count before the instruction ran
+    pub cycle: u64,
+    pub pc: u64,
+    /// The raw instruction bytes
+    pub bytes: Vec<u8>,
+    pub disassembly: String,
+    /// Registers changed since the previous entry by something other than
+    /// an instruction, e.g. a timer tick or a loaded state, in header order
+    pub external: Vec<RegisterChange>,
+    /// Registers whose value changed, in header order; PC is not included
+    pub changes: Vec<RegisterChange>,
+    pub accesses: Vec<TraceAccess>,
+}
+
+/// Receives a trace as it is produced
+pub trait TraceSink: Debug {
+    /// Called once before the first entry
+    fn begin(&mut self, header: &TraceHeader) -> io::Result<()>;
+
+    /// Called after every traced instruction
+    fn record(&mut self, entry: &TraceEntry) -> io::Result<()>;
+
+    /// Writes out anything buffered
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Lets the host keep a handle to a sink it has handed to a CPU
+impl<S: TraceSink + ?Sized> TraceSink for Rc<RefCell<S>> {
+    fn begin(&mut self, header: &TraceHeader) -> io::Result<()> {
+        self.borrow_mut().begin(header)
+    }
+
+    fn record(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        self.borrow_mut().record(entry)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.borrow_mut().flush()
+    }
+}
+
+impl<S: TraceSink + ?Sized> TraceSink for Box<S> {
+    fn begin(&mut self, header: &TraceHeader) -> io::Result<()> {
+        (**self).begin(header)
+    }
+
+    fn record(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        (**self).record(entry)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+/// The tracing state a CPU embeds to drive a [`TraceSink`]
+///
+/// The header is sent lazily, right before the first entry, so it carries
+/// the register values the first traced instruction started from. The hook
+/// remembers the values the trace implies after every entry and fills in
+/// [`TraceEntry::external`] when the next instruction starts from others.
+#[derive(Debug, Default)]
+pub struct TraceHook {
+    sink: Option<Box<dyn TraceSink>>,
+    started: bool,
+    /// Register values the trace implies after the last entry
+    values: Vec<u64>,
+}
+
+impl TraceHook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true while a sink is attached
+    pub fn is_active(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Attaches `sink`, or detaches with `None`, returning the previous sink
+    ///
+    /// The previous sink is flushed; a new sink receives a fresh header.
+    pub fn set_sink(&mut self, sink: Option<Box<dyn TraceSink>>) -> Option<Box<dyn TraceSink>> {
+        let mut previous = std::mem::replace(&mut self.sink, sink);
+        self.started = false;
+        if let Some(previous) = &mut previous {
+            let _ = previous.flush();
+        }
+        previous
+    }
+
+    /// Sends `entry` to the sink, preceded by the header on first use
+    ///
+    /// `before` holds the register values the instruction started from, in
+    /// header order; any that differ from what the trace implies so far are
+    /// added to the entry as external changes. `header` is only called when
+    /// the header is due.
+    pub fn emit(
+        &mut self,
+        header: impl FnOnce() -> TraceHeader,
+        before: &[u64],
+        mut entry: TraceEntry,
+    ) -> io::Result<()> {
+        let Some(sink) = &mut self.sink else {
+            return Ok(());
+        };
+        if !self.started {
+            let header = header();
+            sink.begin(&header)?;
+            self.values = header.initial;
+            self.started = true;
+        }
+        entry.external = register_changes(&self.values, before);
+        self.values = before.to_vec();
+        for change in &entry.changes {
+            if let Some(value) = self.values.get_mut(change.register) {
+                *value = change.after;
+            }
+        }
+        sink.record(&entry)
+    }
+}
+
+/// Reads a trace written by either [`TextTraceSink`] or [`BinaryTraceSink`]
+pub fn read_trace(mut reader: impl Read) -> io::Result<(TraceHeader, Vec<TraceEntry>)> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    if data.starts_with(&binary::MAGIC) {
+        read_binary_trace(&data[..])
+    } else {
+        read_text_trace(&data[..])
+    }
+}
+
+/// Lists the registers that differ between two snapshots in header order
+pub fn register_changes(before: &[u64], after: &[u64]) -> Vec<RegisterChange> {
+    before
+        .iter()
+        .zip(after)
+        .enumerate()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(register, (&before, &after))| RegisterChange {
+            register,
+            before,
+            after,
+        })
+        .collect()
+}
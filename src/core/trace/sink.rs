@@ -0,0 +1,271 @@
+use super::{RegisterChange, TraceAccess, TraceEntry, TraceHeader, TraceSink};
+use crate::core::debug::AccessKind;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+
+/// Keeps the most recent entries in memory
+///
+/// Useful to see what led up to a crash without the cost of writing every
+/// instruction out.
+#[derive(Debug, Clone)]
+pub struct RingBufferSink {
+    capacity: usize,
+    header: Option<TraceHeader>,
+    entries: VecDeque<TraceEntry>,
+}
+
+impl RingBufferSink {
+    /// Creates a sink holding at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            header: None,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the header of the current trace
+    pub fn header(&self) -> Option<&TraceHeader> {
+        self.header.as_ref()
+    }
+
+    /// Returns the entries held, oldest first
+    pub fn entries(&self) -> &VecDeque<TraceEntry> {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl TraceSink for RingBufferSink {
+    fn begin(&mut self, header: &TraceHeader) -> io::Result<()> {
+        self.header = Some(header.clone());
+        self.entries.clear();
+        Ok(())
+    }
+
+    fn record(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry.clone());
+        Ok(())
+    }
+}
+
+/// Writes one line per instruction
+///
+/// The header is written as two `#` comment lines, the architecture and the
+/// initial registers. Each entry line then reads
+///
+/// ```text
+///        cycle  pc    bytes     disassembly              changes and accesses
+///           12  0204  a300      i := 0x300               i=00->300
+///           13  0206  f055      save v0                  i=300->301 w[0300]=07
+///           14  0208  f007      v0 := delay              ~dt=1e->1d v0=07->1d
+/// ```
+///
+/// with all numbers in hex except the decimal cycle count. Changes marked
+/// with `~` happened before the instruction ran, outside of it.
+#[derive(Debug)]
+pub struct TextTraceSink<W: Write> {
+    writer: W,
+    registers: Vec<String>,
+}
+
+impl<W: Write> TextTraceSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            registers: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + std::fmt::Debug> TraceSink for TextTraceSink<W> {
+    fn begin(&mut self, header: &TraceHeader) -> io::Result<()> {
+        self.registers = header.registers.clone();
+        writeln!(self.writer, "# {} trace", header.architecture)?;
+        let initial: Vec<String> = header
+            .registers
+            .iter()
+            .zip(&header.initial)
+            .map(|(name, value)| format!("{}={:02x}", name, value))
+            .collect();
+        writeln!(self.writer, "# {}", initial.join(" "))
+    }
+
+    fn record(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        writeln!(self.writer, "{}", format_entry(&self.registers, entry))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Formats `entry` as a [`TextTraceSink`] line, naming changes from `registers`
+pub(super) fn format_entry(registers: &[String], entry: &TraceEntry) -> String {
+    let bytes: String = entry
+        .bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let mut line = format!(
+        "{:>12}  {:04x}  {:<8}  {:<24}",
+        entry.cycle, entry.pc, bytes, entry.disassembly
+    );
+    let changes = entry
+        .external
+        .iter()
+        .map(|change| ("~", change))
+        .chain(entry.changes.iter().map(|change| ("", change)));
+    for (marker, change) in changes {
+        let name = registers.get(change.register).map_or("?", String::as_str);
+        line += &format!(
+            " {}{}={:02x}->{:02x}",
+            marker, name, change.before, change.after
+        );
+    }
+    for access in &entry.accesses {
+        let kind = match access.kind {
+            AccessKind::Read => 'r',
+            AccessKind::Write => 'w',
+        };
+        line += &format!(" {}[{:04x}]={:02x}", kind, access.address, access.value);
+    }
+    line.trim_end().to_string()
+}
+
+/// Reads a whole trace written by [`TextTraceSink`]
+///
+/// # Returns
+/// * `Err(error)` - With [`io::ErrorKind::InvalidData`] if the text is not a
+///   trace, naming the first line that could not be parsed
+pub fn read_text_trace(reader: impl BufRead) -> io::Result<(TraceHeader, Vec<TraceEntry>)> {
+    let invalid = |number: usize, message: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: {}", number, message),
+        )
+    };
+    let mut lines = reader.lines();
+    let architecture = lines
+        .next()
+        .transpose()?
+        .and_then(|line| {
+            let name = line.strip_prefix("# ")?.strip_suffix(" trace")?;
+            Some(name.to_string())
+        })
+        .ok_or_else(|| invalid(1, "missing `# <architecture> trace` header"))?;
+
+    let mut header = TraceHeader {
+        architecture,
+        registers: Vec::new(),
+        initial: Vec::new(),
+    };
+    let line = lines.next().transpose()?.unwrap_or_default();
+    let registers = line
+        .strip_prefix('#')
+        .ok_or_else(|| invalid(2, "missing register header"))?;
+    for token in registers.split_whitespace() {
+        let (name, value) = token
+            .split_once('=')
+            .and_then(|(name, value)| Some((name, parse_hex(value)?)))
+            .ok_or_else(|| invalid(2, &format!("invalid register `{}`", token)))?;
+        header.registers.push(name.to_string());
+        header.initial.push(value);
+    }
+
+    let mut entries = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = parse_entry(&header.registers, &line)
+            .ok_or_else(|| invalid(index + 3, "malformed trace entry"))?;
+        entries.push(entry);
+    }
+    Ok((header, entries))
+}
+
+fn parse_hex(text: &str) -> Option<u64> {
+    u64::from_str_radix(text, 16).ok()
+}
+
+/// Parses one entry line, taking trailing change and access tokens from the
+/// end so the disassembly in between may contain spaces
+fn parse_entry(registers: &[String], line: &str) -> Option<TraceEntry> {
+    let mut tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() < 3 {
+        return None;
+    }
+    let mut entry = TraceEntry {
+        cycle: tokens[0].parse().ok()?,
+        pc: parse_hex(tokens[1])?,
+        ..Default::default()
+    };
+    let bytes = tokens[2];
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    entry.bytes = (0..bytes.len())
+        .step_by(2)
+        .map(|start| u8::from_str_radix(bytes.get(start..start + 2)?, 16).ok())
+        .collect::<Option<_>>()?;
+
+    while tokens.len() > 3 {
+        let token = tokens[tokens.len() - 1];
+        if let Some(access) = parse_access(token) {
+            entry.accesses.insert(0, access);
+        } else if let Some(change) = token
+            .strip_prefix('~')
+            .and_then(|token| parse_change(registers, token))
+        {
+            entry.external.insert(0, change);
+        } else if let Some(change) = parse_change(registers, token) {
+            entry.changes.insert(0, change);
+        } else {
+            break;
+        }
+        tokens.pop();
+    }
+    entry.disassembly = tokens[3..].join(" ");
+    Some(entry)
+}
+
+/// Parses `name=before->after`
+fn parse_change(registers: &[String], token: &str) -> Option<RegisterChange> {
+    let (name, values) = token.split_once('=')?;
+    let (before, after) = values.split_once("->")?;
+    Some(RegisterChange {
+        register: registers.iter().position(|register| register == name)?,
+        before: parse_hex(before)?,
+        after: parse_hex(after)?,
+    })
+}
+
+/// Parses `r[address]=value` or `w[address]=value`
+fn parse_access(token: &str) -> Option<TraceAccess> {
+    let kind = match token.get(..2)? {
+        "r[" => AccessKind::Read,
+        "w[" => AccessKind::Write,
+        _ => return None,
+    };
+    let (address, value) = token[2..].split_once("]=")?;
+    Some(TraceAccess {
+        address: parse_hex(address)?,
+        kind,
+        value: parse_hex(value)?,
+    })
+}
@@ -0,0 +1,190 @@
+use tiny_computers::arch::chip_8::{assemble, mnemonic, Chip8Assembler, Chip8Inst, Chip8Listing};
+use tiny_computers::core::isa::InstructionCodec;
+
+/// Assembles `source`, panicking with the error position on failure
+fn rom(source: &str) -> Vec<u8> {
+    assemble(source).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Returns `line:column: message` of the error assembling `source` gives
+fn error(source: &str) -> String {
+    assemble(source).unwrap_err().to_string()
+}
+
+#[test]
+fn every_opcode_round_trips_through_mnemonic() {
+    for word in 0..=0xFFFFu16 {
+        let inst = Chip8Inst::new(word);
+        let long = (inst.size() == 4).then_some(0x1234);
+        let text = mnemonic(inst, long, &|address| format!("{:#05x}", address));
+
+        let mut expected = inst.encode();
+        if let Some(long) = long {
+            expected.extend(long.to_be_bytes());
+        }
+        assert_eq!(rom(&text), expected, "{:04x} `{}`", word, text);
+    }
+}
+
+#[test]
+fn listing_ends_paths_at_words_without_a_statement() {
+    // 0x5001 is no instruction and 0x0123 calls machine code
+    for word in [0x5001u16, 0x0123] {
+        let mut original = rom("v0 := 1");
+        original.extend(word.to_be_bytes());
+        let listing = Chip8Listing::new(&original);
+        let lines = listing.lines();
+        assert!(lines[0].is_code);
+        assert!(!lines[1].is_code, "{:04x}", word);
+    }
+}
+
+#[test]
+fn listing_stops_at_the_end_of_the_address_space() {
+    let listing = Chip8Listing::new(&vec![0xFF; 0x10000]);
+    let last = listing.lines().last().unwrap();
+    assert_eq!(last.address as usize + last.bytes.len(), 0x10000);
+    let listed: usize = listing.lines().iter().map(|line| line.bytes.len()).sum();
+    assert_eq!(listed, 0xFE00);
+}
+
+#[test]
+fn listing_reassembles_to_the_same_rom() {
+    let source = "
+        : main
+            i := sprite
+            v0 := 0
+        : loop
+            sprite v0 v0 3
+            v0 += 8
+            if v0 != 64 then jump loop
+            draw-more
+            i := long main
+            jump main
+        : draw-more
+            v1 := random 0xF
+            return
+        : sprite
+            0x80 0xC0 0xE0
+    ";
+    let original = assemble(source).unwrap();
+    let listing = Chip8Listing::new(&original);
+
+    let mut text = String::new();
+    for line in listing.lines() {
+        if let Some(label) = &line.label {
+            text += &format!(": {}\n", label);
+        }
+        text += &line.text;
+        text.push('\n');
+    }
+    assert_eq!(rom(&text), original);
+}
+
+#[test]
+fn data_directives() {
+    assert_eq!(rom("db 1 0x02 0b11 -1"), [1, 2, 3, 0xFF]);
+    assert_eq!(
+        rom("dw 0x1234 -2 end\n: end"),
+        [0x12, 0x34, 0xFF, 0xFE, 0x02, 0x06]
+    );
+    // Data takes the rest of its line only
+    assert_eq!(rom("db 1 2\nclear"), [1, 2, 0x00, 0xE0]);
+    assert_eq!(rom("0xAB 7"), [0xAB, 7]);
+}
+
+#[test]
+fn names_may_be_used_before_they_are_defined() {
+    assert_eq!(
+        rom("jump main : main v0 := size :const size 3"),
+        [0x12, 0x02, 0x60, 0x03]
+    );
+    assert_eq!(rom(":const a b :const b 5 v0 := a"), [0x60, 0x05]);
+    assert_eq!(rom(":const start main : main i := start"), [0xA2, 0x00]);
+}
+
+#[test]
+fn include_splices_sources() {
+    let assembler = Chip8Assembler::new()
+        .with_source("consts.8o", ":const speed 2")
+        .with_source(
+            "draw.8o",
+            ":include \"consts.8o\"\n: draw v0 += speed return",
+        );
+    assert_eq!(
+        assembler.assemble(":include \"draw.8o\"\ndraw").unwrap(),
+        [0x70, 0x02, 0x00, 0xEE, 0x22, 0x00]
+    );
+}
+
+#[test]
+fn include_errors_name_the_included_file() {
+    let assembler = Chip8Assembler::new()
+        .with_source("bad.8o", "clear\n  jump nowhere")
+        .with_source("loop.8o", ":include \"loop.8o\"");
+    assert_eq!(
+        assembler
+            .assemble(":include \"bad.8o\"")
+            .unwrap_err()
+            .to_string(),
+        "bad.8o:2:8: undefined name `nowhere`"
+    );
+    assert_eq!(
+        assembler
+            .assemble(":include \"loop.8o\"")
+            .unwrap_err()
+            .to_string(),
+        "loop.8o:1:10: recursive include of \"loop.8o\""
+    );
+}
+
+#[test]
+fn error_positions() {
+    let cases = [
+        (
+            "clear\n  :include",
+            "2:3: expected a file name after :include",
+        ),
+        (":include name", "1:10: include file names must be quoted"),
+        (
+            "clear\n  :include \"missing-file.8o\"",
+            "2:3: cannot include \"missing-file.8o\"",
+        ),
+        ("v0 := 256", "1:7: 256 is out of range -128..=255"),
+        ("jump 0x1000", "1:6: 4096 is out of range 0x000..=0xfff"),
+        ("sprite v0 v1 16", "1:14: 16 is out of range 0..=15"),
+        (
+            "i := long 0x10000",
+            "1:11: 65536 is out of range 0x0000..=0xffff",
+        ),
+        ("db 1 256", "1:6: 256 does not fit in a byte"),
+        ("dw 0x10000", "1:4: 65536 does not fit in a word"),
+        ("clear\njump nowhere", "2:6: undefined name `nowhere`"),
+        (
+            ":const a b\n:const b a",
+            "1:10: `b` is defined in terms of itself",
+        ),
+        (":const a nothing", "1:10: undefined name `nothing`"),
+        ("v0 +=", "1:4: unexpected end of input"),
+        ("delay = v0", "1:7: expected `:=`, found `=`"),
+        (
+            "if v0 == 1 jump 0x200",
+            "1:12: expected `then`, found `jump`",
+        ),
+        ("bcd 5", "1:5: expected a register, found `5`"),
+        (": 12", "1:3: `12` is not a valid name"),
+        (": v3", "1:3: `v3` is not a valid name"),
+        (": main\n: main", "2:3: `main` is already defined"),
+        (":org 0x300", "1:1: unknown directive `:org`"),
+        ("v0 *= v1", "1:4: unsupported register operation `*=`"),
+        ("i -= v0", "1:3: unsupported operation on i `-=`"),
+        ("if v0 > 1 then", "1:7: unsupported condition `>`"),
+    ];
+    for (source, expected) in cases {
+        let message = error(source);
+        assert!(message.starts_with(expected), "`{}`: {}", source, message);
+    }
+
+    let too_big = "0 ".repeat(0x10000 - 0x200 + 1);
+    assert!(error(&too_big).ends_with("program does not fit in memory"));
+}
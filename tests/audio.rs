@@ -0,0 +1,102 @@
+use tiny_computers::arch::chip_8::{
+    pattern_rate, write_wav, Chip8, Chip8Audio, Chip8InstructionSet, AMPLITUDE, AUDIO_PATTERN_SIZE,
+    DEFAULT_PITCH,
+};
+
+const HIGH: i16 = AMPLITUDE;
+const LOW: i16 = -AMPLITUDE;
+
+#[test]
+fn silent_while_the_sound_timer_is_zero() {
+    let mut audio = Chip8Audio::new(48_000);
+    audio.render_frame(0, None);
+    let samples = audio.take_samples();
+    assert_eq!(samples.len(), 800);
+    assert!(samples.iter().all(|&sample| sample == 0));
+}
+
+#[test]
+fn beep_is_a_square_wave() {
+    let mut audio = Chip8Audio::new(8_000);
+    audio.set_beep_frequency(1_000.0);
+    audio.render_frame(1, None);
+    let samples = audio.take_samples();
+    assert_eq!(
+        samples[..16],
+        [HIGH, HIGH, HIGH, HIGH, LOW, LOW, LOW, LOW].repeat(2)
+    );
+}
+
+#[test]
+fn frame_lengths_add_up_to_the_sample_rate() {
+    let mut audio = Chip8Audio::new(8_000);
+    let lengths: Vec<usize> = (0..3)
+        .map(|_| {
+            audio.render_frame(0, None);
+            audio.take_samples().len()
+        })
+        .collect();
+    assert_eq!(lengths, [133, 133, 134]);
+}
+
+#[test]
+fn pattern_plays_one_bit_per_sample_at_its_rate() {
+    assert_eq!(pattern_rate(DEFAULT_PITCH), 4000.0);
+    assert_eq!(pattern_rate(DEFAULT_PITCH + 48), 8000.0);
+
+    let mut pattern = [0; AUDIO_PATTERN_SIZE];
+    pattern[0] = 0b1010_0000;
+    let mut audio = Chip8Audio::new(4_000);
+    audio.render_frame(2, Some((&pattern, DEFAULT_PITCH)));
+    audio.render_frame(1, Some((&pattern, DEFAULT_PITCH)));
+    let samples = audio.take_samples();
+    assert_eq!(samples[..5], [HIGH, LOW, HIGH, LOW, LOW]);
+    // The pattern loops after 128 bits
+    assert_eq!(samples[128..131], [HIGH, LOW, HIGH]);
+}
+
+#[test]
+fn ring_buffer_drops_the_oldest_samples() {
+    let mut audio = Chip8Audio::new(120);
+    audio.render_frame(1, None);
+    for _ in 0..60 {
+        audio.render_frame(0, None);
+    }
+    assert_eq!(audio.available(), 120);
+    let mut out = [1; 100];
+    assert_eq!(audio.pop_samples(&mut out), 100);
+    assert!(out.iter().all(|&sample| sample == 0));
+    assert_eq!(audio.available(), 20);
+}
+
+#[test]
+fn xo_chip_machines_play_their_pattern() {
+    let mut chip8 = Chip8::new(Chip8InstructionSet::XOChip);
+    // i := pattern, audio, buzzer := 4, spin; the pattern follows the code
+    let mut rom = vec![
+        0xA2, 0x0C, 0xF0, 0x02, 0x60, 0x04, 0xF0, 0x18, 0x12, 0x08, 0x00, 0x00,
+    ];
+    rom.extend([0xF0, 0x0F]);
+    rom.resize(rom.len() + AUDIO_PATTERN_SIZE - 2, 0);
+    chip8.load_rom(&rom).unwrap();
+    *chip8.audio_mut() = Chip8Audio::new(4_000);
+
+    chip8.run_frame().unwrap();
+    let samples = chip8.audio_mut().take_samples();
+    assert_eq!(samples.len(), 66);
+    assert_eq!(samples[..4], [HIGH; 4]);
+    assert_eq!(samples[4..12], [LOW; 8]);
+    assert_eq!(samples[12..16], [HIGH; 4]);
+}
+
+#[test]
+fn wav_files_hold_the_samples() {
+    let mut wav = Vec::new();
+    write_wav(&mut wav, 8_000, &[HIGH, LOW]).unwrap();
+    assert_eq!(wav.len(), 44 + 4);
+    assert_eq!(&wav[..4], b"RIFF");
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(wav[24..28], 8_000u32.to_le_bytes());
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(wav[44..], [HIGH.to_le_bytes(), LOW.to_le_bytes()].concat());
+}
@@ -0,0 +1,526 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use tiny_computers::arch::chip_8::{
+    Chip8, Chip8Cpu, Chip8Error, Chip8Inst, Chip8InstructionSet, Chip8Quirks, RplFlagStore,
+    BIG_FONT_GLYPH_SIZE, BIG_FONT_OFFSET, FONT_BASE,
+};
+use tiny_computers::arch::Architecture;
+use tiny_computers::core::cpu::CpuError;
+use tiny_computers::core::cpu::{Cpu, CpuState, RegisterFile};
+use tiny_computers::core::isa::InstructionError;
+
+const VF: usize = 0xF;
+
+/// Loads `program` on a CPU with the modern quirks
+fn load(instruction_set: Chip8InstructionSet, program: &[u16]) -> Chip8Cpu {
+    load_with(instruction_set, Chip8Quirks::modern(), program)
+}
+
+fn load_with(
+    instruction_set: Chip8InstructionSet,
+    quirks: Chip8Quirks,
+    program: &[u16],
+) -> Chip8Cpu {
+    let mut cpu = Chip8Cpu::with_quirks(instruction_set, quirks);
+    let rom: Vec<u8> = program.iter().flat_map(|word| word.to_be_bytes()).collect();
+    cpu.load_rom(&rom).unwrap();
+    cpu
+}
+
+fn steps(cpu: &mut Chip8Cpu, count: usize) {
+    for _ in 0..count {
+        cpu.step().unwrap();
+    }
+}
+
+/// Runs every instruction of a straight-line CHIP-8 program
+fn run(program: &[u16]) -> Chip8Cpu {
+    let mut cpu = load(Chip8InstructionSet::Chip8, program);
+    steps(&mut cpu, program.len());
+    cpu
+}
+
+fn v(cpu: &Chip8Cpu, register: usize) -> u8 {
+    cpu.state().register_file().registers()[register]
+}
+
+#[test]
+fn reset_restores_plane_selection() {
+    let mut cpu = Chip8Cpu::new(Chip8InstructionSet::XOChip);
+    // plane 2
+    cpu.load_rom(&[0xF2, 0x01]).unwrap();
+    cpu.step().unwrap();
+    assert_eq!(cpu.state().display().planes(), 0b10);
+
+    cpu.reset().unwrap();
+    assert_eq!(cpu.state().display().planes(), 0b01);
+    assert!(!cpu.state().display().is_hires());
+}
+
+#[test]
+fn long_load_is_four_bytes_only_on_xo_chip() {
+    let mut cpu = load(Chip8InstructionSet::XOChip, &[0xF000, 0x1234]);
+    cpu.step().unwrap();
+    assert_eq!(cpu.state().pc(), 0x204);
+    assert_eq!(cpu.state().register_file().i(), 0x1234);
+
+    for instruction_set in [Chip8InstructionSet::Chip8, Chip8InstructionSet::SuperChip] {
+        let mut cpu = load(instruction_set, &[0xF000, 0x1234]);
+        assert_eq!(
+            cpu.step(),
+            Err(Chip8Error::Instruction(InstructionError::InvalidOpcode))
+        );
+        assert_eq!(cpu.state().pc(), 0x200);
+    }
+}
+
+#[test]
+fn invalid_opcode_leaves_pc_at_instruction() {
+    for word in [0x5001, 0x8008, 0xE000, 0xF0FF, 0xF075, 0x5002] {
+        let mut cpu = load(Chip8InstructionSet::Chip8, &[word]);
+        assert_eq!(
+            cpu.step(),
+            Err(Chip8Error::Instruction(InstructionError::InvalidOpcode)),
+            "{:04X}",
+            word
+        );
+        assert_eq!(cpu.state().pc(), 0x200, "{:04X}", word);
+    }
+}
+
+#[test]
+fn is_valid_agrees_with_execution() {
+    for instruction_set in [
+        Chip8InstructionSet::Chip8,
+        Chip8InstructionSet::SuperChip,
+        Chip8InstructionSet::XOChip,
+    ] {
+        let mut cpu = Chip8Cpu::new(instruction_set);
+        for word in 0..=0xFFFFu16 {
+            cpu.reset().unwrap();
+            cpu.state_mut()
+                .memory_mut()
+                .write_slice(0x200, &word.to_be_bytes())
+                .unwrap();
+            let invalid =
+                cpu.step() == Err(Chip8Error::Instruction(InstructionError::InvalidOpcode));
+            assert_eq!(
+                invalid,
+                !Chip8Inst::new(word).is_valid(instruction_set),
+                "{:04X} on {:?}",
+                word,
+                instruction_set
+            );
+        }
+    }
+}
+
+#[test]
+fn add_sets_vf_to_carry() {
+    let cpu = run(&[0x60FF, 0x6102, 0x8014]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x01, 1));
+
+    let cpu = run(&[0x60FE, 0x6101, 0x6F07, 0x8014]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0xFF, 0));
+}
+
+#[test]
+fn flag_wins_when_vf_is_the_destination() {
+    let cpu = run(&[0x6FFF, 0x6102, 0x8F14]);
+    assert_eq!(v(&cpu, VF), 1);
+
+    let cpu = run(&[0x6F03, 0x6105, 0x8F15]);
+    assert_eq!(v(&cpu, VF), 0);
+}
+
+#[test]
+fn sub_sets_vf_to_not_borrow() {
+    let cpu = run(&[0x6005, 0x6103, 0x8015]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x02, 1));
+
+    let cpu = run(&[0x6003, 0x6105, 0x8015]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0xFE, 0));
+
+    // Equal operands do not borrow
+    let cpu = run(&[0x6004, 0x6104, 0x8015]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x00, 1));
+}
+
+#[test]
+fn subn_sets_vf_to_not_borrow() {
+    let cpu = run(&[0x6003, 0x6105, 0x8017]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x02, 1));
+
+    let cpu = run(&[0x6005, 0x6103, 0x8017]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0xFE, 0));
+}
+
+#[test]
+fn shifts_set_vf_to_shifted_out_bit() {
+    let cpu = run(&[0x6005, 0x8016]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x02, 1));
+
+    let cpu = run(&[0x6004, 0x8016]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x02, 0));
+
+    let cpu = run(&[0x6081, 0x801E]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x02, 1));
+
+    let cpu = run(&[0x6041, 0x801E]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x82, 0));
+}
+
+#[test]
+fn vip_shifts_load_vy() {
+    let program = [0x6001, 0x6181, 0x801E];
+    let mut cpu = load_with(Chip8InstructionSet::Chip8, Chip8Quirks::vip(), &program);
+    steps(&mut cpu, program.len());
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x02, 1));
+}
+
+#[test]
+fn logic_ops_reset_vf_only_with_quirk() {
+    let program = [0x6F07, 0x600C, 0x610A, 0x8011];
+    let cpu = run(&program);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x0E, 0x07));
+
+    let mut cpu = load_with(Chip8InstructionSet::Chip8, Chip8Quirks::vip(), &program);
+    steps(&mut cpu, program.len());
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x0E, 0x00));
+}
+
+#[test]
+fn add_immediate_leaves_vf_alone() {
+    let cpu = run(&[0x6F05, 0x60FF, 0x7002]);
+    assert_eq!((v(&cpu, 0), v(&cpu, VF)), (0x01, 0x05));
+}
+
+#[test]
+fn draw_sets_vf_on_collision() {
+    // I points at the 0xFF byte following the three draws
+    let program = [0xA208, 0xD011, 0xD011, 0xD011, 0xFF00];
+    let mut cpu = load(Chip8InstructionSet::Chip8, &program);
+    steps(&mut cpu, 2);
+    assert_eq!(v(&cpu, VF), 0);
+    assert!(cpu.state().display().pixel(0, 0));
+
+    cpu.step().unwrap();
+    assert_eq!(v(&cpu, VF), 1);
+    assert!(!cpu.state().display().pixel(0, 0));
+
+    cpu.step().unwrap();
+    assert_eq!(v(&cpu, VF), 0);
+}
+
+#[test]
+fn skips_follow_their_condition() {
+    let cases: [(&[u16], bool); 8] = [
+        (&[0x6005, 0x3005], true),
+        (&[0x6005, 0x3006], false),
+        (&[0x6005, 0x4006], true),
+        (&[0x6005, 0x4005], false),
+        (&[0x6005, 0x6105, 0x5010], true),
+        (&[0x6005, 0x6106, 0x5010], false),
+        (&[0x6005, 0x6106, 0x9010], true),
+        (&[0x6005, 0x6105, 0x9010], false),
+    ];
+    for (program, taken) in cases {
+        let cpu = run(program);
+        let next = 0x200 + 2 * program.len() as u16;
+        assert_eq!(
+            cpu.state().pc(),
+            if taken { next + 2 } else { next },
+            "{:04X?}",
+            program
+        );
+    }
+}
+
+#[test]
+fn key_skips_follow_keypad() {
+    for (pressed, skp, sknp) in [(true, 0x206, 0x204), (false, 0x204, 0x206)] {
+        let mut cpu = load(Chip8InstructionSet::Chip8, &[0x6007, 0xE09E]);
+        if pressed {
+            cpu.state_mut().keypad_mut().press(7).unwrap();
+        }
+        steps(&mut cpu, 2);
+        assert_eq!(cpu.state().pc(), skp);
+
+        let mut cpu = load(Chip8InstructionSet::Chip8, &[0x6007, 0xE0A1]);
+        if pressed {
+            cpu.state_mut().keypad_mut().press(7).unwrap();
+        }
+        steps(&mut cpu, 2);
+        assert_eq!(cpu.state().pc(), sknp);
+    }
+}
+
+#[test]
+fn xo_chip_skips_over_long_load() {
+    let mut cpu = load(Chip8InstructionSet::XOChip, &[0x3000, 0xF000, 0x1234]);
+    cpu.step().unwrap();
+    assert_eq!(cpu.state().pc(), 0x206);
+
+    // Elsewhere F000 is a single invalid word
+    let mut cpu = load(Chip8InstructionSet::SuperChip, &[0x3000, 0xF000, 0x1234]);
+    cpu.step().unwrap();
+    assert_eq!(cpu.state().pc(), 0x204);
+}
+
+#[test]
+fn call_and_return() {
+    let mut cpu = load(Chip8InstructionSet::Chip8, &[0x2204, 0x0000, 0x00EE]);
+    cpu.step().unwrap();
+    assert_eq!(cpu.state().pc(), 0x204);
+    assert_eq!(cpu.state().stack(), [0x202]);
+
+    cpu.step().unwrap();
+    assert_eq!(cpu.state().pc(), 0x202);
+    assert!(cpu.state().stack().is_empty());
+}
+
+#[test]
+fn bcd_and_register_dump() {
+    let program = [0x609C, 0xA300, 0xF033, 0xF265];
+    let mut cpu = load_with(Chip8InstructionSet::Chip8, Chip8Quirks::vip(), &program);
+    steps(&mut cpu, program.len());
+    assert_eq!((v(&cpu, 0), v(&cpu, 1), v(&cpu, 2)), (1, 5, 6));
+    assert_eq!(cpu.state().register_file().i(), 0x303);
+}
+
+#[test]
+fn key_wait_completes_on_release() {
+    // F30A then a marker instruction
+    let mut cpu = load(Chip8InstructionSet::Chip8, &[0xF30A, 0x6001]);
+    steps(&mut cpu, 3);
+    assert_eq!(cpu.state().pc(), 0x200);
+    assert!(cpu.state().keypad().is_waiting());
+
+    cpu.state_mut().keypad_mut().press(0xB).unwrap();
+    cpu.step().unwrap();
+    assert_eq!(
+        cpu.state().pc(),
+        0x200,
+        "a press alone does not end the wait"
+    );
+
+    cpu.state_mut().keypad_mut().release(0xB).unwrap();
+    cpu.step().unwrap();
+    assert_eq!(cpu.state().pc(), 0x202);
+    assert_eq!(v(&cpu, 3), 0xB);
+    assert!(!cpu.state().keypad().is_waiting());
+}
+
+#[test]
+fn key_wait_ignores_keys_held_before_it() {
+    let mut cpu = load(Chip8InstructionSet::Chip8, &[0xF30A]);
+    cpu.state_mut().keypad_mut().press(0x4).unwrap();
+    cpu.step().unwrap();
+    cpu.state_mut().keypad_mut().release(0x4).unwrap();
+    cpu.step().unwrap();
+    assert_eq!(cpu.state().pc(), 0x200);
+
+    cpu.state_mut().keypad_mut().press(0x2).unwrap();
+    cpu.state_mut().keypad_mut().release(0x2).unwrap();
+    cpu.step().unwrap();
+    assert_eq!((cpu.state().pc(), v(&cpu, 3)), (0x202, 0x2));
+}
+
+#[test]
+fn keys_outside_the_keypad_are_rejected() {
+    let mut cpu = Chip8Cpu::new(Chip8InstructionSet::Chip8);
+    let keypad = cpu.state_mut().keypad_mut();
+    assert_eq!(keypad.press(0x10), Err(Chip8Error::InvalidKey(0x10)));
+    assert_eq!(keypad.release(0xFF), Err(Chip8Error::InvalidKey(0xFF)));
+    assert_eq!(keypad.keys(), 0);
+}
+
+/// Runs `program` under `quirks` on the SUPER-CHIP instruction set, which has
+/// none of the quirks built in
+fn run_with(quirks: Chip8Quirks, program: &[u16]) -> Chip8Cpu {
+    let mut cpu = load_with(Chip8InstructionSet::SuperChip, quirks, program);
+    steps(&mut cpu, program.len());
+    cpu
+}
+
+/// Works out the quirks a machine configured with `quirks` shows by running a
+/// probe program for each
+fn observe(quirks: Chip8Quirks) -> Chip8Quirks {
+    let vf_reset = v(&run_with(quirks, &[0x6F07, 0x600C, 0x610A, 0x8011]), VF) == 0;
+    let memory_increments_i = run_with(quirks, &[0xA300, 0xF265])
+        .state()
+        .register_file()
+        .i()
+        == 0x303;
+    // Draw 0xC0 at the right edge; the sprite byte follows the code
+    let clip_sprites = !run_with(quirks, &[0x603F, 0x6100, 0xA208, 0xD011, 0xC000])
+        .state()
+        .display()
+        .pixel(0, 0);
+    let shift_in_place = v(&run_with(quirks, &[0x6004, 0x6110, 0x8016]), 0) == 0x02;
+    let jump_uses_vx = run_with(quirks, &[0x6000, 0x6204, 0xB210]).state().pc() == 0x214;
+
+    let mut chip8 = Chip8::with_quirks(Chip8InstructionSet::SuperChip, quirks);
+    chip8
+        .load_rom(&[0xD0, 0x01, 0xD0, 0x01, 0x12, 0x04])
+        .unwrap();
+    chip8.run_frame().unwrap();
+    let display_wait = chip8.state().pc() == 0x202;
+
+    Chip8Quirks {
+        vf_reset,
+        memory_increments_i,
+        display_wait,
+        clip_sprites,
+        shift_in_place,
+        jump_uses_vx,
+    }
+}
+
+#[test]
+fn quirk_presets_behave_as_configured() {
+    for quirks in [
+        Chip8Quirks::vip(),
+        Chip8Quirks::schip(),
+        Chip8Quirks::xochip(),
+        Chip8Quirks::modern(),
+    ] {
+        assert_eq!(observe(quirks), quirks);
+    }
+}
+
+#[test]
+fn each_quirk_toggles_independently() {
+    let none = Chip8Quirks {
+        vf_reset: false,
+        memory_increments_i: false,
+        display_wait: false,
+        clip_sprites: false,
+        shift_in_place: false,
+        jump_uses_vx: false,
+    };
+    assert_eq!(observe(none), none);
+    let toggles: [fn(&mut Chip8Quirks) -> &mut bool; 6] = [
+        |quirks| &mut quirks.vf_reset,
+        |quirks| &mut quirks.memory_increments_i,
+        |quirks| &mut quirks.display_wait,
+        |quirks| &mut quirks.clip_sprites,
+        |quirks| &mut quirks.shift_in_place,
+        |quirks| &mut quirks.jump_uses_vx,
+    ];
+    for toggle in toggles {
+        let mut quirks = none;
+        *toggle(&mut quirks) = true;
+        assert_eq!(observe(quirks), quirks);
+    }
+}
+
+#[test]
+fn instruction_sets_pick_their_preset() {
+    for (instruction_set, quirks) in [
+        (Chip8InstructionSet::Chip8, Chip8Quirks::vip()),
+        (Chip8InstructionSet::SuperChip, Chip8Quirks::schip()),
+        (Chip8InstructionSet::XOChip, Chip8Quirks::xochip()),
+    ] {
+        assert_eq!(*Chip8::new(instruction_set).quirks(), quirks);
+    }
+}
+
+#[test]
+fn schip_switches_resolution() {
+    let mut cpu = load(Chip8InstructionSet::SuperChip, &[0x00FF, 0x00FE]);
+    cpu.step().unwrap();
+    assert!(cpu.state().display().is_hires());
+    assert_eq!(cpu.state().display().width(), 128);
+    cpu.step().unwrap();
+    assert!(!cpu.state().display().is_hires());
+
+    // Plain CHIP-8 treats them as machine code calls
+    let mut cpu = load(Chip8InstructionSet::Chip8, &[0x00FF]);
+    cpu.step().unwrap();
+    assert!(!cpu.state().display().is_hires());
+}
+
+#[test]
+fn schip_draws_large_sprites_with_dxy0() {
+    // I points at the 16x16 sprite after the code: a full top row
+    let mut program = vec![0x00FF, 0xA20C, 0x6000, 0xD000, 0xD000, 0x0000, 0xFFFF];
+    program.resize(program.len() + 15, 0);
+    let mut cpu = load(Chip8InstructionSet::SuperChip, &program);
+    steps(&mut cpu, 4);
+    let display = cpu.state().display();
+    assert!((0..16).all(|x| display.pixel(x, 0)));
+    assert!(!display.pixel(16, 0) && !display.pixel(0, 1));
+    assert_eq!(v(&cpu, VF), 0);
+
+    cpu.step().unwrap();
+    assert_eq!(v(&cpu, VF), 1);
+    assert!(!cpu.state().display().pixel(0, 0));
+}
+
+#[test]
+fn schip_scrolls() {
+    // Draw the top row of font glyph 0, then scroll it around
+    let program = [0x6000, 0xF029, 0xD001, 0x00C2, 0x00FB, 0x00FC, 0x00FB];
+    let mut cpu = load(Chip8InstructionSet::SuperChip, &program);
+    steps(&mut cpu, 4);
+    assert!(cpu.state().display().pixel(0, 2));
+    cpu.step().unwrap();
+    assert!(cpu.state().display().pixel(4, 2));
+    cpu.step().unwrap();
+    assert!(cpu.state().display().pixel(0, 2));
+    cpu.step().unwrap();
+    assert!(!cpu.state().display().pixel(0, 2));
+    assert!(cpu.state().display().pixel(4, 2));
+}
+
+#[test]
+fn schip_big_font_follows_the_small_one() {
+    let mut cpu = load(Chip8InstructionSet::SuperChip, &[0x6007, 0xF030]);
+    steps(&mut cpu, 2);
+    assert_eq!(
+        cpu.state().register_file().i(),
+        FONT_BASE + BIG_FONT_OFFSET + 7 * BIG_FONT_GLYPH_SIZE
+    );
+}
+
+#[test]
+fn schip_exit_halts() {
+    let mut cpu = load(Chip8InstructionSet::SuperChip, &[0x00FD, 0x6001]);
+    cpu.step().unwrap();
+    assert!(cpu.state().is_halted());
+    assert_eq!(cpu.step(), Err(Chip8Error::Cpu(CpuError::Halted)));
+}
+
+/// RPL flags shared between machines, as a file would be
+#[derive(Debug, Clone, Default)]
+struct SharedFlags(Rc<RefCell<Vec<u8>>>);
+
+impl RplFlagStore for SharedFlags {
+    fn load(&mut self, flags: &mut [u8]) {
+        for (flag, &stored) in flags.iter_mut().zip(self.0.borrow().iter()) {
+            *flag = stored;
+        }
+    }
+
+    fn save(&mut self, flags: &[u8]) {
+        *self.0.borrow_mut() = flags.to_vec();
+    }
+}
+
+#[test]
+fn schip_rpl_flags_outlive_the_machine() {
+    let store = SharedFlags::default();
+    let mut cpu = load(
+        Chip8InstructionSet::SuperChip,
+        &[0x6011, 0x6122, 0x6233, 0xF175],
+    );
+    cpu.set_rpl_flag_store(Box::new(store.clone()));
+    steps(&mut cpu, 4);
+    assert_eq!(*store.0.borrow(), [0x11, 0x22]);
+
+    let mut cpu = load(Chip8InstructionSet::SuperChip, &[0xF285]);
+    cpu.set_rpl_flag_store(Box::new(store));
+    cpu.step().unwrap();
+    assert_eq!((v(&cpu, 0), v(&cpu, 1), v(&cpu, 2)), (0x11, 0x22, 0));
+}
@@ -0,0 +1,143 @@
+use tiny_computers::arch::chip_8::{assemble, Chip8Cpu, Chip8InstructionSet};
+use tiny_computers::core::cpu::{Cpu, RegisterFile};
+use tiny_computers::core::debug::{
+    AccessKind, Comparison, Condition, Debugger, MemoryAccess, StopReason, WatchKind,
+};
+
+/// Debugs `source` assembled and loaded at 0x200
+fn debugger(source: &str) -> Debugger<Chip8Cpu> {
+    let mut cpu = Chip8Cpu::new(Chip8InstructionSet::Chip8);
+    cpu.load_rom(&assemble(source).unwrap()).unwrap();
+    Debugger::new(cpu)
+}
+
+fn pc(debugger: &Debugger<Chip8Cpu>) -> u16 {
+    debugger.cpu().state().pc()
+}
+
+fn v(debugger: &Debugger<Chip8Cpu>, register: usize) -> u8 {
+    debugger.cpu().state().register_file().registers()[register]
+}
+
+/// Counts v0 up forever
+const COUNTER: &str = ": loop v0 += 1 jump loop";
+
+/// Calls `sub` twice; `sub` calls `leaf`
+const CALLS: &str = "
+    sub
+    sub
+    : end jump end
+    : sub
+        v0 += 1
+        leaf
+        v0 += 1
+        return
+    : leaf
+        v1 += 1
+        return
+";
+
+#[test]
+fn breakpoints_stop_before_the_instruction() {
+    let mut debugger = debugger(COUNTER);
+    let breakpoint = debugger.add_breakpoint(0x202);
+    assert_eq!(debugger.run(), Ok(StopReason::Breakpoint(breakpoint)));
+    assert_eq!((pc(&debugger), v(&debugger, 0)), (0x202, 1));
+
+    // Resuming from a breakpoint makes progress
+    assert_eq!(debugger.run(), Ok(StopReason::Breakpoint(breakpoint)));
+    assert_eq!(v(&debugger, 0), 2);
+
+    assert!(debugger.set_breakpoint_enabled(breakpoint, false));
+    assert_eq!(debugger.run_for(10), Ok(StopReason::StepLimit));
+    assert!(debugger.remove_breakpoint(breakpoint));
+    assert!(!debugger.remove_breakpoint(breakpoint));
+}
+
+#[test]
+fn conditional_breakpoints_wait_for_their_register() {
+    let mut debugger = debugger(COUNTER);
+    let breakpoint = debugger
+        .add_conditional_breakpoint(0x200, Condition::new(0, Comparison::GreaterOrEqual, 5));
+    assert_eq!(debugger.run(), Ok(StopReason::Breakpoint(breakpoint)));
+    assert_eq!((pc(&debugger), v(&debugger, 0)), (0x200, 5));
+}
+
+#[test]
+fn watchpoints_report_the_access() {
+    let mut debugger = debugger(
+        "
+        v0 := 7
+        i := 0x300
+        load v0
+        i := 0x300
+        save v0
+        : end jump end
+    ",
+    );
+    let writes = debugger.add_watchpoint(0x300..=0x30F, WatchKind::Write);
+    assert_eq!(
+        debugger.run(),
+        Ok(StopReason::Watchpoint(
+            writes,
+            MemoryAccess {
+                address: 0x300,
+                kind: AccessKind::Write
+            }
+        ))
+    );
+    assert_eq!(pc(&debugger), 0x20A);
+
+    debugger.cpu_mut().reset().unwrap();
+    debugger.remove_watchpoint(writes);
+    let reads = debugger.add_watchpoint(0x300..=0x300, WatchKind::Read);
+    assert_eq!(
+        debugger.run(),
+        Ok(StopReason::Watchpoint(
+            reads,
+            MemoryAccess {
+                address: 0x300,
+                kind: AccessKind::Read
+            }
+        ))
+    );
+    assert_eq!(pc(&debugger), 0x206);
+}
+
+#[test]
+fn step_executes_one_instruction() {
+    let mut debugger = debugger(COUNTER);
+    assert_eq!(debugger.step(), Ok(StopReason::Completed));
+    assert_eq!((pc(&debugger), v(&debugger, 0)), (0x202, 1));
+}
+
+#[test]
+fn step_over_runs_the_whole_call() {
+    let mut debugger = debugger(CALLS);
+    assert_eq!(debugger.step_over(), Ok(StopReason::Completed));
+    assert_eq!(pc(&debugger), 0x202);
+    assert_eq!(v(&debugger, 0), 2);
+    assert_eq!(v(&debugger, 1), 1);
+
+    // Breakpoints inside the call still stop it
+    let breakpoint = debugger.add_breakpoint(0x20E);
+    assert_eq!(debugger.step_over(), Ok(StopReason::Breakpoint(breakpoint)));
+}
+
+#[test]
+fn step_out_returns_to_the_caller() {
+    let mut debugger = debugger(CALLS);
+    debugger.run_to(0x20A).unwrap();
+    assert_eq!(debugger.cpu().state().stack().len(), 1);
+    assert_eq!(debugger.step_out(), Ok(StopReason::Completed));
+    assert_eq!(pc(&debugger), 0x202);
+    assert!(debugger.cpu().state().stack().is_empty());
+}
+
+#[test]
+fn run_to_stops_at_the_address() {
+    let mut debugger = debugger(CALLS);
+    assert_eq!(debugger.run_to(0x20E), Ok(StopReason::Completed));
+    assert_eq!(pc(&debugger), 0x20E);
+    assert_eq!(debugger.cpu().state().stack().len(), 2);
+}
@@ -0,0 +1,145 @@
+use tiny_computers::arch::chip_8::{
+    Chip8Display, DISPLAY_HEIGHT, DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT, HIRES_DISPLAY_WIDTH,
+};
+
+/// Returns the lit pixels as `(x, y)`, row by row
+fn lit(display: &Chip8Display) -> Vec<(usize, usize)> {
+    display
+        .pixels()
+        .filter(|&(_, _, lit)| lit)
+        .map(|(x, y, _)| (x, y))
+        .collect()
+}
+
+#[test]
+fn sprites_xor_and_report_collisions() {
+    let mut display = Chip8Display::new();
+    assert!(!display.draw_sprite(0, 0, &[0b1100_0000], false));
+    assert_eq!(lit(&display), [(0, 0), (1, 0)]);
+
+    // Overlapping one lit pixel turns it off and collides
+    assert!(display.draw_sprite(1, 0, &[0b1100_0000], false));
+    assert_eq!(lit(&display), [(0, 0), (2, 0)]);
+
+    // Lighting unlit pixels only does not
+    assert!(!display.draw_sprite(0, 1, &[0b1000_0000], false));
+    assert_eq!(lit(&display), [(0, 0), (2, 0), (0, 1)]);
+}
+
+#[test]
+fn drawing_twice_erases_the_sprite() {
+    let mut display = Chip8Display::new();
+    let sprite = [0xF0, 0x90, 0xF0];
+    assert!(!display.draw_sprite(10, 5, &sprite, false));
+    assert_eq!(lit(&display).len(), 10);
+    assert!(display.draw_sprite(10, 5, &sprite, false));
+    assert!(lit(&display).is_empty());
+}
+
+#[test]
+fn sprites_clip_or_wrap_at_the_edges() {
+    let x = DISPLAY_WIDTH - 1;
+    let y = DISPLAY_HEIGHT - 1;
+
+    let mut clipped = Chip8Display::new();
+    clipped.draw_sprite(x, y, &[0xC0, 0xC0], false);
+    assert_eq!(lit(&clipped), [(x, y)]);
+
+    let mut wrapped = Chip8Display::new();
+    wrapped.draw_sprite(x, y, &[0xC0, 0xC0], true);
+    assert_eq!(lit(&wrapped), [(0, 0), (x, 0), (0, y), (x, y)]);
+}
+
+#[test]
+fn sprite_origin_always_wraps() {
+    let mut display = Chip8Display::new();
+    display.draw_sprite(DISPLAY_WIDTH + 2, DISPLAY_HEIGHT + 3, &[0x80], false);
+    assert_eq!(lit(&display), [(2, 3)]);
+}
+
+#[test]
+fn drawing_marks_the_display_dirty() {
+    let mut display = Chip8Display::new();
+    assert!(display.is_dirty());
+    display.mark_clean();
+    assert!(!display.is_dirty());
+
+    display.draw_sprite(0, 0, &[0x80], false);
+    assert!(display.is_dirty());
+    display.mark_clean();
+    display.clear();
+    assert!(display.is_dirty());
+    assert!(lit(&display).is_empty());
+}
+
+#[test]
+fn rows_and_ascii_follow_the_pixels() {
+    let mut display = Chip8Display::new();
+    display.draw_sprite(1, 1, &[0x80], false);
+    let rows: Vec<&[u8]> = display.rows().collect();
+    assert_eq!(rows.len(), DISPLAY_HEIGHT);
+    assert!(rows.iter().all(|row| row.len() == DISPLAY_WIDTH));
+    assert_eq!(rows[1][1], 1);
+
+    let ascii = display.to_ascii();
+    let line = ascii.lines().nth(1).unwrap();
+    assert!(line.starts_with(".#."));
+    assert!(display.pixel(1, 1));
+    assert!(!display.pixel(DISPLAY_WIDTH, 1));
+}
+
+#[test]
+fn switching_resolution_resizes_and_clears() {
+    let mut display = Chip8Display::new();
+    display.draw_sprite(0, 0, &[0x80], false);
+    display.set_hires(true);
+    assert!(display.is_hires());
+    assert_eq!(
+        (display.width(), display.height()),
+        (HIRES_DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT)
+    );
+    assert!(lit(&display).is_empty());
+
+    display.draw_sprite(100, 60, &[0x80], false);
+    assert_eq!(lit(&display), [(100, 60)]);
+    display.set_hires(false);
+    assert_eq!(
+        (display.width(), display.height()),
+        (DISPLAY_WIDTH, DISPLAY_HEIGHT)
+    );
+    assert!(lit(&display).is_empty());
+}
+
+#[test]
+fn large_sprites_are_sixteen_pixels_square() {
+    let mut display = Chip8Display::new();
+    display.set_hires(true);
+    let mut sprite = [0u8; 32];
+    sprite[0] = 0x80;
+    sprite[1] = 0x01;
+    sprite[30] = 0x80;
+    assert!(!display.draw_large_sprite(8, 4, &sprite, false));
+    assert_eq!(lit(&display), [(8, 4), (23, 4), (8, 19)]);
+    assert!(display.draw_large_sprite(8, 4, &sprite, false));
+    assert!(lit(&display).is_empty());
+}
+
+#[test]
+fn scrolling_moves_pixels_and_blanks_what_scrolls_in() {
+    let mut display = Chip8Display::new();
+    display.draw_sprite(4, 0, &[0x80], false);
+
+    display.scroll_down(3);
+    assert_eq!(lit(&display), [(4, 3)]);
+    display.scroll_up(1);
+    assert_eq!(lit(&display), [(4, 2)]);
+    display.scroll_right(4);
+    assert_eq!(lit(&display), [(8, 2)]);
+    display.scroll_left(4);
+    assert_eq!(lit(&display), [(4, 2)]);
+
+    // Pixels scrolled off the edge are gone for good
+    display.scroll_left(5);
+    display.scroll_right(5);
+    assert!(lit(&display).is_empty());
+}
@@ -0,0 +1,131 @@
+#![cfg(unix)]
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::Duration;
+
+use tiny_computers::arch::chip_8::{assemble, Chip8, Chip8InstructionSet};
+use tiny_computers::arch::gdb::GdbStub;
+
+/// A scripted RSP client on the other end of a socket
+struct Client {
+    stream: UnixStream,
+}
+
+impl Client {
+    fn send_raw(&mut self, data: &str) {
+        let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(self.stream, "${}#{:02x}", data, checksum).unwrap();
+    }
+
+    /// Reads the next reply packet, skipping acknowledgements, and acks it
+    fn reply(&mut self) -> String {
+        let mut byte = [0];
+        loop {
+            self.stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'$' {
+                break;
+            }
+            assert_eq!(byte[0], b'+');
+        }
+        let mut data = Vec::new();
+        loop {
+            self.stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'#' {
+                break;
+            }
+            data.push(byte[0]);
+        }
+        let mut checksum = [0; 2];
+        self.stream.read_exact(&mut checksum).unwrap();
+        let sum = data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        assert_eq!(
+            std::str::from_utf8(&checksum).unwrap(),
+            format!("{:02x}", sum)
+        );
+        self.stream.write_all(b"+").unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+    fn send(&mut self, data: &str) -> String {
+        self.send_raw(data);
+        self.reply()
+    }
+}
+
+/// Counts up in v0 from 0x204 on; 0x208 exits
+const PROGRAM: &str = "
+    v0 := 1
+    v1 := 2
+: count
+    v0 += 1
+    jump count
+    exit
+";
+
+/// `g` reply of 21 registers: V0-VF, I, PC, SP, DT, ST
+fn registers(v: [u8; 16], pc: u16) -> String {
+    let mut reply: String = v.iter().map(|byte| format!("{:02x}", byte)).collect();
+    reply += "0000";
+    reply += &format!("{:02x}{:02x}", pc as u8, pc >> 8);
+    reply + "000000"
+}
+
+fn script(stream: UnixStream) {
+    let mut client = Client { stream };
+
+    assert_eq!(client.send("?"), "S05");
+    assert_eq!(client.send("g"), registers([0; 16], 0x200));
+    let mut v = [0; 16];
+    v[5] = 0x55;
+    assert_eq!(client.send(&format!("G{}", registers(v, 0x200))), "OK");
+    assert_eq!(client.send("p5"), "55");
+
+    assert_eq!(client.send("m200,4"), "60016102");
+    assert_eq!(client.send("M300,2:abcd"), "OK");
+    assert_eq!(client.send("m300,2"), "abcd");
+    assert_eq!(client.send("m0,ffffffffffffffff"), "E01");
+    assert_eq!(client.send("m0,2001"), "E01");
+    assert!(client
+        .send("qXfer:features:read:target.xml:10,ffffffffffffffff")
+        .starts_with('l'));
+
+    assert_eq!(client.send("s"), "S05");
+    assert_eq!(client.send("p11"), "0202");
+
+    assert_eq!(client.send("Z0,206,2"), "OK");
+    assert_eq!(client.send("c"), "S05");
+    assert_eq!(client.send("p11"), "0602");
+    assert_eq!(client.send("z0,206,2"), "OK");
+
+    // A packet sent while running must not be lost to the interrupt check
+    client.send_raw("c");
+    thread::sleep(Duration::from_millis(20));
+    client.send_raw("?");
+    client.stream.write_all(b"\x03").unwrap();
+    assert_eq!(client.reply(), "S02");
+    assert_eq!(client.reply(), "S02");
+
+    assert_eq!(client.send("P11=0802"), "OK");
+    assert_eq!(client.send("s"), "S05");
+    assert_eq!(client.send("s"), "W00");
+    assert_eq!(client.send("?"), "W00");
+
+    client.send_raw("k");
+    let mut ack = [0];
+    client.stream.read_exact(&mut ack).unwrap();
+    assert_eq!(&ack, b"+");
+}
+
+#[test]
+fn scripted_session() {
+    let mut chip8 = Chip8::new(Chip8InstructionSet::SuperChip);
+    chip8.load_rom(&assemble(PROGRAM).unwrap()).unwrap();
+
+    let (stub_end, client_end) = UnixStream::pair().unwrap();
+    let client = thread::spawn(move || script(client_end));
+    let served = GdbStub::new(&mut chip8).serve(stub_end);
+    client.join().unwrap();
+    served.unwrap();
+}
@@ -0,0 +1,173 @@
+use std::fmt::Debug;
+use tiny_computers::arch::chip_8::{
+    Chip8, Chip8Error, Chip8InstructionSet, BIG_FONT, BIG_FONT_OFFSET,
+    DEFAULT_INSTRUCTIONS_PER_FRAME, FONT, FONT_BASE, FONT_GLYPH_SIZE, MEMORY_SIZE, PROGRAM_START,
+};
+use tiny_computers::arch::Architecture;
+use tiny_computers::core::isa::InstructionSet;
+use tiny_computers::core::memory::MemoryError;
+
+/// Builds a CHIP-8 machine running `program`
+fn machine(program: &[u16]) -> Chip8 {
+    let mut chip8 = Chip8::new(Chip8InstructionSet::Chip8);
+    let rom: Vec<u8> = program.iter().flat_map(|word| word.to_be_bytes()).collect();
+    chip8.load_rom(&rom).unwrap();
+    chip8
+}
+
+/// DT := 5, ST := 2, then spin
+const TIMERS: [u16; 5] = [0x6005, 0xF015, 0x6002, 0xF018, 0x1208];
+
+fn timers(chip8: &Chip8) -> (u8, u8) {
+    let registers = chip8.state().register_file();
+    (registers.delay_timer(), registers.sound_timer())
+}
+
+#[test]
+fn frames_run_their_instructions_then_tick_once() {
+    let mut chip8 = machine(&TIMERS);
+    assert_eq!(chip8.run_frame(), Ok(DEFAULT_INSTRUCTIONS_PER_FRAME));
+    assert_eq!(chip8.timers().frames(), 1);
+    assert_eq!(timers(&chip8), (4, 1));
+
+    chip8.run_frame().unwrap();
+    assert_eq!(timers(&chip8), (3, 0));
+    for _ in 0..5 {
+        chip8.run_frame().unwrap();
+    }
+    assert_eq!(timers(&chip8), (0, 0), "timers stop at zero");
+    assert_eq!(chip8.timers().frames(), 7);
+}
+
+#[test]
+fn instruction_rate_is_configurable() {
+    let mut chip8 = machine(&TIMERS);
+    chip8.timers_mut().set_instructions_per_frame(2);
+    assert_eq!(chip8.run_frame(), Ok(2));
+    assert_eq!(chip8.state().pc(), 0x204);
+    // DT was set during the frame and ticked at its end
+    assert_eq!(timers(&chip8), (4, 0));
+}
+
+#[test]
+fn failed_frames_do_not_tick() {
+    let mut chip8 = machine(&[0x6005, 0xF015, 0xFFFF]);
+    assert!(chip8.run_frame().is_err());
+    assert_eq!(chip8.timers().frames(), 0);
+    assert_eq!(timers(&chip8), (5, 0));
+}
+
+#[test]
+fn fonts_are_installed_at_the_default_base() {
+    let chip8 = machine(&[]);
+    let memory = chip8.memory();
+    assert_eq!(memory.read_slice(FONT_BASE, FONT.len()), Ok(&FONT[..]));
+    assert_eq!(
+        memory.read_slice(FONT_BASE + BIG_FONT_OFFSET, BIG_FONT.len()),
+        Ok(&BIG_FONT[..])
+    );
+}
+
+#[test]
+fn hex_digits_point_into_the_installed_font() {
+    let mut chip8 = machine(&[0x600A, 0xF029]);
+    chip8.install_font(0x000).unwrap();
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().register_file().i(), 10 * FONT_GLYPH_SIZE);
+    assert_eq!(chip8.memory().read_slice(0x000, FONT.len()), Ok(&FONT[..]));
+}
+
+#[test]
+fn fonts_must_stay_below_the_program() {
+    let mut chip8 = machine(&[]);
+    assert_eq!(
+        chip8.install_font(PROGRAM_START - 0x10),
+        Err(Chip8Error::Memory(MemoryError::InvalidAddressRange))
+    );
+    assert_eq!(chip8.cpu().font_base(), FONT_BASE);
+}
+
+#[test]
+fn roms_must_fit_in_memory() {
+    let capacity = MEMORY_SIZE - PROGRAM_START as usize;
+    let mut chip8 = Chip8::new(Chip8InstructionSet::Chip8);
+    assert_eq!(
+        chip8.load_rom(&vec![0; capacity + 1]),
+        Err(Chip8Error::RomTooLarge {
+            size: capacity + 1,
+            capacity
+        })
+    );
+    chip8.load_rom(&vec![0xAA; capacity]).unwrap();
+    assert_eq!(chip8.memory().read_slice(0xFFF, 1), Ok(&[0xAA][..]));
+}
+
+#[test]
+fn loading_a_rom_clears_the_previous_one() {
+    let mut chip8 = machine(&[0x1234, 0x5678]);
+    chip8.run_frame().ok();
+    chip8.load_rom(&[0x12, 0x00]).unwrap();
+    assert_eq!(
+        chip8.memory().read_slice(PROGRAM_START, 4),
+        Ok(&[0x12, 0x00, 0x00, 0x00][..])
+    );
+    assert_eq!(chip8.state().pc(), PROGRAM_START);
+    assert_eq!(chip8.timers().frames(), 0);
+}
+
+/// Steps any architecture `count` times, as a generic runner would
+fn step_generic<A>(arch: &mut A, count: u32) -> u32
+where
+    A: Architecture,
+    A::Error: Debug,
+{
+    (0..count).map(|_| arch.step().unwrap() as u32).sum()
+}
+
+#[test]
+fn generic_stepping_keeps_timers_at_frame_rate() {
+    let mut chip8 = machine(&TIMERS);
+    assert_eq!(
+        step_generic(&mut chip8, DEFAULT_INSTRUCTIONS_PER_FRAME - 1),
+        DEFAULT_INSTRUCTIONS_PER_FRAME - 1
+    );
+    assert_eq!(timers(&chip8), (5, 2));
+    assert_eq!(chip8.timers().frames(), 0);
+
+    step_generic(&mut chip8, 1);
+    assert_eq!(timers(&chip8), (4, 1));
+    assert_eq!(chip8.timers().frames(), 1);
+
+    step_generic(&mut chip8, 2 * DEFAULT_INSTRUCTIONS_PER_FRAME);
+    assert_eq!(timers(&chip8), (2, 0));
+    assert_eq!(chip8.timers().frames(), 3);
+}
+
+#[test]
+fn architecture_reports_the_dialect() {
+    for (instruction_set, name) in [
+        (Chip8InstructionSet::Chip8, "CHIP-8"),
+        (Chip8InstructionSet::SuperChip, "SUPER-CHIP"),
+        (Chip8InstructionSet::XOChip, "XO-CHIP"),
+    ] {
+        let chip8 = Chip8::new(instruction_set);
+        assert_eq!(chip8.name(), name);
+        assert_eq!(*chip8.instruction_set(), instruction_set);
+        assert_eq!(chip8.instruction_set().name(), name);
+    }
+}
+
+#[test]
+fn architecture_reset_keeps_the_rom() {
+    let mut chip8 = machine(&TIMERS);
+    step_generic(&mut chip8, 2 * DEFAULT_INSTRUCTIONS_PER_FRAME);
+    Architecture::reset(&mut chip8).unwrap();
+    assert_eq!(chip8.state().pc(), PROGRAM_START);
+    assert_eq!(timers(&chip8), (0, 0));
+    assert_eq!(chip8.timers().frames(), 0);
+    assert_eq!(
+        chip8.memory().read_slice(PROGRAM_START, 2),
+        Ok(&[0x60, 0x05][..])
+    );
+}
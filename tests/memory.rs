@@ -0,0 +1,233 @@
+use tiny_computers::core::memory::{
+    Addressing, MemoryBus, MemoryDevice, MemoryError, MemoryMapper, Ram, Rom,
+};
+
+type Mapper = MemoryMapper<u16, u8, MemoryError>;
+
+#[test]
+fn ram_reset_restores_fill_pattern() {
+    let mut ram = Ram::<u16, u8>::with_fill(0x10, 0xA5);
+    assert_eq!(ram.as_slice(), [0xA5; 0x10]);
+    ram.write(0x3, 0x00).unwrap();
+    ram.load(0x8, &[1, 2, 3]).unwrap();
+    ram.reset();
+    assert_eq!(ram.as_slice(), [0xA5; 0x10]);
+}
+
+#[test]
+fn ram_load_must_fit() {
+    let mut ram = Ram::<u16, u8>::new(0x10);
+    assert_eq!(
+        ram.load(0xE, &[1, 2, 3]),
+        Err(MemoryError::AddressOutOfBounds)
+    );
+    assert_eq!(
+        ram.load(usize::MAX, &[1]),
+        Err(MemoryError::AddressOutOfBounds)
+    );
+    assert_eq!(ram.as_slice(), [0; 0x10]);
+
+    ram.load(0xD, &[1, 2, 3]).unwrap();
+    assert_eq!(ram.as_slice()[0xD..], [1, 2, 3]);
+}
+
+#[test]
+fn ram_answers_from_its_base() {
+    let mut ram = Ram::<u16, u8>::new(0x10).with_base(0x200);
+    ram.write(0x200, 0x42).unwrap();
+    assert_eq!(ram.as_slice()[0], 0x42);
+    assert_eq!(ram.read(0x1FF), Err(MemoryError::AddressOutOfBounds));
+    assert_eq!(ram.read(0x210), Err(MemoryError::AddressOutOfBounds));
+}
+
+#[test]
+fn rom_rejects_writes() {
+    let mut rom = Rom::<u16, u8>::from_slice(&[1, 2, 3, 4]);
+    assert_eq!(rom.write(0x1, 0xFF), Err(MemoryError::ReadOnlyMemory));
+    assert_eq!(rom.read(0x1), Ok(2));
+}
+
+#[test]
+fn rom_keeps_contents_across_reset() {
+    let mut rom = Rom::<u16, u8>::with_fill(0x4, 0xFF);
+    rom.load(0x1, &[1, 2]).unwrap();
+    rom.reset();
+    assert_eq!(rom.as_slice(), [0xFF, 1, 2, 0xFF]);
+}
+
+#[test]
+fn mirrored_device_repeats_across_range() {
+    let mut mapper = Mapper::new();
+    mapper
+        .attach_mirrored(0x2000, 0x23FF, 0xFF, false, Box::new(Ram::new(0x100)))
+        .unwrap();
+    mapper.write(0x2001, 0x42).unwrap();
+    assert_eq!(mapper.read(0x2301), Ok(0x42));
+}
+
+#[test]
+fn mirror_mask_must_fit_the_device() {
+    for mask in [0x1FF, 0xFFF] {
+        let mut mapper = Mapper::new();
+        assert_eq!(
+            mapper.attach_mirrored(0x2000, 0x23FF, mask, false, Box::new(Ram::new(0x100))),
+            Err(MemoryError::InvalidMirrorMask),
+            "{:#x}",
+            mask
+        );
+        assert_eq!(mapper.read(0x2000), Err(MemoryError::AddressOutOfBounds));
+    }
+}
+
+#[test]
+fn mirror_mask_must_be_one_less_than_a_power_of_two() {
+    for mask in [0x100, 0xF0, 0xFE] {
+        let mut mapper = Mapper::new();
+        assert_eq!(
+            mapper.attach_device_with(
+                0x2000,
+                0x23FF,
+                false,
+                Addressing::Mirrored { mask },
+                Box::new(Ram::new(0x200)),
+            ),
+            Err(MemoryError::InvalidMirrorMask),
+            "{:#x}",
+            mask
+        );
+    }
+}
+
+#[test]
+fn mirror_mask_may_decode_part_of_the_device() {
+    let mut mapper = Mapper::new();
+    mapper
+        .attach_mirrored(0x2000, 0x23FF, 0x7F, false, Box::new(Ram::new(0x100)))
+        .unwrap();
+    mapper.write(0x2080, 0x42).unwrap();
+    assert_eq!(mapper.read(0x2000), Ok(0x42));
+}
+
+/// Three 0x100-word RAMs back to back from 0x100, each filled with its
+/// number, attached out of order
+fn adjacent() -> Mapper {
+    let mut mapper = Mapper::new();
+    for (start, fill) in [(0x300, 3), (0x100, 1), (0x200, 2)] {
+        mapper
+            .attach_device_with(
+                start,
+                start + 0xFF,
+                false,
+                Addressing::Relative,
+                Box::new(Ram::with_fill(0x100, fill)),
+            )
+            .unwrap();
+    }
+    mapper
+}
+
+#[test]
+fn lookup_finds_adjacent_devices_attached_out_of_order() {
+    let mapper = adjacent();
+    for (address, device) in [
+        (0x100, 1),
+        (0x1FF, 1),
+        (0x200, 2),
+        (0x2FF, 2),
+        (0x300, 3),
+        (0x3FF, 3),
+    ] {
+        assert_eq!(mapper.read(address), Ok(device), "{:#x}", address);
+    }
+}
+
+#[test]
+fn lookup_moves_on_after_a_cache_hit() {
+    let mut mapper = adjacent();
+    assert_eq!(mapper.read(0x150), Ok(1));
+    assert_eq!(mapper.read(0x151), Ok(1));
+    assert_eq!(mapper.read(0x350), Ok(3));
+    mapper.write(0x250, 0x22).unwrap();
+    assert_eq!(mapper.read(0x250), Ok(0x22));
+    assert_eq!(mapper.read(0x150), Ok(1));
+    assert_eq!(mapper.read(0x350), Ok(3));
+}
+
+#[test]
+fn lookup_misses_unmapped_addresses() {
+    let mut mapper = adjacent();
+    assert!(mapper.remove_device(0x200).is_some());
+    assert_eq!(mapper.read(0x1FF), Ok(1));
+    for address in [0x000, 0x0FF, 0x200, 0x2FF, 0x400, 0xFFFF] {
+        assert_eq!(
+            mapper.read(address),
+            Err(MemoryError::AddressOutOfBounds),
+            "{:#x}",
+            address
+        );
+    }
+    assert_eq!(mapper.read(0x300), Ok(3));
+}
+
+#[test]
+fn lookup_forgets_removed_devices() {
+    let mut mapper = adjacent();
+    assert_eq!(mapper.read(0x350), Ok(3));
+    assert!(mapper.remove_device(0x300).is_some());
+    assert_eq!(mapper.read(0x350), Err(MemoryError::AddressOutOfBounds));
+    assert_eq!(mapper.read(0x250), Ok(2));
+}
+
+#[test]
+fn overlapping_ranges_are_rejected() {
+    let mut mapper = adjacent();
+    for (start, end) in [
+        (0x080, 0x100),
+        (0x180, 0x27F),
+        (0x3FF, 0x4FF),
+        (0x000, 0xFFFF),
+    ] {
+        assert_eq!(
+            mapper.attach_device(start, end, false, Box::new(Ram::new(0x100))),
+            Err(MemoryError::DeviceAlreadyAttached),
+            "{:#x}-{:#x}",
+            start,
+            end
+        );
+    }
+    assert_eq!(mapper.device_count(), 3);
+    mapper
+        .attach_device(
+            0x400,
+            0x4FF,
+            false,
+            Box::new(Ram::new(0x100).with_base(0x400)),
+        )
+        .unwrap();
+    assert_eq!(mapper.read(0x3FF), Ok(3));
+    assert_eq!(mapper.read(0x400), Ok(0));
+}
+
+#[test]
+fn relative_devices_read_their_own_offsets_at_any_base() {
+    let mut mapper = MemoryMapper::<u32, u8, MemoryError>::new();
+    for base in [0x1000, 0x8000] {
+        let contents: Vec<u8> = (0..0x10).map(|offset| (base >> 8) as u8 + offset).collect();
+        mapper
+            .attach_device_with(
+                base,
+                base + 0xF,
+                false,
+                Addressing::Relative,
+                Box::new(Ram::from_slice(&contents)),
+            )
+            .unwrap();
+    }
+    for offset in [0x0, 0x7, 0xF] {
+        assert_eq!(mapper.read(0x1000 + offset), Ok(0x10 + offset as u8));
+        assert_eq!(mapper.read(0x8000 + offset), Ok(0x80 + offset as u8));
+    }
+    mapper.write(0x8003, 0xAA).unwrap();
+    assert_eq!(mapper.read(0x1003), Ok(0x13));
+    assert_eq!(mapper.read(0x8003), Ok(0xAA));
+}
@@ -0,0 +1,83 @@
+use tiny_computers::arch::chip_8::{assemble, Chip8, Chip8Error, Chip8InstructionSet};
+use tiny_computers::core::movie::{InputEvent, Movie, MovieError};
+use tiny_computers::core::savestate::RewindBuffer;
+
+/// Waits for a key and adds it to v1, drawing v1's digit each time
+const PROGRAM: &str = "
+: main
+    v0 := key
+    v1 += v0
+    i := hex v1
+    clear
+    sprite v2 v2 5
+    jump main
+";
+
+fn machine() -> Chip8 {
+    let mut chip8 = Chip8::new(Chip8InstructionSet::Chip8);
+    chip8.load_rom(&assemble(PROGRAM).unwrap()).unwrap();
+    chip8
+}
+
+#[test]
+fn recorded_session_replays_to_the_same_hash() {
+    let mut chip8 = machine();
+    chip8.run_frame().unwrap();
+    let mut recorder = chip8.start_recording().unwrap();
+    for frame in 0..120u16 {
+        let key = frame / 10 % 16;
+        match frame % 10 {
+            0 => chip8
+                .record_input(InputEvent::Press(key), &mut recorder)
+                .unwrap(),
+            3 => chip8
+                .record_input(InputEvent::Release(key), &mut recorder)
+                .unwrap(),
+            _ => {}
+        }
+        chip8.run_frame().unwrap();
+    }
+    // End mid-frame so replay has to finish instruction by instruction
+    chip8
+        .record_input(InputEvent::Press(7), &mut recorder)
+        .unwrap();
+    let movie = chip8.finish_recording(recorder).unwrap();
+    let final_hash = chip8.save_state().unwrap().hash();
+    assert_eq!(movie.final_hash(), final_hash);
+
+    let movie = Movie::from_bytes(&movie.to_bytes()).unwrap();
+    assert_eq!(movie.inputs().len(), 25);
+
+    let mut replay = machine();
+    assert!(replay.replay(&movie).unwrap());
+    assert_eq!(replay.save_state().unwrap().hash(), final_hash);
+}
+
+#[test]
+fn recording_after_rewind_is_an_error() {
+    let mut chip8 = machine();
+    let mut rewind = RewindBuffer::new(1, 16);
+    let mut recorder = chip8.start_recording().unwrap();
+    for _ in 0..4 {
+        chip8.run_frame().unwrap();
+        chip8.record_rewind(&mut rewind).unwrap();
+    }
+    chip8
+        .record_input(InputEvent::Press(1), &mut recorder)
+        .unwrap();
+    let recorded = chip8.input_time();
+
+    assert!(chip8.rewind_frame(&mut rewind).unwrap());
+    let error = chip8
+        .record_input(InputEvent::Press(2), &mut recorder)
+        .unwrap_err();
+    assert_eq!(
+        error,
+        Chip8Error::Movie(MovieError::OutOfOrder {
+            last: recorded,
+            time: chip8.input_time(),
+        })
+    );
+    assert_eq!(recorder.inputs().len(), 1);
+    assert!(!chip8.keypad().is_pressed(2));
+}
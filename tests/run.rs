@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Writes `program` as a ROM file named after `name` in the temp directory
+fn rom(name: &str, program: &[u16]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("tiny-run-{}-{}.ch8", std::process::id(), name));
+    let bytes: Vec<u8> = program.iter().flat_map(|word| word.to_be_bytes()).collect();
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn tiny_run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tiny-run"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// v0 := 0x42, save it at 0x300, draw glyph 0 at (0, 0), spin
+const PROGRAM: [u16; 6] = [0x6042, 0xA300, 0xF055, 0xF129, 0xD115, 0x120A];
+
+#[test]
+fn runs_until_the_program_spins() {
+    let path = rom("spin", &PROGRAM);
+    let output = tiny_run(&[
+        "--until-loop",
+        "--dump-memory",
+        "0x300:2",
+        path.to_str().unwrap(),
+    ]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "arch: CHIP-8");
+    assert_eq!(
+        lines[1],
+        "stopped: jump to self at 0x20a after 5 instructions, 0 frames"
+    );
+    assert!(lines[3].starts_with("  v0=42 "));
+    assert!(stdout.contains("memory 0x0300+0x2:\n  0300: 42 00\n"));
+    assert!(stdout.contains("display 64x32:\n####....."));
+}
+
+#[test]
+fn cycle_limit_and_pbm_output() {
+    let path = rom("pbm", &PROGRAM);
+    let output = tiny_run(&["--cycles", "3", "--display", "pbm", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("stopped: cycle limit after 3 instructions"));
+    assert!(stdout.contains("P1\n64 32\n0 0 0"));
+}
+
+#[test]
+fn machine_errors_exit_with_failure() {
+    let path = rom("invalid", &[0x6001, 0xFFFF]);
+    let output = tiny_run(&[path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("stopped: error: "));
+    assert!(stdout.contains("after 1 instructions"));
+}
+
+#[test]
+fn usage_errors_exit_with_two() {
+    assert_eq!(
+        tiny_run(&["--arch", "z80", "rom.ch8"]).status.code(),
+        Some(2)
+    );
+    assert_eq!(tiny_run(&[]).status.code(), Some(2));
+    let help = tiny_run(&["--help"]);
+    assert!(help.status.success());
+    assert!(String::from_utf8(help.stdout)
+        .unwrap()
+        .starts_with("Usage: tiny-run"));
+}
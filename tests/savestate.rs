@@ -0,0 +1,185 @@
+use tiny_computers::arch::chip_8::{Chip8, Chip8InstructionSet};
+use tiny_computers::core::memory::{MemoryBus, MemoryDevice, MemoryError, MemoryMapper, Ram, Rom};
+use tiny_computers::core::savestate::{
+    RewindBuffer, SaveState, SaveStateError, Snapshot, StateReader, StateWriter,
+};
+
+type Mapper = MemoryMapper<u16, u8, MemoryError>;
+
+/// ROM at 0x0000, RAM at 0x1000 and RAM mirrored every 0x100 from 0x2000
+fn mapper() -> Mapper {
+    let mut mapper = Mapper::new();
+    let rom = Rom::from_slice(&[0xC3, 0x00, 0x10]);
+    mapper
+        .attach_device(0x0000, 0x0002, true, Box::new(rom))
+        .unwrap();
+    let ram = Ram::new(0x100).with_base(0x1000);
+    mapper
+        .attach_device(0x1000, 0x10FF, false, Box::new(ram))
+        .unwrap();
+    mapper
+        .attach_mirrored(0x2000, 0x23FF, 0xFF, false, Box::new(Ram::new(0x100)))
+        .unwrap();
+    mapper
+}
+
+#[test]
+fn mapper_with_rom_restores() {
+    let mut mapper = mapper();
+    mapper.write(0x1000, 0x11).unwrap();
+    mapper.write(0x2001, 0x22).unwrap();
+    let state = SaveState::capture("test", "mapper", &mapper).unwrap();
+
+    mapper.write(0x1000, 0x33).unwrap();
+    mapper.write(0x2301, 0x44).unwrap();
+    state.restore("test", "mapper", &mut mapper).unwrap();
+
+    assert_eq!(mapper.read(0x0000), Ok(0xC3));
+    assert_eq!(mapper.read(0x1000), Ok(0x11));
+    assert_eq!(mapper.read(0x2001), Ok(0x22));
+    assert_eq!(mapper.read(0x2101), Ok(0x22));
+}
+
+#[test]
+fn mapper_restore_checks_layout() {
+    let mapper = mapper();
+    let state = SaveState::capture("test", "mapper", &mapper).unwrap();
+
+    let mut other = Mapper::new();
+    other
+        .attach_device(0x0000, 0x0002, true, Box::new(Ram::new(3)))
+        .unwrap();
+    assert!(matches!(
+        state.restore("test", "mapper", &mut other),
+        Err(SaveStateError::Device(_))
+    ));
+}
+
+/// A target that can be saved but never restored
+#[derive(Debug)]
+struct Unrestorable;
+
+impl Snapshot for Unrestorable {
+    fn write_state(&self, _writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        Ok(())
+    }
+
+    fn read_state(&mut self, _reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        Err(SaveStateError::Device("stuck".to_string()))
+    }
+}
+
+#[test]
+fn failed_rollback_is_an_error() {
+    let state = SaveState::capture("test", "stuck", &Unrestorable).unwrap();
+    let error = state
+        .restore("test", "stuck", &mut Unrestorable)
+        .unwrap_err();
+    assert_eq!(
+        error,
+        SaveStateError::Rollback {
+            error: Box::new(SaveStateError::Device("stuck".to_string())),
+            rollback: Box::new(SaveStateError::Device("stuck".to_string())),
+        }
+    );
+}
+
+/// A target whose state is just its bytes, only ever captured
+#[derive(Debug)]
+struct Blob(Vec<u8>);
+
+impl Snapshot for Blob {
+    fn write_state(&self, writer: &mut StateWriter) -> Result<(), SaveStateError> {
+        writer.write_raw(&self.0);
+        Ok(())
+    }
+
+    fn read_state(&mut self, _reader: &mut StateReader<'_>) -> Result<(), SaveStateError> {
+        Ok(())
+    }
+}
+
+fn blob(bytes: &[u8]) -> SaveState {
+    SaveState::capture("test", "blob", &Blob(bytes.to_vec())).unwrap()
+}
+
+#[test]
+fn rewind_rebuilds_every_recorded_state() {
+    // Sparse changes, long zero runs, growing and shrinking payloads
+    let states: Vec<SaveState> = [
+        vec![0; 64],
+        vec![1, 2, 3],
+        [vec![0; 40], vec![7; 10], vec![0; 40]].concat(),
+        [vec![0; 40], vec![7; 9], vec![1], vec![0; 40]].concat(),
+        vec![],
+        (0..=255).collect(),
+    ]
+    .iter()
+    .map(|bytes| blob(bytes))
+    .collect();
+
+    let mut rewind = RewindBuffer::new(1, 16);
+    for (frame, state) in states.iter().enumerate() {
+        rewind.push(frame as u64, state.clone()).unwrap();
+    }
+    assert_eq!(rewind.len(), states.len());
+    for (frame, state) in states.iter().enumerate().rev() {
+        assert_eq!(rewind.pop(), Ok(Some((frame as u64, state.clone()))));
+    }
+    assert_eq!(rewind.pop(), Ok(None));
+}
+
+#[test]
+fn rewind_drops_the_oldest_states_beyond_capacity() {
+    let mut rewind = RewindBuffer::new(2, 3);
+    assert!(rewind.is_due(4) && !rewind.is_due(5));
+    for frame in (0..10).step_by(2) {
+        rewind.push(frame, blob(&[frame as u8; 8])).unwrap();
+    }
+    assert_eq!(rewind.len(), 3);
+    assert_eq!(rewind.oldest_frame(), Some(4));
+    assert_eq!(rewind.rewind_to(3), Ok(None));
+    assert!(rewind.is_empty());
+}
+
+#[test]
+fn rewind_to_finds_the_closest_older_state() {
+    let mut rewind = RewindBuffer::new(1, 16);
+    for frame in [0, 5, 10] {
+        rewind.push(frame, blob(&[frame as u8])).unwrap();
+    }
+    assert_eq!(rewind.rewind_to(7), Ok(Some((5, &blob(&[5])))));
+    assert_eq!(rewind.len(), 2);
+
+    // Recording at an older frame starts a new timeline
+    rewind.push(3, blob(&[3])).unwrap();
+    assert_eq!(rewind.latest(), Some((3, &blob(&[3]))));
+    assert_eq!(rewind.pop(), Ok(Some((3, blob(&[3])))));
+    assert_eq!(rewind.pop(), Ok(Some((0, blob(&[0])))));
+}
+
+#[test]
+fn machine_rewinds_to_the_previous_frame() {
+    let mut chip8 = Chip8::new(Chip8InstructionSet::Chip8);
+    // Count v0 up, store it and draw something different every frame
+    chip8
+        .load_rom(&[
+            0x70, 0x01, 0xA3, 0x00, 0xF0, 0x55, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x00,
+        ])
+        .unwrap();
+    let mut rewind = RewindBuffer::new(1, 16);
+    let mut states = vec![chip8.save_state().unwrap()];
+    chip8.record_rewind(&mut rewind).unwrap();
+    for _ in 0..5 {
+        chip8.run_frame().unwrap();
+        chip8.record_rewind(&mut rewind).unwrap();
+        states.push(chip8.save_state().unwrap());
+    }
+
+    for frame in (0..5).rev() {
+        assert!(chip8.rewind_frame(&mut rewind).unwrap());
+        assert_eq!(chip8.timers().frames(), frame as u64);
+        assert_eq!(chip8.save_state().unwrap(), states[frame]);
+    }
+    assert!(!chip8.rewind_frame(&mut rewind).unwrap());
+}
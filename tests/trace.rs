@@ -0,0 +1,225 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use tiny_computers::arch::chip_8::{assemble, Chip8, Chip8InstructionSet};
+use tiny_computers::arch::Architecture;
+use tiny_computers::core::cpu::RegisterFile;
+use tiny_computers::core::trace::{
+    first_divergence, read_binary_trace, read_reference_log, read_text_trace, read_trace,
+    register_snapshots, trace_steps, BinaryTraceSink, Difference, Divergence, Location,
+    RingBufferSink, TextTraceSink, TraceEntry, TraceHeader, TraceSink, TraceStep,
+};
+
+/// Starts the delay timer at 30 and polls it in a loop
+const TIMER_LOOP: &str = "
+    v0 := 30
+    delay := v0
+: poll
+    v1 := delay
+    jump poll
+";
+
+/// Index of `dt` among the CHIP-8 trace registers
+const DT: usize = 18;
+
+fn timer_machine() -> Chip8 {
+    let mut chip8 = Chip8::new(Chip8InstructionSet::Chip8);
+    chip8.load_rom(&assemble(TIMER_LOOP).unwrap()).unwrap();
+    chip8
+}
+
+/// Runs the timer loop for 5 frames and one more instruction under `sink`
+fn run_traced<S: TraceSink + 'static>(sink: S) -> (Chip8, S) {
+    let mut chip8 = timer_machine();
+    let sink = Rc::new(RefCell::new(sink));
+    chip8.set_trace_sink(Some(Box::new(sink.clone())));
+    for _ in 0..5 {
+        chip8.run_frame().unwrap();
+    }
+    chip8.step().unwrap();
+    chip8.set_trace_sink(None);
+    let sink = Rc::try_unwrap(sink).unwrap().into_inner();
+    (chip8, sink)
+}
+
+/// Rebuilds the register values after the last entry from the header and
+/// the changes alone
+fn replay(header: &TraceHeader, entries: &[TraceEntry]) -> Vec<u64> {
+    let mut values = header.initial.clone();
+    for entry in entries {
+        for change in entry.external.iter().chain(&entry.changes) {
+            assert_eq!(values[change.register], change.before);
+            values[change.register] = change.after;
+        }
+    }
+    values
+}
+
+fn machine_registers(chip8: &Chip8) -> Vec<u64> {
+    let registers = chip8.state().register_file();
+    let mut values: Vec<u64> = registers.registers().iter().map(|&v| v as u64).collect();
+    values.extend([
+        registers.i() as u64,
+        registers.stack_pointer() as u64,
+        registers.delay_timer() as u64,
+        registers.sound_timer() as u64,
+    ]);
+    values
+}
+
+#[test]
+fn binary_trace_follows_running_timer() {
+    let (chip8, sink) = run_traced(BinaryTraceSink::new(Vec::new()));
+    let (header, entries) = read_binary_trace(&sink.into_inner()[..]).unwrap();
+
+    let values = replay(&header, &entries);
+    assert_eq!(chip8.state().register_file().delay_timer(), 25);
+    assert_eq!(values[DT], 25);
+    assert_eq!(values, machine_registers(&chip8));
+}
+
+#[test]
+fn text_trace_follows_running_timer() {
+    let (chip8, sink) = run_traced(TextTraceSink::new(Vec::new()));
+    let text = String::from_utf8(sink.into_inner()).unwrap();
+    assert!(text.contains("~dt=1e->1d"));
+
+    let (header, entries) = read_text_trace(text.as_bytes()).unwrap();
+    assert_eq!(replay(&header, &entries), machine_registers(&chip8));
+}
+
+#[test]
+fn external_changes_are_reported_once() {
+    let (_, sink) = run_traced(BinaryTraceSink::new(Vec::new()));
+    let (_, entries) = read_binary_trace(&sink.into_inner()[..]).unwrap();
+
+    let ticks: Vec<_> = entries
+        .iter()
+        .flat_map(|entry| &entry.external)
+        .map(|change| (change.register, change.before, change.after))
+        .collect();
+    assert_eq!(
+        ticks,
+        [
+            (DT, 30, 29),
+            (DT, 29, 28),
+            (DT, 28, 27),
+            (DT, 27, 26),
+            (DT, 26, 25)
+        ]
+    );
+}
+
+#[test]
+fn binary_trace_follows_loaded_state() {
+    let mut chip8 = timer_machine();
+    chip8.run_frame().unwrap();
+    let saved = chip8.save_state().unwrap();
+
+    let sink = Rc::new(RefCell::new(BinaryTraceSink::new(Vec::new())));
+    chip8.set_trace_sink(Some(Box::new(sink.clone())));
+    for _ in 0..3 {
+        chip8.run_frame().unwrap();
+    }
+    chip8.load_state(&saved).unwrap();
+    chip8.step().unwrap();
+    chip8.set_trace_sink(None);
+
+    let data = Rc::try_unwrap(sink).unwrap().into_inner().into_inner();
+    let (header, entries) = read_binary_trace(&data[..]).unwrap();
+    assert_eq!(replay(&header, &entries), machine_registers(&chip8));
+}
+
+/// The timer loop at 3 instructions per frame as another emulator logs it,
+/// with the delay timer ticking after steps 2 and 5
+const TIMER_LOOP_LOG: &str = "
+# PC    V0  V1  DT   opcode
+PC:0200 V0:00 V1:00 DT:00  601e
+PC:0202 V0:1E V1:00 DT:00  f015
+PC:0204 V0:1E V1:00 DT:1E  f107
+PC:0206 V0:1E V1:1E DT:1D  1204
+PC:0204 V0:1E V1:1E DT:1D  f107
+PC:0206 V0:1E V1:1D DT:1D  1204
+PC:0204 V0:1E V1:1D DT:1C  f107
+PC:0206 V0:1E V1:1C DT:1C  1204
+PC:0204 V0:1E V1:1C DT:1C  f107
+";
+
+/// Traces the timer loop at 3 instructions per frame as text
+fn timer_loop_steps() -> Vec<TraceStep> {
+    let mut chip8 = timer_machine();
+    chip8.timers_mut().set_instructions_per_frame(3);
+    let sink = Rc::new(RefCell::new(TextTraceSink::new(Vec::new())));
+    chip8.set_trace_sink(Some(Box::new(sink.clone())));
+    for _ in 0..3 {
+        chip8.run_frame().unwrap();
+    }
+    chip8.set_trace_sink(None);
+
+    let text = Rc::try_unwrap(sink).unwrap().into_inner().into_inner();
+    let (header, entries) = read_trace(&text[..]).unwrap();
+    trace_steps(&header, &entries)
+}
+
+#[test]
+fn trace_agrees_with_reference_across_timer_ticks() {
+    let trace = timer_loop_steps();
+    let reference = read_reference_log(TIMER_LOOP_LOG.as_bytes()).unwrap();
+    assert_eq!(reference.len(), 9);
+    assert_eq!(trace.len(), 9);
+    assert_eq!(first_divergence(&trace, &reference), None);
+}
+
+#[test]
+fn shorter_trace_diverges_where_it_ends() {
+    let trace = timer_loop_steps();
+    let reference = read_reference_log(TIMER_LOOP_LOG.as_bytes()).unwrap();
+    let end = |expected: u64, found: u64| {
+        Some(Divergence {
+            step: 6,
+            differences: vec![Difference {
+                location: Location::End,
+                expected,
+                found: Some(found),
+            }],
+        })
+    };
+    assert_eq!(first_divergence(&trace[..6], &reference), end(9, 6));
+    assert_eq!(first_divergence(&trace, &reference[..6]), end(6, 9));
+}
+
+#[test]
+fn missing_timer_tick_diverges_where_it_was_due() {
+    let log = TIMER_LOOP_LOG.replace("PC:0206 V0:1E V1:1E DT:1D", "PC:0206 V0:1E V1:1E DT:1E");
+    let reference = read_reference_log(log.as_bytes()).unwrap();
+    assert_eq!(
+        first_divergence(&timer_loop_steps(), &reference),
+        Some(Divergence {
+            step: 3,
+            differences: vec![Difference {
+                location: Location::Register("DT".to_string()),
+                expected: 0x1e,
+                found: Some(0x1d),
+            }],
+        })
+    );
+}
+
+#[test]
+fn register_snapshots_hold_values_before_each_step() {
+    let mut chip8 = timer_machine();
+    chip8.timers_mut().set_instructions_per_frame(3);
+    let sink = Rc::new(RefCell::new(RingBufferSink::new(16)));
+    chip8.set_trace_sink(Some(Box::new(sink.clone())));
+    for _ in 0..2 {
+        chip8.run_frame().unwrap();
+    }
+
+    let sink = sink.borrow();
+    let entries: Vec<_> = sink.entries().iter().cloned().collect();
+    let delay: Vec<_> = register_snapshots(sink.header().unwrap(), &entries)
+        .iter()
+        .map(|values| values[DT])
+        .collect();
+    assert_eq!(delay, [0x00, 0x00, 0x1e, 0x1d, 0x1d, 0x1d]);
+}